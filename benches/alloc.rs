@@ -0,0 +1,28 @@
+//! Measures the throughput of small allocations that don't trigger a collection.
+
+use std::time::{Duration, Instant};
+
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+const ROUNDS: usize = 20;
+const ALLOCATIONS: usize = 1 << 20;
+
+fn run() -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let mut gc = GCAlloc::new(128 << 20);
+        let start = Instant::now();
+        for _ in 0..ALLOCATIONS {
+            std::hint::black_box(Cons::alloc(&mut gc, None, None).unwrap());
+        }
+        total += start.elapsed();
+        assert_eq!(gc.metadata().gc_count, 0);
+    }
+    total / (ROUNDS * ALLOCATIONS) as u32
+}
+
+fn main() {
+    // Warm up the allocator and page tables.
+    run();
+    println!("{:?} per allocation", run());
+}
@@ -0,0 +1,31 @@
+//! Compares collections with separate mark, copy and rewrite passes and with a Cheney scan.
+
+use std::time::{Duration, Instant};
+
+use ike_gc::{fixtures::GcTree, GCAlloc, GCConfig};
+
+const ROUNDS: usize = 50;
+
+fn run(separate_passes: bool) -> Duration {
+    let config = GCConfig {
+        separate_passes,
+        ..Default::default()
+    };
+    let mut gc = GCAlloc::with_config(64 << 20, config);
+    let tree = GcTree::alloc_complete(&mut gc, 18, 0).unwrap();
+    let tree = gc.acquire_handle(tree);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        gc.collect();
+    }
+    let elapsed = start.elapsed();
+    gc.release_handle(tree);
+    elapsed / ROUNDS as u32
+}
+
+fn main() {
+    // Warm up the allocator and page tables.
+    run(false);
+    println!("three passes: {:?} per collection", run(true));
+    println!("Cheney scan:  {:?} per collection", run(false));
+}
@@ -0,0 +1,34 @@
+//! Compares collections with mark bits in the object headers and in a side bitmap.
+//!
+//! Only wall-clock time is reported. For cache misses, run the benchmark under a profiler, e.g.
+//! `perf stat -e cache-misses cargo bench --bench mark_bits`.
+
+use std::time::{Duration, Instant};
+
+use ike_gc::{fixtures::GcTree, GCAlloc, GCConfig};
+
+const ROUNDS: usize = 50;
+
+fn run(side_mark_bits: bool) -> Duration {
+    let config = GCConfig {
+        side_mark_bits,
+        ..Default::default()
+    };
+    let mut gc = GCAlloc::with_config(64 << 20, config);
+    let tree = GcTree::alloc_complete(&mut gc, 18, 0).unwrap();
+    let tree = gc.acquire_handle(tree);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        gc.collect();
+    }
+    let elapsed = start.elapsed();
+    gc.release_handle(tree);
+    elapsed / ROUNDS as u32
+}
+
+fn main() {
+    // Warm up the allocator and page tables.
+    run(false);
+    println!("header mark bits: {:?} per collection", run(false));
+    println!("side mark bits:   {:?} per collection", run(true));
+}
@@ -0,0 +1,334 @@
+//! `#[derive(Trace)]` for `ike-gc`.
+//!
+//! The derive walks the fields of a struct and generates the `visit_fields` method of
+//! `ike_gc::Trace`, which in turn provides the vtable:
+//!
+//! - `Gc<_>` and `Option<Gc<_>>` fields are visited.
+//! - Fields annotated with `#[trace]` are visited through their own `Trace` impl.
+//! - Every other field is assumed to hold no pointers and is skipped.
+//!
+//! Types are matched by name, so a pointer field must be spelled `Gc<..>` or `Option<Gc<..>>`
+//! (optionally with a leading path), not through a type alias. Any other field type that
+//! mentions `Gc`, such as `Vec<Gc<_>>` or `[Gc<_>; 2]`, is rejected rather than skipped, as its
+//! pointers would never be rewritten. Wrap such fields in a type that implements `Trace` and
+//! annotate them with `#[trace]`.
+//!
+//! ```
+//! use ike_gc::{gc_ptr::Gc, Trace};
+//!
+//! #[derive(Trace)]
+//! struct Node {
+//!     value: usize,
+//!     next: Option<Gc<Node>>,
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! use ike_gc::{gc_ptr::Gc, Trace};
+//!
+//! #[derive(Trace)]
+//! struct Node {
+//!     value: usize,
+//!     children: Vec<Gc<Node>>,
+//! }
+//! ```
+//!
+//! This crate parses the token stream by hand to stay free of dependencies.
+
+use proc_macro::{Delimiter, Group, Spacing, TokenStream, TokenTree};
+
+#[proc_macro_derive(Trace, attributes(trace))]
+pub fn derive_trace(input: TokenStream) -> TokenStream {
+    match parse_struct(input) {
+        Ok(s) => s.expand().parse().expect("generated invalid tokens"),
+        Err(msg) => format!("compile_error!({:?});", msg).parse().unwrap(),
+    }
+}
+
+/// How a field takes part in tracing.
+enum FieldKind {
+    /// `Gc<_>`
+    Gc,
+    /// `Option<Gc<_>>`
+    OptionGc,
+    /// A nested value annotated with `#[trace]`.
+    Nested,
+    /// Anything else.
+    Skip,
+}
+
+struct Field {
+    /// The field name, or its index for tuple structs.
+    access: String,
+    kind: FieldKind,
+}
+
+struct Struct {
+    name: String,
+    /// The generic parameters with their bounds, without the angle brackets.
+    params: String,
+    /// The generic arguments, without bounds or defaults.
+    args: String,
+    where_clause: String,
+    fields: Vec<Field>,
+}
+
+impl Struct {
+    fn expand(&self) -> String {
+        let mut visit = String::new();
+        for field in &self.fields {
+            let f = &field.access;
+            match field.kind {
+                FieldKind::Gc => visit += &format!("visitor.visit(self.{f}.erase());"),
+                FieldKind::OptionGc => {
+                    visit +=
+                        &format!("if let Some(ptr) = &self.{f} {{ visitor.visit(ptr.erase()); }}")
+                }
+                FieldKind::Nested => {
+                    visit += &format!("::ike_gc::Trace::visit_fields(&self.{f}, visitor);")
+                }
+                FieldKind::Skip => {}
+            }
+        }
+        format!(
+            "impl<{params}> ::ike_gc::Trace for {name}<{args}> {where_clause} {{
+                #[allow(unused_variables)]
+                fn visit_fields(&self, visitor: &mut dyn ::ike_gc::Visitor) {{ {visit} }}
+            }}",
+            params = self.params,
+            name = self.name,
+            args = self.args,
+            where_clause = self.where_clause,
+        )
+    }
+}
+
+fn is_punct(tt: &TokenTree, ch: char) -> bool {
+    matches!(tt, TokenTree::Punct(p) if p.as_char() == ch)
+}
+
+fn is_ident(tt: &TokenTree, name: &str) -> bool {
+    matches!(tt, TokenTree::Ident(i) if i.to_string() == name)
+}
+
+fn to_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
+
+/// Split tokens at commas outside of angle brackets. Commas inside other delimiters are already
+/// hidden inside their groups.
+fn split_commas(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, tt) in tokens.iter().enumerate() {
+        if is_punct(tt, '<') {
+            depth += 1;
+        } else if is_punct(tt, '>') {
+            // Skip the `>` of `->`.
+            let arrow = i > 0
+                && matches!(&tokens[i - 1], TokenTree::Punct(p)
+                    if p.as_char() == '-' && p.spacing() == Spacing::Joint);
+            if !arrow {
+                depth = depth.saturating_sub(1);
+            }
+        } else if is_punct(tt, ',') && depth == 0 {
+            parts.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+    if start < tokens.len() {
+        parts.push(&tokens[start..]);
+    }
+    parts
+}
+
+/// Skip the outer attributes at the start of `tokens`, returning whether one of them was
+/// `#[trace]`.
+fn skip_attrs(tokens: &[TokenTree], pos: &mut usize) -> bool {
+    let mut traced = false;
+    while *pos + 1 < tokens.len() && is_punct(&tokens[*pos], '#') {
+        let TokenTree::Group(g) = &tokens[*pos + 1] else {
+            break;
+        };
+        let inner: Vec<_> = g.stream().into_iter().collect();
+        if inner.len() == 1 && is_ident(&inner[0], "trace") {
+            traced = true;
+        }
+        *pos += 2;
+    }
+    traced
+}
+
+fn skip_vis(tokens: &[TokenTree], pos: &mut usize) {
+    if *pos < tokens.len() && is_ident(&tokens[*pos], "pub") {
+        *pos += 1;
+        if let Some(TokenTree::Group(g)) = tokens.get(*pos) {
+            if g.delimiter() == Delimiter::Parenthesis {
+                *pos += 1;
+            }
+        }
+    }
+}
+
+/// Whether `tokens` mention `Gc` anywhere, including inside groups.
+fn mentions_gc(tokens: impl IntoIterator<Item = TokenTree>) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Group(g) => mentions_gc(g.stream()),
+        tt => is_ident(&tt, "Gc"),
+    })
+}
+
+/// Classify a field type by the name of its outermost path segment.
+fn classify(ty: &[TokenTree]) -> FieldKind {
+    let Some(open) = ty.iter().position(|tt| is_punct(tt, '<')) else {
+        return FieldKind::Skip;
+    };
+    let Some(TokenTree::Ident(last)) = ty[..open].last() else {
+        return FieldKind::Skip;
+    };
+    match last.to_string().as_str() {
+        "Gc" => FieldKind::Gc,
+        "Option" => {
+            // Everything between the outer angle brackets.
+            let inner = &ty[open + 1..ty.len() - 1];
+            match classify(inner) {
+                FieldKind::Gc => FieldKind::OptionGc,
+                _ => FieldKind::Skip,
+            }
+        }
+        _ => FieldKind::Skip,
+    }
+}
+
+fn parse_fields(group: &Group) -> Result<Vec<Field>, String> {
+    let tokens: Vec<_> = group.stream().into_iter().collect();
+    let named = group.delimiter() == Delimiter::Brace;
+    let mut fields = vec![];
+    for (index, part) in split_commas(&tokens).into_iter().enumerate() {
+        let mut pos = 0;
+        let traced = skip_attrs(part, &mut pos);
+        skip_vis(part, &mut pos);
+        let access = if named {
+            let Some(TokenTree::Ident(name)) = part.get(pos) else {
+                return Err("expected a field name".into());
+            };
+            if !part.get(pos + 1).is_some_and(|tt| is_punct(tt, ':')) {
+                return Err(format!("expected `:` after field `{}`", name));
+            }
+            pos += 2;
+            name.to_string()
+        } else {
+            index.to_string()
+        };
+        let kind = if traced {
+            FieldKind::Nested
+        } else {
+            classify(&part[pos..])
+        };
+        if matches!(kind, FieldKind::Skip) && mentions_gc(part[pos..].iter().cloned()) {
+            return Err(format!(
+                "field `{}` of type `{}` holds `Gc` pointers that #[derive(Trace)] can't trace; \
+                 use `Gc<..>` or `Option<Gc<..>>`, or a type implementing `Trace` with #[trace]",
+                access,
+                to_string(&part[pos..])
+            ));
+        }
+        fields.push(Field { access, kind });
+    }
+    Ok(fields)
+}
+
+fn parse_struct(input: TokenStream) -> Result<Struct, String> {
+    let tokens: Vec<_> = input.into_iter().collect();
+    let mut pos = 0;
+    skip_attrs(&tokens, &mut pos);
+    skip_vis(&tokens, &mut pos);
+    match tokens.get(pos) {
+        Some(tt) if is_ident(tt, "struct") => pos += 1,
+        _ => return Err("#[derive(Trace)] only supports structs".into()),
+    }
+    let Some(TokenTree::Ident(name)) = tokens.get(pos) else {
+        return Err("expected a struct name".into());
+    };
+    pos += 1;
+
+    // Generic parameters.
+    let mut params = vec![];
+    if tokens.get(pos).is_some_and(|tt| is_punct(tt, '<')) {
+        let mut depth = 0usize;
+        loop {
+            let Some(tt) = tokens.get(pos) else {
+                return Err("unterminated generic parameters".into());
+            };
+            pos += 1;
+            if is_punct(tt, '<') {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            } else if is_punct(tt, '>') {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            params.push(tt.clone());
+        }
+    }
+    let args = split_commas(&params)
+        .into_iter()
+        .map(|param| {
+            // `'a: 'b`, `T: Bound = Default` and `const N: usize` all reduce to their name.
+            let param = if is_ident(&param[0], "const") {
+                &param[1..]
+            } else {
+                param
+            };
+            let end = param
+                .iter()
+                .position(|tt| is_punct(tt, ':') || is_punct(tt, '='))
+                .unwrap_or(param.len());
+            to_string(&param[..end])
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Defaults aren't allowed on impl parameters.
+    let params = split_commas(&params)
+        .into_iter()
+        .map(|param| {
+            let end = param
+                .iter()
+                .position(|tt| is_punct(tt, '='))
+                .unwrap_or(param.len());
+            to_string(&param[..end])
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // The body, with the where clause either before it (named) or after it (tuple).
+    let mut where_clause = vec![];
+    let mut fields = vec![];
+    match tokens.get(pos) {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+            fields = parse_fields(g)?;
+            pos += 1;
+        }
+        _ => {}
+    }
+    for tt in &tokens[pos..] {
+        match tt {
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => fields = parse_fields(g)?,
+            tt if is_punct(tt, ';') => {}
+            tt => where_clause.push(tt.clone()),
+        }
+    }
+
+    Ok(Struct {
+        name: name.to_string(),
+        params,
+        args,
+        where_clause: to_string(&where_clause),
+        fields,
+    })
+}
@@ -0,0 +1,37 @@
+//! Compiles the C driver against `include/ike_gc.h` into a static library.
+
+use std::{env, path::PathBuf, process::Command};
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let source = manifest_dir.join("c/driver.c");
+    let include = manifest_dir.join("../include");
+    let object = out_dir.join("driver.o");
+
+    let cc = env::var_os("CC").unwrap_or_else(|| "cc".into());
+    let status = Command::new(cc)
+        .args(["-std=c11", "-Wall", "-Wextra", "-Werror", "-fPIC", "-c"])
+        .arg("-I")
+        .arg(&include)
+        .arg(&source)
+        .arg("-o")
+        .arg(&object)
+        .status()
+        .expect("Failed to run the C compiler");
+    assert!(status.success(), "Failed to compile {}", source.display());
+
+    let ar = env::var_os("AR").unwrap_or_else(|| "ar".into());
+    let status = Command::new(ar)
+        .arg("crs")
+        .arg(out_dir.join("libike_gc_driver.a"))
+        .arg(&object)
+        .status()
+        .expect("Failed to run ar");
+    assert!(status.success(), "Failed to archive {}", object.display());
+
+    println!("cargo:rustc-link-search=native={}", out_dir.display());
+    println!("cargo:rustc-link-lib=static=ike_gc_driver");
+    println!("cargo:rerun-if-changed=c/driver.c");
+    println!("cargo:rerun-if-changed=../include/ike_gc.h");
+}
@@ -0,0 +1,79 @@
+#include <stdint.h>
+
+#include "ike_gc.h"
+
+typedef struct Node {
+    struct Node *next;
+    int64_t value;
+} Node;
+
+static int freed;
+
+static void node_visit(void *obj, IkeVisitor *visitor) {
+    ike_gc_visit(visitor, (void **)&((Node *)obj)->next);
+}
+
+static void node_free(IkeGc *gc, void *obj) {
+    (void)gc;
+    (void)obj;
+    freed++;
+}
+
+int ike_gc_ffi_driver(void) {
+    IkeGc *gc = ike_gc_new(1 << 16);
+    if (!gc) {
+        return 1;
+    }
+    IkeVTable desc = {
+        .size = sizeof(Node),
+        .align = _Alignof(Node),
+        .size_of = NULL,
+        .mark = NULL,
+        .rewrite = NULL,
+        .free = node_free,
+        .visit = node_visit,
+    };
+    const IkeGcVTable *vt = ike_gc_vtable_new(&desc);
+
+    /* A list of 10 nodes, with a garbage node before each. The heap is large enough not to
+     * collect, so the unrooted list stays put. */
+    Node *list = NULL;
+    for (int i = 0; i < 10; i++) {
+        if (!ike_gc_allocate(gc, vt, sizeof(Node))) {
+            return 2;
+        }
+        Node *node = ike_gc_allocate(gc, vt, sizeof(Node));
+        if (!node) {
+            return 2;
+        }
+        node->next = list;
+        node->value = i;
+        list = node;
+    }
+
+    IkeHandle handle = ike_gc_acquire_handle(gc, list);
+    ike_gc_collect(gc);
+    if (freed != 10) {
+        return 3;
+    }
+    Node *head = ike_gc_get_handle(gc, handle);
+    if (head == list) {
+        return 4;
+    }
+    int count = 0;
+    int64_t sum = 0;
+    for (Node *node = head; node; node = node->next) {
+        count++;
+        sum += node->value;
+    }
+    if (count != 10 || sum != 45) {
+        return 5;
+    }
+
+    ike_gc_release_handle(gc, handle);
+    ike_gc_free(gc);
+    if (freed != 20) {
+        return 6;
+    }
+    return 0;
+}
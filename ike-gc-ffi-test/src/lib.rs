@@ -0,0 +1,9 @@
+//! Drives the collector's C interface from C, see `c/driver.c`.
+
+// The collector defines the functions the driver calls.
+use ike_gc as _;
+
+extern "C" {
+    /// Runs the C driver. Returns 0 on success, or the number of the check that failed.
+    pub fn ike_gc_ffi_driver() -> i32;
+}
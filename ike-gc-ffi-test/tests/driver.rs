@@ -0,0 +1,4 @@
+#[test]
+fn c_driver_succeeds() {
+    assert_eq!(unsafe { ike_gc_ffi_test::ike_gc_ffi_driver() }, 0);
+}
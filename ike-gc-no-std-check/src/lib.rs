@@ -0,0 +1,36 @@
+//! Uses the collector from a `no_std` crate. A workspace build turns on the `std` feature of
+//! `ike-gc` for every member, so build this crate on its own to check the `no_std` build:
+//! `cargo build -p ike-gc-no-std-check`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+
+use ike_gc::{GCAlloc, GCConfig, HeapBacking};
+
+/// A chunk of memory aligned like the objects in the heap.
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct Chunk([u8; 16]);
+
+/// A buffer on the Rust heap that, unlike a `Vec<u8>`, is always aligned for the collector.
+struct ChunkBuffer(Vec<Chunk>);
+
+unsafe impl HeapBacking for ChunkBuffer {
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.0.as_mut_ptr() as *mut u8
+    }
+
+    fn len(&self) -> usize {
+        self.0.len() * core::mem::size_of::<Chunk>()
+    }
+}
+
+/// A heap with two halves of `size` bytes each, on the Rust heap.
+pub fn heap(size: usize) -> GCAlloc {
+    let chunks = (2 * size).div_ceil(core::mem::size_of::<Chunk>());
+    let buffer = ChunkBuffer(vec![Chunk([0; 16]); chunks]);
+    GCAlloc::with_backing(buffer, GCConfig::default())
+}
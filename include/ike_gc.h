@@ -0,0 +1,74 @@
+/*
+ * C interface to the ike-gc collector, built with the `ffi` feature.
+ *
+ * Object types are described by an IkeVTable, registered once with ike_gc_vtable_new. During a
+ * collection the heap calls `visit` on every reachable object, which must call ike_gc_visit on
+ * each pointer slot in it; the heap marks and rewrites the pointers through it. Types that need
+ * the heap while they are traced leave `visit` NULL and set `mark`, which must call ike_gc_mark
+ * on each pointer, and `rewrite`, which must call ike_gc_rewrite on the same pointers. Objects
+ * move, so pointers to them are only stable between collections; keep them alive and up to
+ * date across collections with handles.
+ */
+
+#ifndef IKE_GC_H
+#define IKE_GC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct IkeGc IkeGc;
+typedef struct IkeGcVTable IkeGcVTable;
+typedef struct IkeVisitor IkeVisitor;
+typedef uint64_t IkeHandle;
+
+typedef void (*IkeCallback)(IkeGc *gc, void *obj);
+typedef size_t (*IkeSizeCallback)(const void *obj);
+typedef void (*IkeVisitCallback)(void *obj, IkeVisitor *visitor);
+
+typedef struct IkeVTable {
+    /* Size of the object in bytes, or 0 if `size_of` computes it. */
+    size_t size;
+    /* Alignment of the object; objects are always aligned to at least 16 bytes. */
+    size_t align;
+    IkeSizeCallback size_of;
+    IkeCallback mark;
+    IkeCallback rewrite;
+    /* Called when the object dies, if not NULL. */
+    IkeCallback free;
+    /* Visits the pointers of the object, if not NULL, in which case `mark` and `rewrite` are
+     * not called. */
+    IkeVisitCallback visit;
+} IkeVTable;
+
+/* Create a heap with two halves of `size` bytes each. Returns NULL on failure. */
+IkeGc *ike_gc_new(size_t size);
+/* Destroy a heap, running the free callbacks of every object still in it. */
+void ike_gc_free(IkeGc *gc);
+
+/* Register an object type. The result lives as long as the process. */
+const IkeGcVTable *ike_gc_vtable_new(const IkeVTable *vt);
+
+/* Allocate a zeroed object of `size` bytes, which may collect. Returns NULL when out of memory. */
+void *ike_gc_allocate(IkeGc *gc, const IkeGcVTable *vt, size_t size);
+void ike_gc_collect(IkeGc *gc);
+
+IkeHandle ike_gc_acquire_handle(IkeGc *gc, void *obj);
+void *ike_gc_get_handle(const IkeGc *gc, IkeHandle handle);
+void ike_gc_release_handle(IkeGc *gc, IkeHandle handle);
+
+/* From a visit callback: visit the pointer in `slot`. NULL is ignored. */
+void ike_gc_visit(IkeVisitor *visitor, void **slot);
+/* From a mark callback: keep `obj` alive. NULL is ignored. */
+void ike_gc_mark(IkeGc *gc, const void *obj);
+/* From a rewrite callback: update the pointer in `slot` to its object's new location. */
+void ike_gc_rewrite(IkeGc *gc, const void **slot);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
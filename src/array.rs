@@ -0,0 +1,118 @@
+use core::num::NonZeroUsize;
+
+use crate::{gc_ptr::Gc, GCAlloc, SizeKind};
+
+/// A variable-length array of `T` allocated by [`GCAlloc::allocate_array`].
+///
+/// The length is stored in the first word of the object, followed by the elements. Use
+/// [`GcArray::SIZE`] as the size of the array's vtable.
+///
+/// [`GCAlloc::allocate_array`]: crate::GCAlloc::allocate_array
+#[repr(C)]
+pub struct GcArray<T> {
+    len: usize,
+    data: [T; 0],
+}
+
+impl<T> GcArray<T> {
+    /// The size of an array object, read from its stored length.
+    pub const SIZE: SizeKind = SizeKind::callback(Self::object_size);
+
+    /// The size of an array of `len` elements, not including the GC header. Returns `None` on
+    /// overflow.
+    pub fn size_for(len: usize) -> Option<usize> {
+        core::mem::size_of::<T>()
+            .checked_mul(len)?
+            .checked_add(core::mem::offset_of!(GcArray<T>, data))
+    }
+
+    unsafe fn object_size(ptr: *const u8) -> NonZeroUsize {
+        let array = unsafe { &*(ptr as *const GcArray<T>) };
+        let sz = Self::size_for(array.len).expect("Array size overflow");
+        NonZeroUsize::new(sz).expect("Array size cannot be zero")
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        self.data.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.data.as_mut_ptr()
+    }
+
+    /// View the elements as a slice.
+    ///
+    /// # Safety
+    ///
+    /// All elements must be initialized.
+    pub unsafe fn as_slice(&self) -> &[T] {
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+
+    /// View the elements as a mutable slice.
+    ///
+    /// # Safety
+    ///
+    /// All elements must be initialized.
+    pub unsafe fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+}
+
+/// A pointer to a fully initialized [`GcArray`] together with its length, as returned by
+/// [`GCAlloc::allocate_slice`].
+///
+/// The array is an ordinary heap object: a slice stored in another object must be marked and
+/// rewritten through [`GcSlice::array`].
+pub struct GcSlice<T> {
+    array: Gc<GcArray<T>>,
+    len: usize,
+}
+
+impl<T> GcSlice<T> {
+    /// The slice of the array at `array`, whose elements must all be initialized.
+    pub fn new(array: Gc<GcArray<T>>, len: usize) -> Self {
+        GcSlice { array, len }
+    }
+
+    /// The pointer to the array object.
+    pub fn array(&self) -> &Gc<GcArray<T>> {
+        &self.array
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The elements, if the array is still alive in `gc`.
+    pub fn get<'a>(&self, gc: &'a GCAlloc) -> &'a [T] {
+        let array = gc.get(self.array.clone());
+        debug_assert_eq!(
+            array.len(),
+            self.len,
+            "Slice length does not match its array"
+        );
+        unsafe { array.as_slice() }
+    }
+}
+
+impl<T> Clone for GcSlice<T> {
+    fn clone(&self) -> Self {
+        GcSlice {
+            array: self.array.clone(),
+            len: self.len,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! The memory a heap lives in, see [`GCAlloc::with_backing`].
+//!
+//! [`GCAlloc::with_backing`]: crate::GCAlloc::with_backing
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use memmap2::MmapMut;
+
+/// A region of memory to hold the two halves of a heap.
+///
+/// The heap takes ownership of the backing and writes to the region through the pointer it
+/// returns, for as long as the heap lives.
+///
+/// # Safety
+///
+/// `as_mut_ptr` must return a pointer valid for reads and writes of `len` bytes, which stays
+/// valid and isn't accessed by anything else until the backing is dropped. The region must be
+/// aligned to 16 bytes.
+pub unsafe trait HeapBacking {
+    fn as_mut_ptr(&mut self) -> *mut u8;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Anonymous memory from the OS, the default backing.
+#[cfg(feature = "std")]
+unsafe impl HeapBacking for MmapMut {
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        <[u8]>::as_mut_ptr(self)
+    }
+
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+}
+
+/// A buffer on the Rust heap. Its alignment is up to the allocator, and is checked when the
+/// heap is created.
+unsafe impl HeapBacking for Vec<u8> {
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        Vec::as_mut_ptr(self)
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+/// No memory at all, for the heaps that only run the mark callbacks of other threads.
+#[cfg(feature = "parallel-mark")]
+pub(crate) struct NoBacking;
+
+#[cfg(feature = "parallel-mark")]
+unsafe impl HeapBacking for NoBacking {
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        core::ptr::without_provenance_mut(crate::gc::ALIGNMENT)
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+}
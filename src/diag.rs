@@ -0,0 +1,27 @@
+//! Diagnostics for debugging the collector itself, written to the `log` crate when the `log`
+//! feature is on and compiled out otherwise. Anything an embedder may want to act on is a
+//! [`GcEvent`] instead.
+//!
+//! [`GcEvent`]: crate::GcEvent
+
+macro_rules! trace {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::trace!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        let _ = || {
+            let _ = format_args!($($arg)*);
+        };
+    }};
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        let _ = || {
+            let _ = format_args!($($arg)*);
+        };
+    }};
+}
@@ -0,0 +1,252 @@
+//! A C ABI for embedding the collector in runtimes written in other languages. The matching
+//! declarations are in `include/ike_gc.h`.
+//!
+//! Object types are described by an [`IkeVTable`] of `extern "C"` callbacks, registered once
+//! with [`ike_gc_vtable_new`]. The `visit` callback receives the object and a visitor, and calls
+//! [`ike_gc_visit`] on each pointer slot in it, like a [`VTable::visit_cb`] does with a
+//! [`Visitor`]. Types that need the heap while they are traced set `mark` and `rewrite`
+//! instead, which receive the heap and call [`ike_gc_mark`] or [`ike_gc_rewrite`], like the Rust
+//! callbacks do with [`GCAlloc::mark_raw`] and [`GCAlloc::rewrite_raw`]. Handles are the 64-bit
+//! keys of the handle table.
+//!
+//! A panic can't unwind into C, so misuse that panics in Rust, e.g. an unaligned pointer,
+//! aborts the process.
+
+use alloc::boxed::Box;
+use core::ptr::NonNull;
+
+use crate::{gc_ptr::Gc, GCAlloc, GCHeader, Handle, SizeKind, VTable, Visitor};
+
+/// A callback on an object, called with the heap and the object's payload.
+pub type IkeCallback = unsafe extern "C" fn(gc: *mut GCAlloc, obj: *mut u8);
+
+/// Returns the size of an object of variable size, computed from its contents.
+pub type IkeSizeCallback = unsafe extern "C" fn(obj: *const u8) -> usize;
+
+/// Calls [`ike_gc_visit`] with the visitor on each pointer slot of the object.
+pub type IkeVisitCallback = unsafe extern "C" fn(obj: *mut u8, visitor: *mut IkeVisitor<'_>);
+
+/// The visitor passed to an [`IkeVisitCallback`], opaque to C.
+pub struct IkeVisitor<'a>(&'a mut dyn Visitor);
+
+/// The C description of an object type, see [`VTable`] for the meaning of the fields.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct IkeVTable {
+    /// The size of the object, or 0 if `size_of` computes it.
+    pub size: usize,
+    pub align: usize,
+    pub size_of: Option<IkeSizeCallback>,
+    pub mark: Option<IkeCallback>,
+    pub rewrite: Option<IkeCallback>,
+    /// Called inline when the object dies, if set.
+    pub free: Option<IkeCallback>,
+    /// Visits the pointers of the object, if set, in which case `mark` and `rewrite` are not
+    /// called.
+    pub visit: Option<IkeVisitCallback>,
+}
+
+/// A vtable calling into C. The heap only sees the [`VTable`] at the start, and the trampolines
+/// find the C callbacks after it through the object's header.
+#[repr(C)]
+struct FfiVTable {
+    vt: VTable,
+    c: IkeVTable,
+}
+
+fn c_vtable(obj: *const u8) -> &'static IkeVTable {
+    let hdr = unsafe { &*(obj as *const GCHeader).sub(1) };
+    unsafe { &(*(hdr.get_vt().ptr() as *const FfiVTable)).c }
+}
+
+unsafe fn size_trampoline(obj: *const u8) -> core::num::NonZeroUsize {
+    let size_of = c_vtable(obj)
+        .size_of
+        .expect("Variable-size vtable without size_of");
+    core::num::NonZeroUsize::new(unsafe { size_of(obj) }).expect("Object size is 0")
+}
+
+unsafe fn visit_trampoline(obj: *const u8, visitor: &mut dyn Visitor) {
+    let visit = c_vtable(obj).visit.expect("Visiting vtable without visit");
+    unsafe { visit(obj as *mut u8, &mut IkeVisitor(visitor)) };
+}
+
+unsafe fn mark_trampoline(gc: &mut GCAlloc, obj: *const u8) {
+    if let Some(mark) = c_vtable(obj).mark {
+        unsafe { mark(gc, obj as *mut u8) };
+    }
+}
+
+unsafe fn rewrite_trampoline(gc: &mut GCAlloc, obj: *const u8) {
+    if let Some(rewrite) = c_vtable(obj).rewrite {
+        unsafe { rewrite(gc, obj as *mut u8) };
+    }
+}
+
+unsafe fn free_trampoline(gc: &mut GCAlloc, obj: *const u8) {
+    if let Some(free) = c_vtable(obj).free {
+        unsafe { free(gc, obj as *mut u8) };
+    }
+}
+
+/// Create a heap with two halves of `size` bytes each. Returns null if the memory can't be
+/// mapped. The heap must be destroyed with [`ike_gc_free`].
+#[no_mangle]
+pub extern "C" fn ike_gc_new(size: usize) -> *mut GCAlloc {
+    match GCAlloc::try_new(size) {
+        Ok(gc) => Box::into_raw(Box::new(gc)),
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+/// Destroy a heap, running the free callbacks of every object still in it.
+///
+/// # Safety
+///
+/// `gc` must come from [`ike_gc_new`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_free(gc: *mut GCAlloc) {
+    if !gc.is_null() {
+        let mut gc = unsafe { Box::from_raw(gc) };
+        gc.reset();
+    }
+}
+
+/// Register an object type. The returned vtable lives as long as the process, and can be
+/// passed to [`ike_gc_allocate`] on any heap.
+///
+/// # Safety
+///
+/// `vt` must point to a valid [`IkeVTable`]. Its callbacks must follow the contract of the
+/// [`VTable`] callbacks.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_vtable_new(vt: *const IkeVTable) -> *const VTable {
+    let c = unsafe { *vt };
+    let size = if c.size == 0 {
+        SizeKind::callback(size_trampoline)
+    } else {
+        SizeKind::fixed(c.size)
+    };
+    let ffi = Box::leak(Box::new(FfiVTable {
+        vt: VTable {
+            size,
+            align: c.align,
+            visit_cb: if c.visit.is_some() {
+                Some(visit_trampoline)
+            } else {
+                None
+            },
+            mark_cb: mark_trampoline,
+            rewrite_cb: rewrite_trampoline,
+            free_cb: free_trampoline,
+            ..VTable::DEFAULT
+        },
+        c,
+    }));
+    &ffi.vt
+}
+
+/// Allocate a zeroed object of `size` bytes, which may collect. Returns null if the heap is
+/// out of memory. See [`GCAlloc::allocate`].
+///
+/// # Safety
+///
+/// `gc` must be a live heap, and `vt` must come from [`ike_gc_vtable_new`].
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_allocate(
+    gc: *mut GCAlloc,
+    vt: *const VTable,
+    size: usize,
+) -> *mut u8 {
+    match unsafe { (*gc).allocate(vt, size) } {
+        Ok(ptr) => ptr.get() as *mut u8,
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+/// Collect the heap.
+///
+/// # Safety
+///
+/// `gc` must be a live heap.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_collect(gc: *mut GCAlloc) {
+    unsafe { (*gc).collect() };
+}
+
+/// Acquire a handle keeping `obj` alive across collections.
+///
+/// # Safety
+///
+/// `gc` must be a live heap, and `obj` an object in it.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_acquire_handle(gc: *mut GCAlloc, obj: *mut u8) -> u64 {
+    let handle = unsafe { (*gc).acquire_handle(Gc::new(obj as *const u8)) };
+    handle.as_raw()
+}
+
+/// The current location of the object held by a handle.
+///
+/// # Safety
+///
+/// `gc` must be a live heap, and `handle` a handle acquired from it and not yet released.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_get_handle(gc: *const GCAlloc, handle: u64) -> *mut u8 {
+    unsafe { (*gc).get_handle(&Handle::<u8>::from_raw(handle)).get() as *mut u8 }
+}
+
+/// Release a handle.
+///
+/// # Safety
+///
+/// `gc` must be a live heap, and `handle` a handle acquired from it.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_release_handle(gc: *mut GCAlloc, handle: u64) {
+    unsafe { (*gc).release_handle(Handle::<u8>::from_raw(handle)) };
+}
+
+/// Mark `obj` as reachable, from a mark callback. Null pointers are ignored.
+///
+/// # Safety
+///
+/// `gc` must be the heap that called the callback, and `obj` null or an object in it.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_mark(gc: *mut GCAlloc, obj: *const u8) {
+    if !obj.is_null() {
+        unsafe { (*gc).mark_raw(obj) };
+    }
+}
+
+/// Point the pointer in `slot` to the new location of its object, from a rewrite callback.
+/// Null pointers are left alone.
+///
+/// # Safety
+///
+/// `gc` must be the heap that called the callback, and `slot` must hold null or a pointer
+/// passed to [`ike_gc_mark`] in this collection.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_rewrite(gc: *mut GCAlloc, slot: *mut *const u8) {
+    if let Some(mut slot) = NonNull::new(slot) {
+        if !unsafe { *slot.as_ptr() }.is_null() {
+            unsafe { (*gc).rewrite_raw(slot.as_mut()) };
+        }
+    }
+}
+
+/// Visit the pointer in `slot`, from a visit callback. Null pointers are skipped.
+///
+/// # Safety
+///
+/// `visitor` must be the visitor passed to the callback, and `slot` must hold null or a pointer
+/// to an object of the heap.
+#[no_mangle]
+pub unsafe extern "C" fn ike_gc_visit(visitor: *mut IkeVisitor<'_>, slot: *mut *const u8) {
+    if let Some(slot) = NonNull::new(slot) {
+        if !unsafe { *slot.as_ptr() }.is_null() {
+            // `Gc` is a transparent cell around a non-null pointer, so rewriting visitors can
+            // set the slot through it.
+            let field = unsafe { &*(slot.as_ptr() as *const Gc<()>) };
+            unsafe { (*visitor).0.visit(field) };
+        }
+    }
+}
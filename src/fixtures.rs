@@ -0,0 +1,121 @@
+//! Ready-made GC-managed types for tests.
+//!
+//! These types implement the callback contract of [`VTable`] correctly, so downstream crates can
+//! test their integration without hand-rolling callbacks. They also double as reference examples
+//! of how to write the callbacks for a type of your own.
+
+use crate::{gc::AllocError, gc_ptr::Gc, GCAlloc, SizeKind, VTable, Visitor};
+
+/// A Lisp-style pair of two optional pointers.
+pub struct Cons {
+    pub car: Option<Gc<Cons>>,
+    pub cdr: Option<Gc<Cons>>,
+}
+
+impl Cons {
+    pub fn new(car: Option<Gc<Cons>>, cdr: Option<Gc<Cons>>) -> Self {
+        Self { car, cdr }
+    }
+
+    /// Allocate a new cons cell.
+    pub fn alloc(
+        gc: &mut GCAlloc,
+        car: Option<Gc<Cons>>,
+        cdr: Option<Gc<Cons>>,
+    ) -> Result<Gc<Cons>, AllocError> {
+        gc.allocate_typed(&CONS_VTABLE, Cons::new(car, cdr))
+    }
+}
+
+fn cons_visit(ptr: *const u8, visitor: &mut dyn Visitor) {
+    let cons = unsafe { &*(ptr as *const Cons) };
+    if let Some(car) = &cons.car {
+        visitor.visit(car.erase());
+    }
+    if let Some(cdr) = &cons.cdr {
+        visitor.visit(cdr.erase());
+    }
+}
+
+pub static CONS_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Cons>(),
+    align: core::mem::align_of::<Cons>(),
+    visit_cb: Some(cons_visit),
+    ..VTable::DEFAULT
+};
+
+/// A binary tree node carrying a value.
+pub struct GcTree {
+    pub value: usize,
+    pub left: Option<Gc<GcTree>>,
+    pub right: Option<Gc<GcTree>>,
+}
+
+impl GcTree {
+    /// Allocate a new tree node.
+    pub fn alloc(
+        gc: &mut GCAlloc,
+        value: usize,
+        left: Option<Gc<GcTree>>,
+        right: Option<Gc<GcTree>>,
+    ) -> Result<Gc<GcTree>, AllocError> {
+        gc.allocate_typed(&GC_TREE_VTABLE, GcTree { value, left, right })
+    }
+
+    /// Allocate a complete tree of the given depth, numbering the nodes in pre-order starting
+    /// from `first`. A depth of 0 is an empty tree.
+    ///
+    /// The heap must be large enough to hold the whole tree without collecting, as the partially
+    /// built subtrees aren't rooted.
+    pub fn alloc_complete(gc: &mut GCAlloc, depth: usize, first: usize) -> Option<Gc<GcTree>> {
+        if depth == 0 {
+            return None;
+        }
+        let left = Self::alloc_complete(gc, depth - 1, first + 1);
+        let right = Self::alloc_complete(gc, depth - 1, first + (1 << (depth - 1)));
+        Some(Self::alloc(gc, first, left, right).expect("Malloc failed"))
+    }
+}
+
+fn tree_visit(ptr: *const u8, visitor: &mut dyn Visitor) {
+    let tree = unsafe { &*(ptr as *const GcTree) };
+    if let Some(left) = &tree.left {
+        visitor.visit(left.erase());
+    }
+    if let Some(right) = &tree.right {
+        visitor.visit(right.erase());
+    }
+}
+
+pub static GC_TREE_VTABLE: VTable = VTable {
+    size: SizeKind::of::<GcTree>(),
+    align: core::mem::align_of::<GcTree>(),
+    visit_cb: Some(tree_visit),
+    ..VTable::DEFAULT
+};
+
+/// A heap cell holding a plain value. The value must not contain any [`Gc`] pointers, as they
+/// won't be traced. It is dropped when the box is collected.
+pub struct GcBox<T> {
+    pub value: T,
+}
+
+impl<T> GcBox<T> {
+    pub const VTABLE: VTable = VTable {
+        size: SizeKind::of::<GcBox<T>>(),
+        align: core::mem::align_of::<GcBox<T>>(),
+        free_cb: Self::free,
+        ..VTable::DEFAULT
+    };
+
+    /// Allocate a new box.
+    pub fn alloc(gc: &mut GCAlloc, value: T) -> Result<Gc<GcBox<T>>, AllocError> {
+        // Make sure the vtable is promoted to a static, as the heap keeps pointing to it.
+        let vt: &'static VTable = &Self::VTABLE;
+        gc.allocate_typed(vt, GcBox { value })
+    }
+
+    fn free(_gc: &mut GCAlloc, ptr: *const u8) {
+        unsafe { core::ptr::drop_in_place(ptr as *mut GcBox<T>) };
+    }
+}
@@ -1,15 +1,56 @@
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    format,
+    string::String,
+    vec,
+    vec::Vec,
+};
 use core::panic;
-use std::{cell::Cell, collections::VecDeque, ptr::NonNull};
+use core::{cell::Cell, ptr::NonNull, time::Duration};
+#[cfg(feature = "std")]
+use std::time::Instant;
 
-use log::{debug, error, info, trace, warn};
+#[cfg(feature = "std")]
 use memmap2::MmapMut;
-use slotmap::{new_key_type, SlotMap};
+use slotmap::{new_key_type, Key, KeyData, SlotMap};
+
+/// Without std there is no clock to time collections with, so they all take no time.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Copy)]
+struct Instant;
+
+#[cfg(not(feature = "std"))]
+impl Instant {
+    fn now() -> Self {
+        Instant
+    }
 
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+#[cfg(feature = "compressed-ptrs")]
+use crate::gc_ptr::CompressedGc;
 use crate::{
-    gc_ptr::Gc,
-    vtable::{VTPtr, VTable},
-    GCHeader,
+    array::{GcArray, GcSlice},
+    backing::HeapBacking,
+    gc_ptr::{CellValue, Gc, GcCell, Unrooted, Weak},
+    large::LargeObjects,
+    mark_bits::MarkBits,
+    mem,
+    observer::{AbandonReason, GcEvent, GcObserver},
+    snapshot::SnapshotWriter,
+    stack,
+    stack_map::{FrameWalker, StackMap},
+    string::{GcStr, GC_STR_VTABLE},
+    trace::{MarkVisitor, RewriteVisitor, Trace, Visitor},
+    vtable::{SizeKind, VTPtr, VTable},
+    GCHeader, MAX_AGE,
 };
+#[cfg(feature = "parallel-mark")]
+use crate::{backing::NoBacking, parallel::MarkQueue};
 
 fn header_from_ptr<T>(ptr: *const T) -> *mut GCHeader {
     let ptr = ptr as *const GCHeader as *mut GCHeader;
@@ -20,339 +61,4489 @@ fn ptr_from_header<T>(header: *const GCHeader) -> *const T {
     unsafe { header.add(1) as *const T }
 }
 
+/// The size of the cell holding an object of `raw_sz` bytes, header included. Empty objects
+/// still get a payload slot, so the address of their payload lies inside their own cell rather
+/// than at the header of the next one.
+fn cell_size(raw_sz: usize) -> Option<usize> {
+    raw_sz
+        .max(1)
+        .checked_add(core::mem::size_of::<GCHeader>())
+        .and_then(|sz| sz.checked_next_multiple_of(ALIGNMENT))
+}
+
 new_key_type! {
     pub struct HandleKey;
+    pub struct WeakHandleKey;
+    pub struct PinKey;
+    /// A group of handles that can be released at once, see [`GCAlloc::new_handle_set`].
+    pub struct HandleSetId;
+}
+
+/// Write the header of a free block of `sz` bytes at `ptr`. Nothing is written if the block
+/// can't hold a header, which happens when the space before it is exactly full.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writing `sz` bytes.
+unsafe fn write_free_block(ptr: *mut u8, sz: usize) {
+    if sz < core::mem::size_of::<GCHeader>() {
+        return;
+    }
+    let free_header = GCHeader {
+        vt: Cell::new(VTPtr::new_free().into()),
+        sz,
+    };
+    trace!("Writing free block of size {} at {:?}", sz, ptr);
+    unsafe { core::ptr::write(ptr as *mut GCHeader, free_header) };
+}
+
+/// Extra bytes reserved by each object aligned to `align`, on top of its block size.
+fn align_slack(align: usize) -> usize {
+    align.max(ALIGNMENT) - ALIGNMENT
+}
+
+/// Padding needed before a block placed at `addr` for its payload to be aligned to `align`.
+fn align_pad(addr: usize, align: usize) -> usize {
+    let payload = addr + core::mem::size_of::<GCHeader>();
+    payload.next_multiple_of(align.max(ALIGNMENT)) - payload
+}
+
+/// Lay out a `sz`-byte block aligned to `align` in the space reserved for it at `ptr`: the block
+/// starts after a free block of padding that aligns its payload, and is followed by a free block
+/// for the rest of the reservation. Either free block is left out if empty. Returns the start of
+/// the block and the end of the reservation.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writing `sz + align_slack(align)` bytes.
+unsafe fn write_padded(ptr: *mut u8, align: usize, sz: usize) -> (*mut u8, *mut u8) {
+    let pad = align_pad(ptr as usize, align);
+    let tail = align_slack(align) - pad;
+    unsafe {
+        if pad > 0 {
+            write_free_block(ptr, pad);
+        }
+        let block = ptr.add(pad);
+        if tail > 0 {
+            write_free_block(block.add(sz), tail);
+        }
+        (block, block.add(sz + tail))
+    }
+}
+
+/// Iterator over the headers of all blocks in a space, live or free, in address order.
+///
+/// Every block's size includes its own header, free blocks included, so the next block always
+/// starts `sz` bytes after the current one.
+struct Blocks {
+    cursor: *const u8,
+    end: *const u8,
+}
+
+impl Blocks {
+    fn new(space: *const u8, space_size: usize) -> Self {
+        Blocks {
+            cursor: space,
+            end: space.wrapping_add(space_size),
+        }
+    }
+}
+
+impl Iterator for Blocks {
+    type Item = *const GCHeader;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cursor >= self.end {
+            return None;
+        }
+        let hdr = self.cursor as *const GCHeader;
+        let sz = unsafe { (*hdr).size() };
+        assert!(
+            sz >= core::mem::size_of::<GCHeader>(),
+            "Invalid size smaller than header: {}, found at {:p}",
+            sz,
+            hdr
+        );
+        let remaining = self.end as usize - self.cursor as usize;
+        assert!(
+            sz <= remaining,
+            "Block of size {} at {:p} overruns the space",
+            sz,
+            hdr
+        );
+        self.cursor = self.cursor.wrapping_add(sz);
+        Some(hdr)
+    }
+}
+
+/// Collects the headers of the objects behind the visited fields, see [`GCAlloc::pointers_of`].
+struct CollectVisitor(Vec<*const GCHeader>);
+
+impl Visitor for CollectVisitor {
+    fn visit(&mut self, field: &Gc<()>) {
+        self.0.push(header_from_ptr(field.get()));
+    }
 }
 
+/// A handle that keeps its object alive. See [`GCAlloc::acquire_handle`].
+///
+/// A handle is only a key into the heap's handle table, and can only be resolved through the
+/// heap. So handles can be sent between threads regardless of their object type, which only
+/// matters while resolving them.
 pub struct Handle<T> {
     key: HandleKey,
-    _marker: std::marker::PhantomData<T>,
+    _marker: core::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    /// The key of the handle in the heap's handle table, as passed to
+    /// [`GCAlloc::for_each_handle`].
+    pub fn key(&self) -> HandleKey {
+        self.key
+    }
+
+    /// Forget the type of the object, e.g. to keep objects of different types together. See
+    /// [`GCAlloc::downcast_handle`] to get it back.
+    pub fn erase(self) -> Handle<u8> {
+        Handle {
+            key: self.key,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// The handle as an opaque integer, e.g. to store it in a C struct. It holds the index and
+    /// version of the key, so a released handle stays released when it comes back.
+    pub fn as_raw(&self) -> u64 {
+        self.key.data().as_ffi()
+    }
+
+    /// Turn an integer from [`Handle::as_raw`] back into a handle.
+    ///
+    /// # Safety
+    ///
+    /// The handle must hold an object of type `T`, which isn't checked.
+    pub unsafe fn from_raw(raw: u64) -> Self {
+        Handle {
+            key: KeyData::from_ffi(raw).into(),
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// The position of a root on the shadow stack. See [`GCAlloc::push_root`].
+pub struct RootIndex<T> {
+    index: usize,
+    _marker: core::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for RootIndex<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for RootIndex<T> {}
+
+/// A handle that keeps its object alive and at a fixed address. See [`GCAlloc::pin`].
+pub struct PinnedHandle<T> {
+    key: PinKey,
+    _marker: core::marker::PhantomData<fn() -> T>,
+}
+
+/// A guard that releases the handles rooted through it on drop. See [`GCAlloc::scope`].
+///
+/// Scopes nest by calling [`GCAlloc::scope`] on a scope; the inner scope borrows the outer one,
+/// so it is always dropped first. Handles rooted in a scope must not be used after it is
+/// dropped, as [`GCAlloc::get_handle`] panics on released handles.
+pub struct HandleScope<'gc> {
+    gc: &'gc mut GCAlloc,
+    keys: Vec<HandleKey>,
 }
 
+impl HandleScope<'_> {
+    /// Root a pointer until the scope is dropped.
+    pub fn root<T>(&mut self, ptr: Gc<T>) -> Handle<T> {
+        let handle = self.gc.acquire_handle(ptr);
+        self.keys.push(handle.key);
+        handle
+    }
+}
+
+impl core::ops::Deref for HandleScope<'_> {
+    type Target = GCAlloc;
+
+    fn deref(&self) -> &GCAlloc {
+        self.gc
+    }
+}
+
+impl core::ops::DerefMut for HandleScope<'_> {
+    fn deref_mut(&mut self) -> &mut GCAlloc {
+        self.gc
+    }
+}
+
+impl Drop for HandleScope<'_> {
+    fn drop(&mut self) {
+        for key in self.keys.drain(..).rev() {
+            self.gc.handles.remove(key);
+        }
+    }
+}
+
+/// A handle that refers to an object without keeping it alive. See [`GCAlloc::acquire_weak`].
+pub struct WeakHandle<T> {
+    key: WeakHandleKey,
+    _marker: core::marker::PhantomData<fn() -> T>,
+}
+
+/// A garbage-collected heap.
+///
+/// A heap can be moved to another thread along with everything in it, provided the objects it
+/// holds are safe to send. It can't be shared between threads.
 pub struct GCAlloc {
-    _mmap: MmapMut,
+    _backing: Box<dyn HeapBacking + Send>,
+    config: GCConfig,
 
     from_half: *mut u8,
     to_half: *mut u8,
     chunk_size: usize,
+    /// Size the halves may grow to. Equal to `chunk_size` for a heap that doesn't grow.
+    max_size: usize,
+    /// When the heap shrinks, see [`GCAlloc::set_shrink_policy`].
+    shrink_policy: Option<ShrinkPolicy>,
+    /// Number of collections in a row whose survivors were below the shrink policy's occupancy.
+    low_occupancy_collections: usize,
+    /// The memory the heap grew out of. Its forward pointers stay readable until the next
+    /// collection, as they are in a regular to-space.
+    retired_backing: Option<Box<dyn HeapBacking + Send>>,
+    /// Size of the to-space. It only differs from `chunk_size` while collecting into a new heap
+    /// or growing, or in a heap made by [`GCAlloc::with_split`], where the two sizes trade
+    /// places at every collection.
+    to_size: usize,
+    /// Whether the to-space is released to the OS after collecting, see
+    /// [`GCAlloc::set_return_memory`].
+    return_memory: bool,
+    /// Whether the to-space holds nothing but the forward pointers of the last collection,
+    /// waiting to be released.
+    to_space_stale: bool,
 
     from_cursor: usize,
+    /// End of the old generation in the from-space. The objects after it, allocated since the
+    /// last collection, make up the nursery.
+    old_end: usize,
+    /// Bytes at the start of each half known to be committed.
+    from_committed: usize,
+    to_committed: usize,
 
     in_gc: bool,
+    /// What the collection in progress is doing, see [`GCAlloc::phase`].
+    phase: GCPhase,
+    /// Set between [`GCAlloc::mark_step`] and the collection that finishes its marking.
+    incremental_marking: bool,
+    /// Set during a minor collection, which only marks and moves the nursery.
+    minor_gc: bool,
+    /// Where the last minor collection moved each live nursery object, as pairs of old and new
+    /// payload addresses sorted by the old one. `None` after a full collection.
+    promoted: Option<Vec<(usize, usize)>>,
+    /// Old objects that may point into the nursery, which minor collections trace and rewrite.
+    remembered: BTreeSet<*const GCHeader>,
+    /// Allocation cursor in the to-space while pointers are rewritten. Rewrite callbacks
+    /// allocate by bumping it.
+    rewrite_cursor: Option<usize>,
+    /// Set while `allocate_typed` re-runs a rewrite callback after a collection. Allocations
+    /// then don't collect, so the pointers being rewritten stay valid.
+    no_collect: bool,
+    /// Fraction of the from-space that can fill up before the next allocation collects.
+    gc_threshold: f64,
+    /// Number of minor collections a nursery object survives before it is promoted, see
+    /// [`GCAlloc::set_tenure_threshold`].
+    tenure_threshold: u8,
+    /// Threads marking in parallel, see [`GCAlloc::set_mark_threads`].
+    #[cfg(feature = "parallel-mark")]
+    mark_threads: usize,
+    /// Write protection of the from-space while marking and copying.
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    protection: Option<crate::protect::Protection>,
 
     work_list: VecDeque<*const GCHeader>,
+    /// Mark bits of the collection in progress, if they are kept outside of the headers.
+    mark_bits: Option<MarkBits>,
+    /// Set during a Cheney scan, where marking an object copies it instead of queueing it.
+    scanning: bool,
+    /// Set while a mark callback runs during a Cheney scan, which can't allocate.
+    scan_marking: bool,
+    /// Start and end offsets of the blocks the rewrite callbacks allocated during a Cheney scan,
+    /// which aren't scanned themselves.
+    scan_skip: Vec<(usize, usize)>,
+    /// Weak pointers met during a Cheney scan before their object was reached. Whether it
+    /// survives is only known once the scan is done.
+    deferred_weak: Vec<*const Weak<u8>>,
+    /// Set while marking, when the work list is bounded by [`GCConfig::work_list_limit`].
+    bounded_marking: bool,
+    /// Set when a pointer was dropped because the work list was full.
+    work_list_overflowed: bool,
+    /// Set while marking for [`GCAlloc::try_collect`], when errors reported by the callbacks
+    /// abandon the collection instead of panicking.
+    fallible_trace: bool,
+    /// The first error reported while marking with `fallible_trace` set.
+    trace_error: Option<TraceError>,
+    /// Dead objects whose vtable asks for finalization, with a copy of their payload.
+    finalize_queue: Vec<(*const VTable, FinalizePayload)>,
 
     handles: SlotMap<HandleKey, NonNull<u8>>,
+    /// The handles acquired in each handle set. Handles released on their own stay listed.
+    handle_sets: SlotMap<HandleSetId, Vec<HandleKey>>,
+    /// The shadow stack of roots, see [`GCAlloc::push_root`].
+    root_stack: Vec<NonNull<u8>>,
+    /// Weak handles, which are cleared when their object dies.
+    weak_handles: SlotMap<WeakHandleKey, Option<NonNull<u8>>>,
+    /// Pinned objects, which are roots that never move.
+    pins: SlotMap<PinKey, NonNull<u8>>,
+    /// Unpinned objects that still sit in the to-space, where the next copy would overwrite
+    /// them. They stay in place for one more collection.
+    unpinned: Vec<NonNull<u8>>,
+    /// Headers of the objects kept in place by the last collection, sorted by address.
+    pinned: Vec<*const GCHeader>,
+    /// Objects found on the stack by a conservative collection, which are roots kept in place
+    /// while it runs.
+    conservative_roots: Vec<NonNull<u8>>,
+    /// End of the stack scanned by conservative collections, if set explicitly.
+    stack_bottom: Option<*const u8>,
+    /// Objects above the large object threshold, which live outside of the semispaces.
+    large: LargeObjects,
+    /// Hooks run around each collection. See [`GCAlloc::on_before_collect`].
+    before_collect: Vec<CollectHook>,
+    after_collect: Vec<CollectHook>,
+    /// Receives the events of the heap, see [`GCAlloc::set_observer`].
+    observer: Box<dyn GcObserver + Send>,
+    /// Callbacks reporting and rewriting roots held outside of the heap, see
+    /// [`GCAlloc::set_root_enumerator`].
+    root_enumerator: Option<RootCallback>,
+    root_rewriter: Option<RootCallback>,
+    /// Precise stack roots, see [`GCAlloc::set_frame_walker`]. Stack maps are keyed by return
+    /// address.
+    frame_walker: Option<FrameWalker>,
+    stack_maps: BTreeMap<usize, StackMap>,
+    /// Where the offsets of compressed pointers start, see [`GCAlloc::compress`], and where
+    /// they started before the last collection.
+    #[cfg(feature = "compressed-ptrs")]
+    compress_base: *const u8,
+    #[cfg(feature = "compressed-ptrs")]
+    prev_compress_base: *const u8,
+    /// Called with the old and new address of each object moved, see
+    /// [`GCAlloc::set_move_observer`].
+    move_observer: Option<MoveCallback>,
+    /// Called when an allocation runs out of memory, see [`GCAlloc::set_oom_handler`].
+    oom_handler: Option<OomHandler>,
+    /// Values waiting in [`GCAlloc::allocate_typed`] for an allocation to be retried, with
+    /// their vtables. Their pointers are roots until then.
+    retrying: Vec<(*const VTable, *const u8)>,
 
     gc_count: usize,
+    /// Counts the collections and resets, which may move or free objects, for
+    /// [`GCAlloc::get_unrooted`]. Unlike `gc_count`, it never starts over.
+    epoch: usize,
+    minor_gc_count: usize,
     meta_total_allocated: usize,
+    /// Number of objects and bytes allocated per vtable, see [`GCAlloc::type_stats`].
+    #[cfg(feature = "stats")]
+    meta_type_stats: alloc::collections::BTreeMap<*const VTable, (usize, usize)>,
     meta_high_water_mark: usize,
+    meta_last_reclaimed: usize,
+    meta_last_survivors: usize,
+    meta_live_bytes: usize,
+    meta_occupancy: f64,
+    /// `meta_total_allocated` at the end of the last collection.
+    meta_allocated_at_gc: usize,
+    meta_allocation_rate: f64,
+    meta_total_gc_time: Duration,
+    meta_last_gc_time: Duration,
+    /// Filled in as the collection in progress, or the last one, moves through its phases.
+    meta_last_phase_timings: PhaseTimings,
+    /// When the collection entered its current phase.
+    phase_start: Instant,
+    meta_peak_work_list: usize,
 }
 
+type CollectHook = Box<dyn FnMut(&GCMeta) + Send>;
+type RootCallback = Box<dyn FnMut(&mut GCAlloc) + Send>;
+type MoveCallback = Box<dyn FnMut(*const u8, *const u8) + Send>;
+type OomHandler = Box<dyn FnMut(&mut GCAlloc, usize) -> bool + Send>;
+
+// SAFETY: The raw pointers in the heap only point into memory the heap owns, or to vtables,
+// which are `'static` and immutable. `Gc` pointers to heap objects are `!Send`, so they can't be
+// moved to another thread apart from the heap, and handles are plain keys that can only be
+// resolved through the heap itself. Moving the heap thus moves every way of reaching its objects
+// along with it.
+//
+// The heap can't check the objects themselves: anything stored in a heap that is sent to
+// another thread must itself be safe to send.
+unsafe impl Send for GCAlloc {}
+
 #[derive(Debug, Default)]
 pub struct GCMeta {
     pub currently_allocated: usize,
     pub gc_count: usize,
+    /// Number of minor collections, which are included in `gc_count`.
+    pub minor_gc_count: usize,
     pub total_allocated: usize,
     pub high_water_mark: usize,
+    /// Pages of the heap mapping (both halves) that the collector has written to and not yet
+    /// released back to the OS.
+    pub committed_pages: usize,
+    /// Bytes freed by the last collection.
+    pub last_reclaimed: usize,
+    /// Bytes in use right after the last collection, in the semispace and the large object
+    /// space, including objects allocated by rewrite callbacks.
+    pub last_survivors: usize,
+    /// Bytes of the semispace in use right after the last collection. Unlike `last_survivors`,
+    /// this leaves out the large object space.
+    pub live_bytes: usize,
+    /// `live_bytes` as a fraction of `semispace_size` at the end of the last collection, for
+    /// sizing the heap.
+    pub occupancy: f64,
+    /// Moving average of the bytes allocated between collections, weighing recent collections
+    /// more. See [`GCAlloc::bytes_allocated_since_last_gc`].
+    pub allocation_rate_bytes_per_gc: f64,
+    /// Bytes taken up by objects in the large object space, headers included.
+    pub large_allocated: usize,
+    /// Wall-clock time spent in all collections so far.
+    pub total_gc_time: Duration,
+    /// Wall-clock time spent in the last collection.
+    pub last_gc_time: Duration,
+    /// How `last_gc_time` splits between the phases of the collection.
+    pub last_phase_timings: PhaseTimings,
+    /// Largest length the mark work list reached in the last collection, 0 after a Cheney scan.
+    pub peak_work_list: usize,
+    /// Capacity of the mark work list, which is kept from one collection to the next.
+    pub work_list_capacity: usize,
+    /// Size of the half of the heap objects are allocated in.
+    pub semispace_size: usize,
 }
 
-const ALIGNMENT: usize = 16;
-
-impl GCAlloc {
-    pub fn new(sz: usize) -> Self {
-        // Request 2*sz bytes from the system, and split it into two halves.
-        let mmap = MmapMut::map_anon(2 * sz).unwrap();
-        let ptr = mmap.as_ptr();
-        let from_half = ptr as *mut u8;
-        let to_half = unsafe { ptr.add(sz) } as *mut u8;
-
-        GCAlloc {
-            _mmap: mmap,
-            from_half,
-            to_half,
-            from_cursor: 0,
-            chunk_size: sz,
-            in_gc: false,
-            work_list: VecDeque::new(),
-            handles: SlotMap::with_key(),
+/// Wall-clock time spent in each [`GCPhase`] of a collection, see
+/// [`GCMeta::last_phase_timings`]. Setting up and wrapping up the collection isn't counted, so
+/// the phases add up to a little less than [`GCMeta::last_gc_time`].
+///
+/// Sweeping dead objects counts as copying. A Cheney scan, see [`GCConfig::separate_passes`],
+/// copies and rewrites in a single pass, which counts as copying, and compacting plans the new
+/// addresses in the copy phase too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub mark_roots: Duration,
+    pub mark: Duration,
+    pub copy: Duration,
+    pub rewrite: Duration,
+}
 
-            gc_count: 0,
-            meta_total_allocated: 0,
-            meta_high_water_mark: 0,
-        }
+impl PhaseTimings {
+    /// The time spent in all phases.
+    pub fn total(&self) -> Duration {
+        self.mark_roots + self.mark + self.copy + self.rewrite
     }
+}
 
-    pub fn metadata(&self) -> GCMeta {
-        GCMeta {
-            currently_allocated: self.from_cursor,
-            gc_count: self.gc_count,
-            total_allocated: self.meta_total_allocated,
-            high_water_mark: self.meta_high_water_mark,
+/// When a heap moves into a smaller mapping, see [`GCAlloc::set_shrink_policy`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShrinkPolicy {
+    /// Fraction of the semispace the survivors of a collection must stay below to count
+    /// towards shrinking.
+    pub occupancy: f64,
+    /// Number of collections in a row whose survivors stay below `occupancy`, after which the
+    /// next collection shrinks the heap.
+    pub collections: usize,
+    /// Size of each half below which the heap doesn't shrink.
+    pub min_size: usize,
+}
+
+impl Default for ShrinkPolicy {
+    fn default() -> Self {
+        ShrinkPolicy {
+            occupancy: 0.25,
+            collections: 4,
+            min_size: 64 * 1024,
         }
     }
+}
 
-    /// Acquire a handle to a pointer of type T. The pointer must be allocated
-    /// by [`GCAlloc::allocate`].
-    pub fn acquire_handle<T>(&mut self, ptr: Gc<T>) -> Handle<T> {
-        let ptr = ptr.get();
-        assert!(ptr as usize % ALIGNMENT == 0);
-        assert!(ptr as usize >= self.from_half as usize);
-        let key = self.handles.insert(NonNull::new(ptr as *mut u8).unwrap());
-        Handle {
-            key,
-            _marker: std::marker::PhantomData,
-        }
+/// Construction-time options for [`GCAlloc`].
+#[derive(Debug, Clone, Default)]
+pub struct GCConfig {
+    /// Commit heap pages explicitly in chunks as allocation advances, and release the pages past
+    /// the end of the live data back to the OS after each collection. This keeps the resident
+    /// size proportional to actual usage, at the cost of page faults when the heap grows again.
+    pub incremental_commit: bool,
+    /// Debug aid: make the from-space read-only while marking and copying, so user code that
+    /// mutates a heap object during a collection faults immediately instead of silently
+    /// corrupting the heap. The fault aborts the process with a message naming the cause. The
+    /// collector itself writes headers by briefly unprotecting the page involved, which makes
+    /// collections much slower.
+    ///
+    /// Only whole pages are protected, so objects in a partial page at either end of the
+    /// from-space are not covered unless the heap size is a multiple of the page size. Only
+    /// supported on Linux; ignored elsewhere.
+    pub protect_during_gc: bool,
+    /// Objects whose block, header included, is larger than this many bytes are allocated in the
+    /// large object space instead of the semispaces. Large objects each get their own mapping
+    /// and are never moved. Defaults to half the size of a semispace.
+    pub large_object_threshold: Option<usize>,
+    /// Soft limit on the length of the mark work list. Once it is reached, pointers reported by
+    /// mark callbacks are dropped instead of queued, and the heap is rescanned for marked
+    /// objects whose pointers haven't all been followed yet. This bounds the memory used for
+    /// marking wide object graphs, at the cost of time. Roots are always queued. Defaults to
+    /// [`DEFAULT_WORK_LIST_LIMIT`] entries. A Cheney scan, see [`GCConfig::separate_passes`],
+    /// doesn't use the work list, as the to-space takes its place.
+    pub work_list_limit: Option<usize>,
+    /// Size of the nursery in bytes, enabling generational collection. Once the objects
+    /// allocated since the last collection fill the nursery, the next allocation runs a minor
+    /// collection, which moves the survivors into the old generation without touching the old
+    /// objects. Full collections only run when the heap fills up. See
+    /// [`GCAlloc::minor_collect`]. Defaults to `None`, where every collection is a full one.
+    pub nursery_size: Option<usize>,
+    /// Keep mark bits in a bitmap on the side instead of in the object headers, so marking
+    /// doesn't write to the headers. With [`GCConfig::protect_during_gc`], this saves
+    /// unprotecting a page for every object marked. The bitmap takes a bit per 16 bytes of the
+    /// heap, and is allocated for each collection.
+    pub side_mark_bits: bool,
+    /// Collect with separate mark, copy and rewrite passes over the heap even where a Cheney
+    /// scan would do, e.g. to time the passes apart in [`PhaseTimings`] or to bound the mark
+    /// work list with [`GCConfig::work_list_limit`].
+    ///
+    /// By default, a full collection is a single Cheney scan where it can be. The roots are
+    /// copied to the to-space, which is then scanned in order: each object is marked, which
+    /// copies the objects it points to to the end of the to-space, and then rewritten to point
+    /// at the copies. The to-space serves as the work list, and objects are copied in the order
+    /// they are reached rather than in address order.
+    ///
+    /// The scan moves objects while it traces them, and the callbacks run on the copies, so
+    /// the separate passes are used regardless for collections that keep objects in place, i.e.
+    /// with pinned objects, that may grow or shrink the heap or copy into a smaller to-space,
+    /// that finish an incremental mark or may be abandoned by [`GCAlloc::try_collect`], and for
+    /// heaps with [`GCConfig::protect_during_gc`] or marking on several threads.
+    pub separate_passes: bool,
+    /// Alignment of the payload of every object, a power of two no smaller than the alignment
+    /// of [`GCHeader`]. Objects are always aligned to at least 16 bytes, the size of their
+    /// header, so smaller values have no effect. Larger ones pad each object like a larger
+    /// [`VTable::align`] does. Defaults to 16.
+    pub alignment: Option<usize>,
+    /// How collections reclaim the space of dead objects. Defaults to
+    /// [`CollectorMode::Copying`].
+    pub mode: CollectorMode,
+}
+
+/// How a heap collects, see [`GCConfig::mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CollectorMode {
+    /// Copy the live objects into the other half of the heap, which has to be as large as the
+    /// live set.
+    #[default]
+    Copying,
+    /// Mark the live objects, then slide them down to the start of a single space, so no
+    /// second half is mapped. Pointers are rewritten to the slid addresses with the rewrite
+    /// callbacks, before anything moves. Pinned objects stay in place, and the others slide
+    /// around them.
+    ///
+    /// The heap doesn't grow or shrink in this mode, and collections never run as a Cheney scan,
+    /// see [`GCConfig::separate_passes`].
+    /// [`GCAlloc::collect_into_new`] still copies into a new heap, which is compacting too.
+    Compacting,
+}
+
+/// What the collector is doing, see [`GCAlloc::phase`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GCPhase {
+    /// No collection or incremental mark step is running.
+    #[default]
+    Idle,
+    /// The handles, root stack and root callbacks are being marked.
+    MarkingRoots,
+    /// The mark callbacks of the objects reached from the roots are running.
+    Marking,
+    /// The live objects are being moved, or their new addresses planned. A Cheney scan, see
+    /// [`GCConfig::separate_passes`], runs both the mark and rewrite callbacks of the objects it
+    /// copies in this phase, as it copies and rewrites them in a single scan.
+    Copying,
+    /// The rewrite callbacks of the moved objects, and the root rewriter, are running.
+    Rewriting,
+}
+
+/// Builds a [`GCAlloc`] from its construction-time options, see [`GCAlloc::builder`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct GCAllocBuilder {
+    size: usize,
+    config: GCConfig,
+    gc_threshold: Option<f64>,
+    max_size: Option<usize>,
+    handle_capacity: usize,
+    huge_pages: bool,
+}
+
+#[cfg(feature = "std")]
+impl GCAllocBuilder {
+    /// Size of each half of the heap, or of its single space when compacting. Defaults to
+    /// 1 MiB.
+    pub fn size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
     }
 
-    /// Get a handle to a pointer of type T.
-    pub fn get_handle<T>(&self, handle: &Handle<T>) -> Gc<T> {
-        Gc::new(self.handles[handle.key].as_ptr() as *const T)
+    /// The options of [`GCConfig`]. Options set on the builder afterwards override it.
+    pub fn config(mut self, config: GCConfig) -> Self {
+        self.config = config;
+        self
     }
 
-    /// Release a handle.
-    pub fn release_handle<T>(&mut self, handle: Handle<T>) {
-        self.handles.remove(handle.key);
+    /// See [`GCConfig::alignment`].
+    pub fn alignment(mut self, align: usize) -> Self {
+        self.config.alignment = Some(align);
+        self
     }
 
-    #[allow(clippy::not_unsafe_ptr_arg_deref)]
-    pub fn allocate_typed<T: Sized>(&mut self, vt: *const VTable, v: T) -> Option<Gc<T>> {
-        unsafe {
-            let init_gc_cnt = self.gc_count;
-            let ptr = self.allocate(vt, std::mem::size_of::<T>())?;
-            let ptr = ptr.cast();
-            (ptr.get() as *mut T).write(v);
-            // Might have gc during allocation, so we need to run the rewrite callback
-            if self.gc_count != init_gc_cnt {
-                ((*vt).rewrite_cb)(self, ptr.get() as *const u8);
-            }
-            Some(ptr)
-        }
+    /// See [`GCAlloc::set_gc_threshold`].
+    pub fn gc_threshold(mut self, fraction: f64) -> Self {
+        self.gc_threshold = Some(fraction);
+        self
     }
 
-    pub fn allocate(&mut self, vt: *const VTable, raw_sz: usize) -> Option<Gc<u8>> {
-        if self.in_gc {
-            error!("Allocation during GC");
-            return None;
-        }
+    /// Grow the heap up to halves of `max` bytes instead of running out of memory, see
+    /// [`GCAlloc::with_growable`].
+    pub fn growable(mut self, max: usize) -> Self {
+        self.max_size = Some(max);
+        self
+    }
 
-        let sz = (std::mem::size_of::<GCHeader>() + raw_sz).next_multiple_of(ALIGNMENT);
-        let available = self.chunk_size - self.from_cursor;
-        if sz > available {
-            trace!("Allocate size {} exceeds available space {}", sz, available);
-            self.collect();
+    /// Room for `cap` handles before the handle table grows, see
+    /// [`GCAlloc::with_handle_capacity`].
+    pub fn handle_capacity(mut self, cap: usize) -> Self {
+        self.handle_capacity = cap;
+        self
+    }
 
-            let available = self.chunk_size - self.from_cursor;
-            if sz > available {
-                warn!("Out of memory: No space for allocation even after GC");
-                return None;
-            }
-        }
+    /// Map the heap with 2 MiB huge pages, rounding its size up to a multiple of them. See
+    /// [`GCAlloc::with_huge_pages`].
+    #[cfg(target_os = "linux")]
+    pub fn huge_pages(mut self, huge_pages: bool) -> Self {
+        self.huge_pages = huge_pages;
+        self
+    }
 
-        let start_ptr = unsafe { self.from_half.add(self.from_cursor) };
-        let header = GCHeader {
-            vt: Cell::new(VTPtr::new(vt).into()),
-            sz,
+    /// Create the heap, returning an error if the options are invalid, the total size
+    /// overflows or the memory cannot be mapped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maximum size of a growable heap is below its size, or the GC threshold is
+    /// invalid.
+    pub fn build(self) -> Result<GCAlloc, GCInitError> {
+        let mut gc = match self.huge_pages {
+            #[cfg(target_os = "linux")]
+            true => GCAlloc::try_with_huge_pages(self.size, self.config)?,
+            _ => GCAlloc::try_with_config(self.size, self.config)?,
         };
-        trace!("Allocating {} + header bytes at {:?}", sz, start_ptr);
-
-        unsafe {
-            std::ptr::write(start_ptr as *mut GCHeader, header);
+        if let Some(max) = self.max_size {
+            assert!(
+                max >= gc.chunk_size,
+                "Maximum heap size {} is below the initial size {}",
+                max,
+                gc.chunk_size
+            );
+            gc.max_size = max;
         }
-
-        self.from_cursor += sz;
-        self.meta_total_allocated += sz;
-        self.meta_high_water_mark = self.meta_high_water_mark.max(self.from_cursor);
-        let ptr = unsafe { start_ptr.add(std::mem::size_of::<GCHeader>()) };
-
-        // Write a free block after the allocated block
-        let free_header = GCHeader {
-            vt: Cell::new(VTPtr::new_free().into()),
-            sz: available - sz,
-        };
-        let free_ptr = unsafe { start_ptr.add(sz) as *mut GCHeader };
-        trace!(
-            "Writing free block of size {} at {:?}",
-            available - sz,
-            free_ptr
-        );
-        unsafe {
-            std::ptr::write(free_ptr, free_header);
+        if let Some(fraction) = self.gc_threshold {
+            gc.set_gc_threshold(fraction);
         }
-
-        Some(Gc::new(ptr))
+        if self.handle_capacity > 0 {
+            gc.handles = SlotMap::with_capacity_and_key(self.handle_capacity);
+        }
+        Ok(gc)
     }
+}
 
-    pub fn collect(&mut self) {
-        if self.in_gc {
-            panic!("Recursive GC");
+#[cfg(feature = "std")]
+impl Default for GCAllocBuilder {
+    fn default() -> Self {
+        GCAllocBuilder {
+            size: 1 << 20,
+            config: GCConfig::default(),
+            gc_threshold: None,
+            max_size: None,
+            handle_capacity: 0,
+            huge_pages: false,
         }
+    }
+}
 
-        trace!("Starting GC");
+/// Weight of the latest collection in [`GCMeta::allocation_rate_bytes_per_gc`].
+const ALLOCATION_RATE_WEIGHT: f64 = 0.25;
 
-        self.in_gc = true;
-        self.gc_count += 1;
+/// Default for [`GCConfig::work_list_limit`].
+pub const DEFAULT_WORK_LIST_LIMIT: usize = 1 << 20;
 
-        debug!("Mark roots");
-        self.mark_roots();
+/// Why an allocation failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocError {
+    /// The heap has no room for the object, even after collecting.
+    OutOfMemory,
+    /// The allocation was attempted during a collection, outside of a rewrite callback.
+    DuringCollection,
+    /// The size of the object overflows.
+    SizeOverflow,
+}
 
-        debug!("Mark phase");
-        self.mark();
+impl core::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            AllocError::OutOfMemory => write!(f, "out of memory"),
+            AllocError::DuringCollection => write!(f, "allocation during garbage collection"),
+            AllocError::SizeOverflow => write!(f, "allocation size overflows"),
+        }
+    }
+}
 
-        debug!("Copy phase");
-        let alloc_start_size = self.copy(self.from_half, self.to_half, self.chunk_size);
+impl core::error::Error for AllocError {}
 
-        debug!("Rewrite pointers");
-        self.rewrite_ptrs(self.to_half, self.chunk_size);
-        self.rewrite_handles();
+/// Why a heap could not be created.
+#[derive(Debug)]
+pub enum GCInitError {
+    /// The two halves of the requested size don't fit in the address space.
+    SizeOverflow,
+    /// The OS failed to map the memory.
+    #[cfg(feature = "std")]
+    Map(std::io::Error),
+    /// The memory given to [`GCAlloc::with_backing`] is not aligned to 16 bytes.
+    Misaligned,
+    /// [`GCConfig::alignment`] is not a power of two, or is below the alignment of the object
+    /// header.
+    InvalidAlignment(usize),
+}
 
-        // Swap spaces
-        debug!("Swapping spaces");
-        std::mem::swap(&mut self.from_half, &mut self.to_half);
-        self.from_cursor = alloc_start_size;
-        self.in_gc = false;
-        info!("GC done");
+impl core::fmt::Display for GCInitError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GCInitError::SizeOverflow => write!(f, "heap size overflows"),
+            #[cfg(feature = "std")]
+            GCInitError::Map(err) => write!(f, "failed to map heap: {}", err),
+            GCInitError::Misaligned => write!(f, "heap backing is not aligned to 16 bytes"),
+            GCInitError::InvalidAlignment(align) => {
+                write!(f, "invalid object alignment {}", align)
+            }
+        }
     }
+}
 
-    fn mark_roots(&mut self) {
-        for handle in self.handles.values() {
-            trace!("Adding handle {:p} to work list", handle.as_ptr());
-            self.work_list.push_back(header_from_ptr(handle.as_ptr()));
+impl core::error::Error for GCInitError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            GCInitError::SizeOverflow
+            | GCInitError::Misaligned
+            | GCInitError::InvalidAlignment(_) => None,
+            #[cfg(feature = "std")]
+            GCInitError::Map(err) => Some(err),
         }
     }
+}
 
-    fn mark(&mut self) {
-        // Process work list
-        while let Some(ptr) = self.work_list.pop_front() {
-            let hdr = unsafe { ptr.as_ref().unwrap() };
-
-            if hdr.mark() {
-                continue;
-            }
-            trace!("Marking {:p}", ptr);
+/// An object a mark callback couldn't trace, see [`GCAlloc::report_trace_error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceError {
+    /// The payload of the object.
+    pub object: *const u8,
+    /// What is wrong with it.
+    pub reason: &'static str,
+}
 
-            // Call the mark callback
-            let vt = hdr.get_vt();
-            if vt.is_free() {
-                panic!("Free block in work list");
-            }
-            let vt = vt.ptr();
-            unsafe {
-                ((*vt).mark_cb)(self, ptr_from_header(ptr));
-            }
-        }
+impl core::fmt::Display for TraceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "can't trace object {:p}: {}", self.object, self.reason)
     }
+}
 
-    fn copy(&mut self, from_space: *mut u8, to_space: *mut u8, space_size: usize) -> usize {
-        // Copy phase
-        let mut to_cursor = 0;
-        let mut from_cursor = 0;
-        trace!("Copying objects");
-        while from_cursor < self.chunk_size {
-            let from_ptr = unsafe { from_space.add(from_cursor) };
-            let hdr = unsafe { (from_ptr as *const GCHeader).as_ref().unwrap() };
-            let sz = hdr.sz;
-            assert!(
-                sz >= std::mem::size_of::<GCHeader>(),
-                "Invalid size smaller than header: {}, found at {:p}",
-                sz,
-                from_ptr
-            );
+// SAFETY: The object is only an address to report, which the error never dereferences.
+unsafe impl Send for TraceError {}
+unsafe impl Sync for TraceError {}
 
-            if hdr.get_vt().is_free() {
-                trace!("Skipping free block {:p}, size {}", from_ptr, sz);
-                from_cursor += sz;
-                continue;
-            }
+impl core::error::Error for TraceError {}
 
-            let marked = hdr.get_vt().is_marked();
-            if !marked {
-                trace!("Freeing {:p} as it's not marked", from_ptr);
-                unsafe {
-                    ((*hdr.get_vt().ptr()).free_cb)(self, from_ptr);
-                }
-                from_cursor += sz;
-                continue;
-            }
+/// Why [`GCAlloc::try_collect`] failed. The heap is left as it was before the collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectError {
+    /// A callback reported an object it couldn't trace.
+    Trace(TraceError),
+}
 
-            let to_ptr = unsafe { to_space.add(to_cursor) };
-            trace!("Copying {:p} to {:p}", from_ptr, to_ptr);
-            unsafe {
-                std::ptr::copy_nonoverlapping(from_ptr, to_ptr, sz);
-            }
-            unsafe { hdr.set_fwd_ptr(ptr_from_header(to_ptr as *const GCHeader)) };
-            let to_hdr = unsafe { (to_ptr as *const GCHeader).as_ref().unwrap() };
-            to_hdr.unmark();
+impl core::fmt::Display for CollectError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CollectError::Trace(_) => write!(f, "collection abandoned while tracing"),
+        }
+    }
+}
 
-            from_cursor += sz;
-            to_cursor += sz;
+impl core::error::Error for CollectError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            CollectError::Trace(err) => Some(err),
         }
-        // Write free block at the end
-        let free_header = GCHeader {
-            vt: Cell::new(VTPtr::new_free().into()),
-            sz: space_size - to_cursor,
+    }
+}
+
+pub(crate) const ALIGNMENT: usize = 16;
+
+/// The payload of an object awaiting finalization, moved out of the heap into a buffer from the
+/// global allocator that is aligned like the object was.
+struct FinalizePayload {
+    ptr: NonNull<u8>,
+    layout: core::alloc::Layout,
+}
+
+impl FinalizePayload {
+    /// Copy the payload of the object behind `hdr` into a buffer aligned to `align`.
+    fn copy_from(hdr: &GCHeader, align: usize) -> Self {
+        let payload_sz = hdr.size() - core::mem::size_of::<GCHeader>();
+        let layout = core::alloc::Layout::from_size_align(payload_sz, align)
+            .expect("Object alignment is a power of two");
+        let ptr = if payload_sz == 0 {
+            NonNull::new(core::ptr::without_provenance_mut(align)).unwrap()
+        } else {
+            let ptr = unsafe { alloc::alloc::alloc(layout) };
+            NonNull::new(ptr).unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout))
         };
-        let free_ptr = unsafe { to_space.add(to_cursor) as *mut GCHeader };
-        trace!(
-            "Writing free block of size {} at {:?}",
-            space_size - to_cursor,
-            free_ptr
-        );
         unsafe {
-            std::ptr::write(free_ptr, free_header);
-        }
+            core::ptr::copy_nonoverlapping(ptr_from_header::<u8>(hdr), ptr.as_ptr(), payload_sz)
+        };
+        Self { ptr, layout }
+    }
+}
 
-        to_cursor
+impl Drop for FinalizePayload {
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            unsafe { alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+        }
     }
+}
 
-    fn rewrite_ptrs(&mut self, space: *mut u8, space_size: usize) {
-        // Rewrite pointers
-        trace!("Rewriting pointers");
-        let mut cursor = 0;
-        while cursor < space_size {
-            let hdr = unsafe { (space.add(cursor) as *const GCHeader).as_ref().unwrap() };
-            let sz = hdr.sz;
-            let total_sz = sz + std::mem::size_of::<GCHeader>();
+/// Granularity in which pages are committed when [`GCConfig::incremental_commit`] is set.
+const COMMIT_CHUNK: usize = 64 * 1024;
 
-            if hdr.get_vt().is_free() {
-                cursor += total_sz;
-                continue;
-            }
+/// Size of the huge pages used by [`GCAlloc::with_huge_pages`].
+#[cfg(all(target_os = "linux", feature = "std"))]
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
 
-            unsafe {
-                ((*hdr.get_vt().ptr()).rewrite_cb)(self, self.to_half.add(cursor));
-            }
+/// A growable heap grows when more than this fraction of it would be live after a collection.
+const GROW_OCCUPANCY: f64 = 0.5;
 
-            cursor += total_sz;
-        }
+/// Headers a marking thread takes from the shared queue at once, and the length its work list
+/// grows to before it shares half of it with idle threads.
+#[cfg(feature = "parallel-mark")]
+const MARK_BATCH: usize = 256;
+
+/// Offset of the second half of a heap mapping whose first half is `size` bytes. With the
+/// `debug-guard` feature, the halves are kept apart by an inaccessible guard page, so a cursor
+/// overrunning the first half faults instead of corrupting the second.
+fn second_half_offset(size: usize) -> usize {
+    if cfg!(feature = "debug-guard") {
+        size.next_multiple_of(mem::page_size()) + mem::page_size()
+    } else {
+        size
     }
+}
 
-    fn rewrite_handles(&mut self) {
-        // rewrite handles
-        for handle in self.handles.values_mut() {
-            let ptr = handle.as_ptr();
-            let header = header_from_ptr(ptr);
-            let fwd_ptr = unsafe { (*header).fwd_ptr() };
-            trace!("Rewriting handle {:p} to {:p}", ptr, fwd_ptr);
+/// Map a heap of two halves of `from_size` and `to_size` bytes, with the second one starting
+/// at [`second_half_offset`].
+#[cfg(feature = "std")]
+fn map_halves(from_size: usize, to_size: usize) -> Result<MmapMut, GCInitError> {
+    // The guard page takes at most two pages.
+    let total = from_size
+        .checked_add(2 * mem::page_size())
+        .and_then(|_| second_half_offset(from_size).checked_add(to_size))
+        .ok_or(GCInitError::SizeOverflow)?;
+    #[allow(unused_mut)]
+    let mut mmap = MmapMut::map_anon(total).map_err(GCInitError::Map)?;
+    #[cfg(feature = "debug-guard")]
+    unsafe {
+        let guard = second_half_offset(from_size) - mem::page_size();
+        mem::guard(mmap.as_mut_ptr().add(guard), mem::page_size());
+    }
+    Ok(mmap)
+}
+
+impl GCAlloc {
+    /// Create a heap with two halves of `sz` bytes each.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be mapped. See [`GCAlloc::try_new`].
+    #[cfg(feature = "std")]
+    pub fn new(sz: usize) -> Self {
+        Self::try_new(sz).expect("Failed to map GC heap")
+    }
+
+    /// Create a heap with two halves of `sz` bytes each, returning an error if the total size
+    /// overflows or the memory cannot be mapped.
+    #[cfg(feature = "std")]
+    pub fn try_new(sz: usize) -> Result<Self, GCInitError> {
+        Self::builder().size(sz).build()
+    }
+
+    /// Configure a heap option by option, instead of through one of the `with_*` constructors.
+    #[cfg(feature = "std")]
+    pub fn builder() -> GCAllocBuilder {
+        GCAllocBuilder::default()
+    }
+
+    /// Like [`GCAlloc::new`], with explicit configuration.
+    #[cfg(feature = "std")]
+    pub fn with_config(sz: usize, config: GCConfig) -> Self {
+        Self::try_with_config(sz, config).expect("Failed to map GC heap")
+    }
+
+    /// Create a heap with two halves of `sz` bytes each, whose handle table has room for `cap`
+    /// handles before it grows. This saves reallocating the table for a program that roots
+    /// many objects up front.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be mapped.
+    #[cfg(feature = "std")]
+    pub fn with_handle_capacity(sz: usize, cap: usize) -> Self {
+        Self::builder()
+            .size(sz)
+            .handle_capacity(cap)
+            .build()
+            .expect("Failed to map GC heap")
+    }
+
+    /// Create a heap with two halves of `sz` bytes each, whose objects are all aligned to
+    /// `align` bytes. See [`GCConfig::alignment`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is invalid, or if the memory cannot be mapped.
+    #[cfg(feature = "std")]
+    pub fn with_alignment(sz: usize, align: usize) -> Self {
+        Self::builder()
+            .size(sz)
+            .alignment(align)
+            .build()
+            .expect("Failed to map GC heap")
+    }
+
+    /// Like [`GCAlloc::new`], but with every page of both halves touched up front, so that
+    /// allocating and collecting never wait for the OS to fault in a page.
+    ///
+    /// This moves the cost of the page faults of the whole heap to its creation, and makes all
+    /// of it resident right away, even the parts the program never gets to use. It only pays off
+    /// for programs that care more about the latency of their first allocations and collections
+    /// than about startup time and memory use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be mapped.
+    #[cfg(feature = "std")]
+    pub fn with_prefault(sz: usize) -> Self {
+        let mut gc = Self::new(sz);
+        gc.prefault();
+        gc
+    }
+
+    /// Create a heap with two halves of `initial` bytes each, which grows up to halves of `max`
+    /// bytes instead of running out of memory.
+    ///
+    /// A collection doubles the heap when the live objects, plus the allocation that triggered
+    /// the collection, would fill more than half of it. Growing maps a fresh region and copies the
+    /// live objects into it; the old region is unmapped at the following collection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initial memory cannot be mapped, or if `max` is smaller than `initial`.
+    #[cfg(feature = "std")]
+    pub fn with_growable(initial: usize, max: usize) -> Self {
+        Self::builder()
+            .size(initial)
+            .growable(max)
+            .build()
+            .expect("Failed to map GC heap")
+    }
+
+    /// Create a heap whose halves have different sizes: `from_size` bytes for the half objects
+    /// are allocated in first, and `to_size` bytes for the other. A copying collection only
+    /// needs room in the to-space for the objects that survive, so when the live set is small
+    /// the second half can be much smaller than the first, instead of reserving twice the heap.
+    ///
+    /// The halves trade places at every collection, so the heap alternates between allocating
+    /// in the larger and the smaller one. If the survivors of a collection don't fit in the
+    /// to-space, the collection is abandoned and the heap is left as it was, so the allocation
+    /// that triggered it fails with [`AllocError::OutOfMemory`] once the from-space is full.
+    /// Objects kept in place in the to-space by pins count against its size, and as the
+    /// copies have to go around them, a to-space that is nearly full of pinned objects may
+    /// still overflow, which panics. Allocations also fail when they don't fit in what the
+    /// smaller half has left after the survivors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be mapped. See [`GCAlloc::try_with_split`].
+    #[cfg(feature = "std")]
+    pub fn with_split(from_size: usize, to_size: usize) -> Self {
+        Self::try_with_split(from_size, to_size, GCConfig::default())
+            .expect("Failed to map GC heap")
+    }
+
+    /// Like [`GCAlloc::with_split`], with explicit configuration, returning an error if the
+    /// total size overflows or the memory cannot be mapped.
+    #[cfg(feature = "std")]
+    pub fn try_with_split(
+        from_size: usize,
+        to_size: usize,
+        config: GCConfig,
+    ) -> Result<Self, GCInitError> {
+        let from_size = from_size / ALIGNMENT * ALIGNMENT;
+        let to_size = to_size / ALIGNMENT * ALIGNMENT;
+        let mmap = map_halves(from_size, to_size)?;
+        Self::from_backing(
+            Box::new(mmap),
+            from_size,
+            second_half_offset(from_size),
+            to_size,
+            config,
+        )
+    }
+
+    /// Create a heap with two halves of `sz` bytes each, rounded up to a multiple of 2 MiB,
+    /// mapped with 2 MiB huge pages. On a large heap this saves many TLB misses, both when
+    /// allocating and when collecting.
+    ///
+    /// Huge pages have to be reserved by the system administrator, e.g. through
+    /// `/proc/sys/vm/nr_hugepages`. If there aren't enough of them, a warning is logged and the
+    /// heap falls back to regular pages, so this never fails where [`GCAlloc::new`] wouldn't.
+    /// [`GCConfig::incremental_commit`] and [`GCConfig::protect_during_gc`] work on regular
+    /// pages and should be left off for a heap of huge pages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be mapped at all.
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    pub fn with_huge_pages(sz: usize) -> Self {
+        Self::builder()
+            .size(sz)
+            .huge_pages(true)
+            .build()
+            .expect("Failed to map GC heap")
+    }
+
+    /// Like [`GCAlloc::with_huge_pages`], with explicit configuration, returning an error if the
+    /// total size overflows or the memory cannot be mapped.
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    fn try_with_huge_pages(sz: usize, config: GCConfig) -> Result<Self, GCInitError> {
+        let sz = sz
+            .checked_next_multiple_of(HUGE_PAGE_SIZE)
+            .ok_or(GCInitError::SizeOverflow)?;
+        let total = match config.mode {
+            CollectorMode::Copying => sz.checked_mul(2).ok_or(GCInitError::SizeOverflow)?,
+            CollectorMode::Compacting => sz,
+        };
+        let (mmap, huge) = match memmap2::MmapOptions::new()
+            .len(total)
+            .huge(Some(HUGE_PAGE_SIZE.trailing_zeros() as u8))
+            .map_anon()
+        {
+            Ok(mmap) => (mmap, true),
+            Err(err) => {
+                debug!("Failed to map huge pages: {}", err);
+                (MmapMut::map_anon(total).map_err(GCInitError::Map)?, false)
+            }
+        };
+        let mut gc = Self::try_with_backing(mmap, config)?;
+        if !huge {
+            gc.emit(GcEvent::HugePagesUnavailable);
+        }
+        Ok(gc)
+    }
+
+    /// Like [`GCAlloc::try_new`], with explicit configuration.
+    #[cfg(feature = "std")]
+    pub fn try_with_config(sz: usize, config: GCConfig) -> Result<Self, GCInitError> {
+        let sz = sz / ALIGNMENT * ALIGNMENT;
+        let to_size = match config.mode {
+            CollectorMode::Copying => sz,
+            CollectorMode::Compacting => 0,
+        };
+        let mmap = map_halves(sz, to_size)?;
+        Self::from_backing(Box::new(mmap), sz, second_half_offset(sz), to_size, config)
+    }
+
+    /// Create a heap in the memory of `backing`, split into two halves, instead of mapping
+    /// memory from the OS.
+    ///
+    /// [`GCConfig::incremental_commit`], [`GCConfig::protect_during_gc`] and
+    /// [`GCAlloc::set_return_memory`] work on whole pages, so they should only be used with a
+    /// backing that is page-aligned and doesn't share its pages with other data. Incremental
+    /// commit and returning memory also discard pages with `madvise`, so for them the backing
+    /// must be private anonymous memory, not a file or shared mapping. A heap that grows maps
+    /// the larger heap from the OS; without std, it keeps its size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backing is not aligned to 16 bytes. See [`GCAlloc::try_with_backing`].
+    pub fn with_backing(backing: impl HeapBacking + Send + 'static, config: GCConfig) -> Self {
+        Self::try_with_backing(backing, config).expect("Invalid GC heap backing")
+    }
+
+    /// Like [`GCAlloc::with_backing`], returning an error if the backing is misaligned.
+    pub fn try_with_backing(
+        backing: impl HeapBacking + Send + 'static,
+        config: GCConfig,
+    ) -> Result<Self, GCInitError> {
+        if config.mode == CollectorMode::Compacting {
+            let sz = backing.len() / ALIGNMENT * ALIGNMENT;
+            return Self::from_backing(Box::new(backing), sz, sz, 0, config);
+        }
+        let sz = backing.len() / 2 / ALIGNMENT * ALIGNMENT;
+        Self::from_backing(Box::new(backing), sz, sz, sz, config)
+    }
+
+    /// Create a heap with a from-space of `from_size` bytes at the start of `backing`, and a
+    /// to-space of `to_size` bytes `to_offset` bytes into it. All three must be multiples of
+    /// [`ALIGNMENT`].
+    fn from_backing(
+        mut backing: Box<dyn HeapBacking + Send>,
+        from_size: usize,
+        to_offset: usize,
+        to_size: usize,
+        config: GCConfig,
+    ) -> Result<Self, GCInitError> {
+        if let Some(align) = config.alignment {
+            if !align.is_power_of_two() || align < core::mem::align_of::<GCHeader>() {
+                return Err(GCInitError::InvalidAlignment(align));
+            }
+        }
+        let ptr = backing.as_mut_ptr();
+        if !(ptr as usize).is_multiple_of(ALIGNMENT) {
+            return Err(GCInitError::Misaligned);
+        }
+        let sz = from_size;
+        let from_half = ptr;
+        let to_half = unsafe { ptr.add(to_offset) };
+
+        let mut gc = GCAlloc {
+            _backing: backing,
+            config,
+            from_half,
+            to_half,
+            from_cursor: 0,
+            old_end: 0,
+            from_committed: 0,
+            to_committed: 0,
+            chunk_size: sz,
+            max_size: sz.max(to_size),
+            shrink_policy: None,
+            low_occupancy_collections: 0,
+            retired_backing: None,
+            to_size,
+            return_memory: false,
+            to_space_stale: false,
+            in_gc: false,
+            phase: GCPhase::Idle,
+            incremental_marking: false,
+            minor_gc: false,
+            promoted: None,
+            remembered: BTreeSet::new(),
+            rewrite_cursor: None,
+            no_collect: false,
+            gc_threshold: 1.0,
+            tenure_threshold: 1,
+            #[cfg(feature = "parallel-mark")]
+            mark_threads: 1,
+            #[cfg(all(target_os = "linux", feature = "std"))]
+            protection: None,
+            work_list: VecDeque::new(),
+            mark_bits: None,
+            scanning: false,
+            scan_marking: false,
+            scan_skip: Vec::new(),
+            deferred_weak: Vec::new(),
+            bounded_marking: false,
+            work_list_overflowed: false,
+            fallible_trace: false,
+            trace_error: None,
+            finalize_queue: Vec::new(),
+            handles: SlotMap::with_key(),
+            handle_sets: SlotMap::with_key(),
+            root_stack: Vec::new(),
+            weak_handles: SlotMap::with_key(),
+            pins: SlotMap::with_key(),
+            unpinned: Vec::new(),
+            pinned: Vec::new(),
+            conservative_roots: Vec::new(),
+            stack_bottom: None,
+            large: LargeObjects::default(),
+            before_collect: Vec::new(),
+            after_collect: Vec::new(),
+            #[cfg(feature = "log")]
+            observer: Box::new(crate::observer::LogObserver),
+            #[cfg(not(feature = "log"))]
+            observer: Box::new(crate::observer::NoObserver),
+            root_enumerator: None,
+            frame_walker: None,
+            stack_maps: BTreeMap::new(),
+            root_rewriter: None,
+            #[cfg(feature = "compressed-ptrs")]
+            compress_base: ptr,
+            #[cfg(feature = "compressed-ptrs")]
+            prev_compress_base: ptr,
+            move_observer: None,
+            oom_handler: None,
+            retrying: Vec::new(),
+
+            gc_count: 0,
+            epoch: 0,
+            minor_gc_count: 0,
+            meta_total_allocated: 0,
+            #[cfg(feature = "stats")]
+            meta_type_stats: alloc::collections::BTreeMap::new(),
+            meta_high_water_mark: 0,
+            meta_last_reclaimed: 0,
+            meta_last_survivors: 0,
+            meta_live_bytes: 0,
+            meta_occupancy: 0.0,
+            meta_allocated_at_gc: 0,
+            meta_allocation_rate: 0.0,
+            meta_total_gc_time: Duration::ZERO,
+            meta_last_gc_time: Duration::ZERO,
+            meta_last_phase_timings: PhaseTimings::default(),
+            phase_start: Instant::now(),
+            meta_peak_work_list: 0,
+        };
+        // The whole space starts out as one free block.
+        if sz >= core::mem::size_of::<GCHeader>() {
+            gc.commit_from_space(core::mem::size_of::<GCHeader>());
+            unsafe { write_free_block(from_half, sz) };
+        }
+        Ok(gc)
+    }
+
+    pub fn metadata(&self) -> GCMeta {
+        GCMeta {
+            currently_allocated: self.from_cursor,
+            gc_count: self.gc_count,
+            minor_gc_count: self.minor_gc_count,
+            total_allocated: self.meta_total_allocated,
+            high_water_mark: self.meta_high_water_mark,
+            committed_pages: (self.from_committed + self.to_committed).div_ceil(mem::page_size()),
+            last_reclaimed: self.meta_last_reclaimed,
+            last_survivors: self.meta_last_survivors,
+            live_bytes: self.meta_live_bytes,
+            occupancy: self.meta_occupancy,
+            allocation_rate_bytes_per_gc: self.meta_allocation_rate,
+            large_allocated: self.large.bytes(),
+            total_gc_time: self.meta_total_gc_time,
+            last_gc_time: self.meta_last_gc_time,
+            last_phase_timings: self.meta_last_phase_timings,
+            peak_work_list: self.meta_peak_work_list,
+            work_list_capacity: self.work_list.capacity(),
+            semispace_size: self.chunk_size,
+        }
+    }
+
+    /// Bytes allocated since the end of the last collection, counted like
+    /// [`GCMeta::total_allocated`].
+    pub fn bytes_allocated_since_last_gc(&self) -> usize {
+        self.meta_total_allocated - self.meta_allocated_at_gc
+    }
+
+    /// Bytes of the semispace in use, as in [`GCMeta::currently_allocated`].
+    pub fn used_bytes(&self) -> usize {
+        self.from_cursor
+    }
+
+    /// Bytes of the semispace left for allocation before the next collection. Large objects are
+    /// allocated elsewhere and don't take from this.
+    pub fn free_bytes(&self) -> usize {
+        self.chunk_size - self.from_cursor
+    }
+
+    /// What the collector is doing, e.g. for callbacks to check that they run when expected.
+    /// Outside of a collection this is [`GCPhase::Idle`], including while an incremental mark
+    /// is paused between [`GCAlloc::mark_step`] calls.
+    pub fn phase(&self) -> GCPhase {
+        self.phase
+    }
+
+    /// The number of objects and bytes of heap allocated for each vtable since the heap was
+    /// created or reset, sorted by vtable address. The bytes include headers and alignment
+    /// padding, as in [`GCMeta::total_allocated`].
+    #[cfg(feature = "stats")]
+    pub fn type_stats(&self) -> Vec<(*const VTable, usize, usize)> {
+        self.meta_type_stats
+            .iter()
+            .map(|(&vt, &(count, bytes))| (vt, count, bytes))
+            .collect()
+    }
+
+    /// Register a hook that runs at the start of every collection, with the metadata of the heap
+    /// before anything is freed. Hooks run in registration order.
+    ///
+    /// Hooks only see the metadata, not the heap, so they can't allocate from it; they must not
+    /// reach the heap through other means either.
+    pub fn on_before_collect(&mut self, hook: Box<dyn FnMut(&GCMeta) + Send>) {
+        self.before_collect.push(hook);
+    }
+
+    /// Register a hook that runs at the end of every collection, with the metadata of the heap
+    /// after it, including [`GCMeta::last_reclaimed`]. It runs before the finalizers of the dead
+    /// objects. The same restrictions as for [`GCAlloc::on_before_collect`] apply.
+    pub fn on_after_collect(&mut self, hook: Box<dyn FnMut(&GCMeta) + Send>) {
+        self.after_collect.push(hook);
+    }
+
+    /// Register a handler for allocations that run out of memory even after collecting. It is
+    /// called with the size requested, and can e.g. release caches held by handles or roots. If
+    /// it returns true, the allocation is tried once more, collecting again if needed;
+    /// otherwise it fails with [`AllocError::OutOfMemory`].
+    ///
+    /// [`GCAlloc::allocate_typed`] keeps the value being allocated alive through the handler and
+    /// the retry. [`GCAlloc::allocate_with`] and [`GCAlloc::allocate_slice`] don't run the
+    /// handler, and neither do allocations from callbacks during a collection.
+    pub fn set_oom_handler(
+        &mut self,
+        handler: impl FnMut(&mut GCAlloc, usize) -> bool + Send + 'static,
+    ) {
+        self.oom_handler = Some(Box::new(handler));
+    }
+
+    /// Run the OOM handler for an allocation of `size` bytes, and return whether it asks for a
+    /// retry.
+    fn handle_oom(&mut self, size: usize) -> bool {
+        if self.in_gc || self.no_collect {
+            return false;
+        }
+        let Some(mut handler) = self.oom_handler.take() else {
+            return false;
+        };
+        let retry = handler(self, size);
+        if self.oom_handler.is_none() {
+            self.oom_handler = Some(handler);
+        }
+        retry
+    }
+
+    /// Send the events of the heap to `observer` instead of the default one, which writes them to
+    /// the `log` crate with the `log` feature and drops them without it.
+    pub fn set_observer(&mut self, observer: impl GcObserver + Send + 'static) {
+        self.observer = Box::new(observer);
+    }
+
+    /// Register a callback reporting roots the heap has no handle for, e.g. pointers in a
+    /// native stack or a global table. It runs at the start of every collection, and should call
+    /// [`GCAlloc::mark_accessible`] or [`GCAlloc::mark_raw`] on each of them.
+    ///
+    /// Objects reported this way still move, so their slots must be updated by the callback
+    /// given to [`GCAlloc::set_root_rewriter`].
+    pub fn set_root_enumerator(&mut self, enumerate: Box<dyn FnMut(&mut GCAlloc) + Send>) {
+        self.root_enumerator = Some(enumerate);
+    }
+
+    /// Register a callback updating the roots reported by the root enumerator. It runs once
+    /// the objects have moved, and should call [`GCAlloc::rewrite_ptr`] or
+    /// [`GCAlloc::rewrite_raw`] on each of them.
+    pub fn set_root_rewriter(&mut self, rewrite: Box<dyn FnMut(&mut GCAlloc) + Send>) {
+        self.root_rewriter = Some(rewrite);
+    }
+
+    /// Register a callback walking the frames of the mutator's stack, for code that emits stack
+    /// maps. In every collection, the slots of each frame with a stack map, see
+    /// [`GCAlloc::register_stack_map`], are marked as roots and rewritten once their objects
+    /// have moved. Frames without one, e.g. those of native code, are skipped.
+    ///
+    /// The frames must stay suspended, and their slots valid, until the collection is done.
+    pub fn set_frame_walker(&mut self, walker: FrameWalker) {
+        self.frame_walker = Some(walker);
+    }
+
+    /// Register the stack map of the frames returning to `return_address`, replacing the one
+    /// registered before.
+    pub fn register_stack_map(&mut self, return_address: usize, map: StackMap) {
+        self.stack_maps.insert(return_address, map);
+    }
+
+    /// The non-null slots of the frames walked by the frame walker.
+    fn stack_map_slots(&mut self) -> Vec<*mut *const u8> {
+        let Some(mut walker) = self.frame_walker.take() else {
+            return Vec::new();
+        };
+        let mut slots = Vec::new();
+        let stack_maps = &self.stack_maps;
+        walker(&mut |frame| {
+            let Some(map) = stack_maps.get(&frame.return_address) else {
+                return;
+            };
+            for &offset in map.slots() {
+                let slot = frame.frame_pointer.wrapping_offset(offset) as *mut *const u8;
+                if !unsafe { slot.read() }.is_null() {
+                    slots.push(slot);
+                }
+            }
+        });
+        self.frame_walker = Some(walker);
+        slots
+    }
+
+    /// Run the root enumerator and mark the stack map slots and the values waiting for a retried
+    /// allocation, or the root rewriter and rewrite them if `rewrite` is set.
+    fn run_root_callback(&mut self, rewrite: bool) {
+        // Roots can't be rescanned, so none of them may be dropped from a full work list.
+        let bounded = core::mem::replace(&mut self.bounded_marking, false);
+        for slot in self.stack_map_slots() {
+            if rewrite {
+                self.rewrite_raw(unsafe { &mut *slot });
+            } else {
+                self.mark_raw(unsafe { slot.read() });
+            }
+        }
+        for (vt, obj) in self.retrying.clone() {
+            if rewrite {
+                unsafe { self.rewrite_fields(vt, obj) };
+            } else {
+                unsafe { self.mark_fields(vt, obj) };
+            }
+        }
+        self.bounded_marking = bounded;
+
+        let slot = if rewrite {
+            &mut self.root_rewriter
+        } else {
+            &mut self.root_enumerator
+        };
+        let Some(mut callback) = slot.take() else {
+            return;
+        };
+        // Roots can't be rescanned, so none of them may be dropped from a full work list.
+        let bounded = core::mem::replace(&mut self.bounded_marking, false);
+        callback(self);
+        self.bounded_marking = bounded;
+        if rewrite {
+            self.root_rewriter = Some(callback);
+        } else {
+            self.root_enumerator = Some(callback);
+        }
+    }
+
+    /// Register a callback for every object a collection moves, called with the old and the new
+    /// address of its payload, so tables caching raw object addresses outside of the heap can be
+    /// patched. The object may not have been copied yet when it is called, and the callback must
+    /// not touch the heap.
+    pub fn set_move_observer(
+        &mut self,
+        observer: impl FnMut(*const u8, *const u8) + Send + 'static,
+    ) {
+        self.move_observer = Some(Box::new(observer));
+    }
+
+    /// Report that the object at `from` moves to `to`.
+    fn report_move(&mut self, from: *const GCHeader, to: *const GCHeader, size: usize) {
+        self.emit(GcEvent::ObjectCopied { from, to, size });
+        if let Some(observer) = &mut self.move_observer {
+            observer(ptr_from_header(from), ptr_from_header(to));
+        }
+    }
+
+    fn emit(&mut self, event: GcEvent) {
+        self.observer.event(event);
+    }
+
+    /// Report the end of a collection, once its metadata is recorded.
+    fn emit_finished(&mut self, minor: bool) {
+        self.emit(GcEvent::CollectFinished {
+            minor,
+            reclaimed: self.meta_last_reclaimed,
+            survivors: self.meta_last_survivors,
+            time: self.meta_last_gc_time,
+        });
+    }
+
+    fn run_hooks(&mut self, after: bool) {
+        let meta = self.metadata();
+        let hooks = if after {
+            &mut self.after_collect
+        } else {
+            &mut self.before_collect
+        };
+        for hook in hooks {
+            hook(&meta);
+        }
+    }
+
+    /// Acquire a handle to a pointer of type T. The pointer must be allocated
+    /// by [`GCAlloc::allocate`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pointer is misaligned, or doesn't point into the from-space, a pinned
+    /// object or a large object of this heap.
+    pub fn acquire_handle<T>(&mut self, ptr: Gc<T>) -> Handle<T> {
+        let ptr = ptr.get();
+        self.check_root("Handle", ptr as *const u8);
+        let key = self.handles.insert(NonNull::new(ptr as *mut u8).unwrap());
+        Handle {
+            key,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Check that `ptr`, about to be held by a root of the given `kind`, points to an object of
+    /// this heap aligned like every object in it.
+    fn check_root(&self, kind: &str, ptr: *const u8) {
+        assert!(
+            (ptr as usize).is_multiple_of(self.alignment()),
+            "{} to misaligned pointer {:p}",
+            kind,
+            ptr
+        );
+        assert!(
+            self.contains(ptr),
+            "{} to {:p}, which is not an object of this heap (from-space is {:p}..{:p})",
+            kind,
+            ptr,
+            self.from_half,
+            self.from_half.wrapping_add(self.chunk_size)
+        );
+    }
+
+    /// Get a handle to a pointer of type T.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle has been released, e.g. along with its scope or handle set. Keys
+    /// are versioned, so a released handle never resolves to an object whose handle took its
+    /// slot in the table, unless the table was compacted in the meantime, see
+    /// [`GCAlloc::compact_handles`].
+    pub fn get_handle<T>(&self, handle: &Handle<T>) -> Gc<T> {
+        self.try_get_handle(handle).expect("Handle is released")
+    }
+
+    /// Like [`GCAlloc::get_handle`], but returns `None` for a released handle.
+    pub fn try_get_handle<T>(&self, handle: &Handle<T>) -> Option<Gc<T>> {
+        let ptr = self.handles.get(handle.key)?;
+        Some(Gc::new(ptr.as_ptr() as *const T))
+    }
+
+    /// Get the object of an untyped handle as a `T`, if the type id of its vtable is
+    /// `expected`. Objects with the same type id must all be of type `T`.
+    pub fn downcast_handle<T>(&self, handle: &Handle<u8>, expected: u32) -> Option<Gc<T>> {
+        let ptr = self.get_handle(handle);
+        let vt = unsafe { (*header_from_ptr(ptr.get())).get_vt().ptr() };
+        (unsafe { (*vt).type_id } == expected).then(|| unsafe { ptr.cast() })
+    }
+
+    /// Release a handle. A handle already released along with its scope or handle set is
+    /// left alone.
+    pub fn release_handle<T>(&mut self, handle: Handle<T>) {
+        self.handles.remove(handle.key);
+    }
+
+    /// Acquire a handle to each of `ptrs`, in order, growing the handle table at most once.
+    /// See [`GCAlloc::acquire_handle`].
+    pub fn acquire_handles<T>(&mut self, ptrs: &[Gc<T>]) -> Vec<Handle<T>> {
+        self.handles.reserve(ptrs.len());
+        ptrs.iter()
+            .map(|ptr| self.acquire_handle(ptr.clone()))
+            .collect()
+    }
+
+    /// Release each of `handles`, see [`GCAlloc::release_handle`].
+    pub fn release_handles<T>(&mut self, handles: Vec<Handle<T>>) {
+        for handle in handles {
+            self.release_handle(handle);
+        }
+    }
+
+    /// Borrow the object `ptr` points to. The reference borrows the heap, so the object can't be
+    /// moved by a collection while it is in use.
+    ///
+    /// `ptr` must point to a live object of type `T` in this heap, e.g. one just taken out of a
+    /// handle or another live object. A `Gc` kept across a collection without being rewritten is
+    /// stale, and must not be passed here.
+    ///
+    /// The borrow only protects the returned reference, not the `Gc` passed in, which safe code
+    /// can make from any address with [`Gc::new`] or [`Gc::dangling`]. So `ptr` is checked as by
+    /// [`GCAlloc::assert_live`], which catches pointers that are misaligned, outside the heap or
+    /// into a free block, as stale pointers often are. It can't tell the type of an object or
+    /// whether it is still reachable, so reading through a `Gc` with the wrong type, or to an
+    /// object that is dead but not yet collected, is still undefined behavior.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ptr` fails the checks of [`GCAlloc::assert_live`].
+    pub fn get<T>(&self, ptr: Gc<T>) -> &T {
+        self.verify_pointer(core::ptr::null(), header_from_ptr(ptr.get()));
+        unsafe { &*ptr.get() }
+    }
+
+    /// Like [`GCAlloc::get`], but borrows the object mutably.
+    pub fn get_mut<T>(&mut self, ptr: Gc<T>) -> &mut T {
+        self.verify_pointer(core::ptr::null(), header_from_ptr(ptr.get()));
+        unsafe { &mut *(ptr.get() as *mut T) }
+    }
+
+    /// Borrow the object held by a handle, like [`GCAlloc::get`] on [`GCAlloc::get_handle`].
+    pub fn read<'a, T>(&'a self, handle: &Handle<T>) -> &'a T {
+        self.get(self.get_handle(handle))
+    }
+
+    /// Like [`GCAlloc::read`], but borrows the object mutably.
+    pub fn read_mut<'a, T>(&'a mut self, handle: &Handle<T>) -> &'a mut T {
+        let ptr = self.get_handle(handle);
+        self.get_mut(ptr)
+    }
+
+    /// Run `f` with the heap borrowed immutably, and return its result. Nothing that can
+    /// collect, allocate or release a handle is reachable through the borrow, so every reference
+    /// taken with [`GCAlloc::get`] or [`GCAlloc::read`] inside stays valid until `f` returns.
+    ///
+    /// Collecting inside the block doesn't compile:
+    ///
+    /// ```compile_fail
+    /// let mut gc = ike_gc::GCAlloc::new(4096);
+    /// gc.with(|heap| heap.collect());
+    /// ```
+    pub fn with<R>(&self, f: impl FnOnce(&GCAlloc) -> R) -> R {
+        f(self)
+    }
+
+    /// Keep `ptr` outside of the roots until the next collection, see [`Unrooted`].
+    pub fn unrooted<T>(&self, ptr: Gc<T>) -> Unrooted<T> {
+        Unrooted {
+            ptr,
+            epoch: self.epoch,
+        }
+    }
+
+    /// The pointer kept by `ptr`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heap has collected or been reset since `ptr` was made, as its object may
+    /// have moved or died. Minor collections count, even though they leave the old generation
+    /// in place.
+    pub fn get_unrooted<T>(&self, ptr: &Unrooted<T>) -> Gc<T> {
+        assert!(
+            ptr.epoch == self.epoch,
+            "Unrooted pointer {:p} used after a collection",
+            ptr.ptr.get()
+        );
+        ptr.ptr.clone()
+    }
+
+    /// Release the pages of the to-space back to the OS after each collection, so the memory
+    /// held by the heap drops towards the size of the live set instead of staying at twice it.
+    /// The released pages are faulted back in when the next collection copies into them, which
+    /// costs time, so this is off by default. Only use this with a heap backing that is private
+    /// anonymous memory, see [`GCAlloc::with_backing`].
+    ///
+    /// After a collection triggered by an allocation, the to-space still holds the forward
+    /// pointers the caller rewrites its pointers through, so it is only released at the next
+    /// allocation. After [`GCAlloc::collect`], it is released straight away. Objects kept in
+    /// place by pins are left alone.
+    pub fn set_return_memory(&mut self, return_memory: bool) {
+        self.return_memory = return_memory;
+    }
+
+    /// Move the heap into a smaller mapping once the survivors of several collections in a row
+    /// take up only a small part of it, so that the memory of a spike in allocation is given
+    /// back. `None`, the default, keeps the heap at its size.
+    ///
+    /// Shrinking copies the live objects into the new mapping during a collection, as growing
+    /// does, and is skipped while objects are pinned. A shrunk heap grows back as needed up to
+    /// its former size, or the maximum size of a growable heap.
+    pub fn set_shrink_policy(&mut self, policy: Option<ShrinkPolicy>) {
+        self.shrink_policy = policy;
+        self.low_occupancy_collections = 0;
+    }
+
+    /// Collect before allocating once more than `fraction` of the from-space is in use, instead
+    /// of waiting for an allocation that doesn't fit. The default of 1.0 only collects when the
+    /// heap is full.
+    ///
+    /// If the live data alone exceeds the threshold, every allocation collects, so the fraction
+    /// should leave room for the expected live set.
+    pub fn set_gc_threshold(&mut self, fraction: f64) {
+        assert!(
+            fraction > 0.0 && fraction <= 1.0,
+            "GC threshold {} is not in (0, 1]",
+            fraction
+        );
+        self.gc_threshold = fraction;
+    }
+
+    /// Promote nursery objects to the old generation once they have survived `collections`
+    /// minor collections, instead of after the first one. Objects that are short-lived, but not
+    /// quite enough to die before the next minor collection, then die in the nursery instead of
+    /// filling up the old generation.
+    ///
+    /// Young survivors are slid down along with the promoted ones, so the old generation only
+    /// extends up to the first of them. Old objects are remembered for as long as young
+    /// survivors are left, in case they point to them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `collections` is 0 or above 3, the largest age an object header can hold.
+    pub fn set_tenure_threshold(&mut self, collections: u8) {
+        assert!(
+            (1..=MAX_AGE).contains(&collections),
+            "Tenure threshold {} is not in 1..={}",
+            collections,
+            MAX_AGE
+        );
+        self.tenure_threshold = collections;
+    }
+
+    /// Collect if more than the fraction of the from-space set by
+    /// [`GCAlloc::set_gc_threshold`] is in use, and return whether it did. Call this at safepoints
+    /// to collect where it suits the program, rather than in whichever allocation crosses the
+    /// threshold.
+    pub fn collect_if_needed(&mut self) -> bool {
+        if !self.over_threshold() {
+            return false;
+        }
+        debug!(
+            "Heap usage {} exceeds threshold {}",
+            self.from_cursor, self.gc_threshold
+        );
+        self.collect();
+        true
+    }
+
+    fn over_threshold(&self) -> bool {
+        self.from_cursor as f64 > self.gc_threshold * self.chunk_size as f64
+    }
+
+    /// Mark on `threads` threads, the calling one included. Each collection spawns the other
+    /// threads for its mark phase. The default of 1 marks on the calling thread alone.
+    ///
+    /// The mark callbacks of the other threads are called with a heap of their own, which only
+    /// supports [`GCAlloc::mark_accessible`], [`GCAlloc::mark_raw`] and
+    /// [`GCAlloc::report_trace_error`]. Full collections then use separate passes instead of a
+    /// Cheney scan, see [`GCConfig::separate_passes`]. Marking stays serial in minor collections,
+    /// and heaps with [`GCConfig::side_mark_bits`] or [`GCConfig::protect_during_gc`]. The work
+    /// lists are not bounded by [`GCConfig::work_list_limit`] while marking in parallel.
+    ///
+    /// # Safety
+    ///
+    /// The mark callbacks of every object in the heap must be safe to call from several threads
+    /// at once, on different objects, and must not do anything but mark.
+    #[cfg(feature = "parallel-mark")]
+    pub unsafe fn set_mark_threads(&mut self, threads: usize) {
+        assert!(threads > 0, "Marking needs at least one thread");
+        self.mark_threads = threads;
+    }
+
+    /// Number of handles currently held.
+    pub fn handle_count(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Call `f` with the key and object of every handle currently held, e.g. to find roots that
+    /// should have been released.
+    pub fn for_each_handle(&self, mut f: impl FnMut(HandleKey, *const u8)) {
+        for (key, ptr) in &self.handles {
+            f(key, ptr.as_ptr());
+        }
+    }
+
+    /// Number of handles the handle table has room for without growing. The table reuses the
+    /// slots of released handles, but never shrinks on its own.
+    pub fn handle_capacity(&self) -> usize {
+        self.handles.capacity()
+    }
+
+    /// Free the memory of the handle table, if no handles are held. Tables with live handles
+    /// can't be compacted, as that would change their keys.
+    ///
+    /// Keys are reused afterwards, so handles that were rooted in a dropped [`HandleScope`] must
+    /// not be used at all: they may resolve to another object instead of panicking.
+    pub fn compact_handles(&mut self) {
+        if self.handles.is_empty() {
+            self.handles = SlotMap::with_key();
+        } else {
+            debug!(
+                "Not compacting handle table with {} live handles",
+                self.handles.len()
+            );
+        }
+    }
+
+    /// Push a root onto the shadow stack, keeping its object alive until it is popped. This is a
+    /// cheaper alternative to [`GCAlloc::acquire_handle`] for roots with a stack discipline, such
+    /// as the locals of interpreter frames.
+    ///
+    /// The returned index stays valid until the root is popped.
+    pub fn push_root<T>(&mut self, ptr: Gc<T>) -> RootIndex<T> {
+        let ptr = ptr.get();
+        self.check_root("Root", ptr as *const u8);
+        self.root_stack.push(NonNull::new(ptr as *mut u8).unwrap());
+        RootIndex {
+            index: self.root_stack.len() - 1,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Pop the top `n` roots off the shadow stack.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stack holds fewer than `n` roots.
+    pub fn pop_root(&mut self, n: usize) {
+        let len = self.root_stack.len();
+        assert!(n <= len, "Popping {} roots off a stack of {}", n, len);
+        self.root_stack.truncate(len - n);
+    }
+
+    /// Get the object a root on the shadow stack refers to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the root has been popped.
+    pub fn get_root<T>(&self, root: RootIndex<T>) -> Gc<T> {
+        Gc::new(self.root_stack[root.index].as_ptr() as *const T)
+    }
+
+    /// Number of roots on the shadow stack.
+    pub fn root_stack_len(&self) -> usize {
+        self.root_stack.len()
+    }
+
+    /// Pin an object: like [`GCAlloc::acquire_handle`], it stays alive until unpinned, and in
+    /// addition it never moves, so its address can be handed to code outside of the collector's
+    /// control.
+    ///
+    /// Pinned objects are left in place by collections, which copy the other objects around
+    /// them. Allocation always continues after the last pinned object, and the space around
+    /// pinned objects is not reused until they are unpinned, so many long-lived pins defeat
+    /// compaction. While any object is pinned, a growable heap doesn't grow. A pinned object may
+    /// lie outside of the from-space, so [`GCAlloc::in_young_gen`] can return `false` for it.
+    pub fn pin<T>(&mut self, ptr: Gc<T>) -> PinnedHandle<T> {
+        let ptr = ptr.get();
+        self.check_root("Pin", ptr as *const u8);
+        let key = self.pins.insert(NonNull::new(ptr as *mut u8).unwrap());
+        PinnedHandle {
+            key,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Get the object a pinned handle refers to.
+    pub fn get_pinned<T>(&self, handle: &PinnedHandle<T>) -> Gc<T> {
+        Gc::new(self.pins[handle.key].as_ptr() as *const T)
+    }
+
+    /// Unpin an object, allowing it to move and die again.
+    pub fn unpin<T>(&mut self, handle: PinnedHandle<T>) {
+        if let Some(ptr) = self.pins.remove(handle.key) {
+            if self.in_to_space(ptr.as_ptr()) {
+                self.unpinned.push(ptr);
+            }
+        }
+    }
+
+    /// Create an empty handle set. Handles acquired in it with [`GCAlloc::acquire_handle_in`]
+    /// can all be released at once by [`GCAlloc::release_handle_set`], e.g. the roots of one
+    /// tenant of the heap.
+    pub fn new_handle_set(&mut self) -> HandleSetId {
+        self.handle_sets.insert(Vec::new())
+    }
+
+    /// Like [`GCAlloc::acquire_handle`], but adds the handle to `set`. The handle can still be
+    /// released on its own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set has been released, or for the same reasons as
+    /// [`GCAlloc::acquire_handle`].
+    pub fn acquire_handle_in<T>(&mut self, set: HandleSetId, ptr: Gc<T>) -> Handle<T> {
+        assert!(self.handle_sets.contains_key(set), "Handle set is released");
+        let handle = self.acquire_handle(ptr);
+        self.handle_sets[set].push(handle.key);
+        handle
+    }
+
+    /// Release a handle set and every handle in it.
+    pub fn release_handle_set(&mut self, set: HandleSetId) {
+        for key in self.handle_sets.remove(set).into_iter().flatten() {
+            self.handles.remove(key);
+        }
+    }
+
+    /// Open a scope whose handles are released when it is dropped. The scope dereferences to the
+    /// heap, so it can be used for allocating and collecting in the meantime.
+    pub fn scope(&mut self) -> HandleScope<'_> {
+        HandleScope {
+            gc: self,
+            keys: vec![],
+        }
+    }
+
+    /// Allocate an object whose vtable is generated from its [`Trace`] implementation.
+    pub fn alloc<T: Trace>(&mut self, v: T) -> Result<Gc<T>, AllocError> {
+        // Make sure the vtable is promoted to a static, as the heap keeps pointing to it.
+        let vt: &'static VTable = &T::VTABLE;
+        self.allocate_typed(vt, v)
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn allocate_typed<T: Sized>(
+        &mut self,
+        vt: *const VTable,
+        v: T,
+    ) -> Result<Gc<T>, AllocError> {
+        let init_gc_cnt = self.gc_count;
+        let mut v = Some(v);
+        match self.allocate_with(vt, |ptr: *mut T| unsafe { ptr.write(v.take().unwrap()) }) {
+            Err(AllocError::OutOfMemory) if self.oom_handler.is_some() => {}
+            result => return result,
+        }
+        let v = v.unwrap();
+        let obj = &v as *const T as *const u8;
+        // Catch the pointers in the value up with the collection that ran out of memory, then
+        // keep them rooted, and up to date, through the collections of the handler and the
+        // retry.
+        if self.gc_count != init_gc_cnt {
+            self.no_collect = true;
+            unsafe { self.rewrite_fields(vt, obj) };
+            self.no_collect = false;
+        }
+        self.retrying.push((vt, obj));
+        let ptr = if self.handle_oom(core::mem::size_of::<T>()) {
+            self.allocate_raw(vt, core::mem::size_of::<T>(), false)
+        } else {
+            Err(AllocError::OutOfMemory)
+        };
+        self.retrying.pop();
+        unsafe {
+            let ptr = ptr?.cast::<T>();
+            (ptr.get() as *mut T).write(v);
+            Ok(ptr)
+        }
+    }
+
+    /// Allocate an object of type `T` and initialize it in place, without building it on the
+    /// stack first. `init` gets the zeroed slot and must leave a valid `T` in it.
+    ///
+    /// Pointers `init` writes into the object are rewritten afterwards if reserving the slot
+    /// collected, so they may come from before the call. They can only be caught up with one
+    /// collection, so the handler set by [`GCAlloc::set_oom_handler`] isn't run.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn allocate_with<T: Sized>(
+        &mut self,
+        vt: *const VTable,
+        init: impl FnOnce(*mut T),
+    ) -> Result<Gc<T>, AllocError> {
+        VTPtr::check(vt);
+        unsafe {
+            assert!(
+                core::mem::align_of::<T>() <= self.object_align(vt),
+                "Alignment of vtable {:p} is too small for the type",
+                vt
+            );
+            let init_gc_cnt = self.gc_count;
+            let ptr = self.allocate_raw(vt, core::mem::size_of::<T>(), false)?;
+            let ptr = ptr.cast();
+            init(ptr.get() as *mut T);
+            // Might have gc during allocation, so we need to run the rewrite callback
+            if self.gc_count != init_gc_cnt {
+                self.no_collect = true;
+                self.rewrite_fields(vt, ptr.get() as *const u8);
+                self.no_collect = false;
+            }
+            Ok(ptr)
+        }
+    }
+
+    /// Allocate an array of `len` elements of type `T`. The length is stored in the object, and
+    /// the elements are left uninitialized.
+    ///
+    /// The vtable should use [`GcArray::SIZE`] as its size, and its callbacks should iterate the
+    /// elements up to [`GcArray::len`]. All elements must be initialized before the next
+    /// collection.
+    pub fn allocate_array<T>(
+        &mut self,
+        vt: *const VTable,
+        len: usize,
+    ) -> Result<Gc<GcArray<T>>, AllocError> {
+        self.allocate_array_raw(vt, len, true)
+    }
+
+    /// Like [`GCAlloc::allocate_array`], running the OOM handler if `retry` is set.
+    fn allocate_array_raw<T>(
+        &mut self,
+        vt: *const VTable,
+        len: usize,
+        retry: bool,
+    ) -> Result<Gc<GcArray<T>>, AllocError> {
+        assert!(
+            core::mem::align_of::<T>() <= ALIGNMENT,
+            "Array element alignment exceeds {}",
+            ALIGNMENT
+        );
+        let sz = GcArray::<T>::size_for(len).ok_or(AllocError::SizeOverflow)?;
+        let ptr = self.allocate_raw(vt, sz, retry)?;
+        unsafe {
+            let ptr = ptr.cast::<GcArray<T>>();
+            (ptr.get() as *mut usize).write(len);
+            Ok(ptr)
+        }
+    }
+
+    /// Allocate a copy of `s` in the heap.
+    pub fn alloc_str(&mut self, s: &str) -> Result<Gc<GcStr>, AllocError> {
+        let array = self.allocate_array::<u8>(&GC_STR_VTABLE, s.len())?;
+        unsafe {
+            let data = (*(array.get() as *mut GcArray<u8>)).as_mut_ptr();
+            core::ptr::copy_nonoverlapping(s.as_ptr(), data, s.len());
+            Ok(array.cast())
+        }
+    }
+
+    /// Allocate an array holding the elements of `elems`, like [`GCAlloc::allocate_array`] with
+    /// the elements moved in.
+    ///
+    /// Pointers among the elements are rewritten by the array's rewrite callback if the
+    /// allocation collects, as for [`GCAlloc::allocate_with`], which also means the OOM handler
+    /// isn't run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `elems` yields fewer elements than its reported length.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn allocate_slice<T>(
+        &mut self,
+        vt: *const VTable,
+        elems: impl ExactSizeIterator<Item = T>,
+    ) -> Result<GcSlice<T>, AllocError> {
+        let len = elems.len();
+        let init_gc_cnt = self.gc_count;
+        let array = self.allocate_array_raw::<T>(vt, len, false)?;
+        let data = unsafe { (*(array.get() as *mut GcArray<T>)).as_mut_ptr() };
+        let mut written = 0;
+        for elem in elems.take(len) {
+            unsafe { data.add(written).write(elem) };
+            written += 1;
+        }
+        assert_eq!(
+            written, len,
+            "Iterator yielded fewer elements than its length"
+        );
+        if self.gc_count != init_gc_cnt {
+            self.no_collect = true;
+            unsafe { self.rewrite_fields(vt, array.get() as *const u8) };
+            self.no_collect = false;
+        }
+        Ok(GcSlice::new(array, len))
+    }
+
+    /// Allocate an object of `raw_sz` bytes, not including the header.
+    ///
+    /// For a vtable with a [`SizeKind::Fixed`] size, `raw_sz` must match that size. For a
+    /// [`SizeKind::Variable`] size, the caller must initialize the object so that the size
+    /// callback returns `raw_sz` before the next collection.
+    ///
+    /// The payload is zeroed, so it never holds the remains of a previous object.
+    ///
+    /// If the heap is still full after collecting, the handler set by
+    /// [`GCAlloc::set_oom_handler`] may ask for one more attempt, which can collect again.
+    ///
+    /// # Allocating from callbacks
+    ///
+    /// - Mark callbacks can't allocate: the allocation fails with
+    ///   [`AllocError::DuringCollection`].
+    /// - Rewrite callbacks can allocate, but never trigger a collection, so the allocation fails
+    ///   if the heap is full. During a collection, the object is placed in the to-space after
+    ///   the copied objects. It is not itself rewritten, so it must only be given pointers that
+    ///   have already been rewritten. This also applies when [`GCAlloc::allocate_typed`]
+    ///   re-runs the rewrite callback of a new object after a collection. Rewrite callbacks
+    ///   can't allocate at all during a minor collection.
+    /// - Finalizers run after the collection has finished and can allocate freely.
+    ///
+    /// # Large objects
+    ///
+    /// Objects above [`GCConfig::large_object_threshold`] go to the large object space, where
+    /// they never move. Allocating them collects once the large objects allocated since the last
+    /// collection add up to the size of a semispace.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vt` is not aligned to 2 bytes, see [`VTable`], or if `raw_sz` doesn't match
+    /// a fixed size.
+    ///
+    /// [`SizeKind::Fixed`]: crate::SizeKind::Fixed
+    /// [`SizeKind::Variable`]: crate::SizeKind::Variable
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn allocate(&mut self, vt: *const VTable, raw_sz: usize) -> Result<Gc<u8>, AllocError> {
+        self.allocate_raw(vt, raw_sz, true)
+    }
+
+    /// Like [`GCAlloc::allocate`], running the OOM handler and retrying once if `retry` is set.
+    fn allocate_raw(
+        &mut self,
+        vt: *const VTable,
+        raw_sz: usize,
+        retry: bool,
+    ) -> Result<Gc<u8>, AllocError> {
+        match self.allocate_once(vt, raw_sz) {
+            Err(AllocError::OutOfMemory) if retry && self.handle_oom(raw_sz) => {
+                self.allocate_once(vt, raw_sz)
+            }
+            result => result,
+        }
+    }
+
+    fn allocate_once(&mut self, vt: *const VTable, raw_sz: usize) -> Result<Gc<u8>, AllocError> {
+        if self.in_gc && (self.rewrite_cursor.is_none() || self.scan_marking) {
+            return Err(AllocError::DuringCollection);
+        }
+        VTPtr::check(vt);
+        if let SizeKind::Fixed(fixed) = unsafe { &(*vt).size } {
+            assert_eq!(
+                *fixed, raw_sz,
+                "Allocation size does not match the fixed size of vtable {:p}",
+                vt
+            );
+        }
+
+        let sz = cell_size(raw_sz).ok_or(AllocError::SizeOverflow)?;
+        // Heap space taken up by the object, including the padding it reserves.
+        let reserved = sz
+            .checked_add(align_slack(self.object_align(vt)))
+            .ok_or(AllocError::SizeOverflow)?;
+        if reserved > self.large_object_threshold() {
+            return self.allocate_large(vt, sz);
+        }
+        if self.to_space_stale && !self.no_collect && self.rewrite_cursor.is_none() {
+            self.release_to_space();
+        }
+        if let Some(cursor) = self.rewrite_cursor {
+            let available = self.to_size - cursor;
+            if reserved > available {
+                self.emit(GcEvent::OutOfMemory { size: reserved });
+                return Err(AllocError::OutOfMemory);
+            }
+            self.commit_to_space(cursor + reserved);
+            let ptr =
+                unsafe { Self::place(self.to_half.add(cursor), vt, self.object_align(vt), sz) };
+            if self.scanning {
+                self.scan_skip.push((cursor, cursor + reserved));
+            }
+            self.rewrite_cursor = Some(cursor + reserved);
+            self.count_allocation(vt, reserved);
+            return Ok(ptr);
+        }
+
+        // Collect at most once per attempt: callers such as `allocate_with` rewrite pointers
+        // through the forward pointers of a single collection. Only `allocate_raw` retries
+        // after the OOM handler, for callers that can keep their pointers up to date.
+        let mut available = self.chunk_size - self.from_cursor;
+        if self.no_collect {
+            // Fall through to the out-of-memory check below.
+        } else if reserved > available {
+            trace!(
+                "Allocate size {} exceeds available space {}",
+                reserved,
+                available
+            );
+            self.collect_reserving(reserved);
+        } else if self.nursery_full(reserved) {
+            trace!("Allocate size {} overflows the nursery", reserved);
+            self.minor_collect();
+        } else if self.over_threshold() {
+            trace!(
+                "Heap usage {} exceeds threshold {}",
+                self.from_cursor,
+                self.gc_threshold
+            );
+            self.collect_reserving(reserved);
+        }
+        available = self.chunk_size - self.from_cursor;
+        if reserved > available {
+            self.emit(GcEvent::OutOfMemory { size: reserved });
+            return Err(AllocError::OutOfMemory);
+        }
+
+        self.commit_from_space(self.from_cursor + reserved);
+        let ptr = unsafe {
+            Self::place(
+                self.from_half.add(self.from_cursor),
+                vt,
+                self.object_align(vt),
+                sz,
+            )
+        };
+        self.shade_new(ptr.get());
+        self.from_cursor += reserved;
+        self.count_allocation(vt, reserved);
+        self.meta_high_water_mark = self.meta_high_water_mark.max(self.from_cursor);
+        Ok(ptr)
+    }
+
+    /// Whether the nursery holds objects and has no room left for `reserved` more bytes.
+    fn nursery_full(&self, reserved: usize) -> bool {
+        let used = self.from_cursor - self.old_end;
+        self.config
+            .nursery_size
+            .is_some_and(|size| used > 0 && used + reserved > size)
+    }
+
+    /// The alignment of the payload of every object in this heap.
+    fn alignment(&self) -> usize {
+        self.config.alignment.unwrap_or(ALIGNMENT).max(ALIGNMENT)
+    }
+
+    /// The alignment of the payload of objects with vtable `vt` in this heap.
+    fn object_align(&self, vt: *const VTable) -> usize {
+        unsafe { (*vt).align }.max(self.alignment())
+    }
+
+    fn large_object_threshold(&self) -> usize {
+        self.config
+            .large_object_threshold
+            .unwrap_or(self.chunk_size / 2)
+    }
+
+    /// Allocate a `sz`-byte block in the large object space.
+    fn allocate_large(&mut self, vt: *const VTable, sz: usize) -> Result<Gc<u8>, AllocError> {
+        if !self.in_gc && !self.no_collect && self.large.bytes_since_sweep() >= self.chunk_size {
+            trace!("Large objects allocated since the last GC exceed the semispace size");
+            self.collect_reserving(0);
+        }
+        let Some(hdr) = self.large.map(align_slack(self.object_align(vt)), sz) else {
+            self.emit(GcEvent::OutOfMemory { size: sz });
+            return Err(AllocError::OutOfMemory);
+        };
+        let header = GCHeader {
+            vt: Cell::new(VTPtr::new(vt).into()),
+            sz,
+        };
+        unsafe { core::ptr::write(hdr, header) };
+        self.count_allocation(vt, sz);
+        // Large objects are born old, and may be initialized with pointers into the nursery.
+        self.remembered.insert(hdr);
+        let ptr = Gc::new(ptr_from_header(hdr));
+        self.shade_new(ptr.get());
+        Ok(ptr)
+    }
+
+    /// Add an allocation of `bytes` bytes of heap for an object of type `vt` to the statistics.
+    fn count_allocation(&mut self, vt: *const VTable, bytes: usize) {
+        self.meta_total_allocated += bytes;
+        #[cfg(feature = "stats")]
+        {
+            let (count, total) = self.meta_type_stats.entry(vt).or_default();
+            *count += 1;
+            *total += bytes;
+        }
+        #[cfg(not(feature = "stats"))]
+        let _ = vt;
+    }
+
+    /// Queue an object allocated during incremental marking to be marked. It may be initialized
+    /// with pointers to unmarked objects, which the roots no longer lead to.
+    fn shade_new(&mut self, ptr: *const u8) {
+        if self.incremental_marking {
+            self.work_list.push_back(header_from_ptr(ptr));
+        }
+    }
+
+    /// Write the header of a `sz`-byte block at `start_ptr`, padded into place for the vtable's
+    /// alignment. The payload is zeroed, as the space may still hold a dead object. Returns the
+    /// pointer to the block's payload.
+    ///
+    /// Nothing is written past the block: the heap is only walked up to the allocation cursor,
+    /// and the rest of the space is one free block by definition.
+    ///
+    /// # Safety
+    ///
+    /// The block and its padding must fit in committed, unused memory at `start_ptr`.
+    unsafe fn place(start_ptr: *mut u8, vt: *const VTable, align: usize, sz: usize) -> Gc<u8> {
+        let header = GCHeader {
+            vt: Cell::new(VTPtr::new(vt).into()),
+            sz,
+        };
+        unsafe {
+            let (block_ptr, _) = write_padded(start_ptr, align, sz);
+            trace!("Allocating {} + header bytes at {:?}", sz, block_ptr);
+            core::ptr::write(block_ptr as *mut GCHeader, header);
+            let payload = block_ptr.add(core::mem::size_of::<GCHeader>());
+            core::ptr::write_bytes(payload, 0, sz - core::mem::size_of::<GCHeader>());
+            Gc::new(payload)
+        }
+    }
+
+    pub fn collect(&mut self) {
+        self.collect_reserving(0);
+        if self.to_space_stale {
+            self.release_to_space();
+        }
+    }
+
+    /// Like [`GCAlloc::collect`], but mark callbacks and the root enumerator can give up on a
+    /// corrupt object with [`GCAlloc::report_trace_error`]. The collection is then abandoned
+    /// before anything moves, unmarking what was marked so far, and the error is returned. The
+    /// heap stays as it was and can be used and collected again.
+    ///
+    /// Nothing is traced after the error, and the finished event and hooks don't run. The
+    /// collection never runs as a Cheney scan, see [`GCConfig::separate_passes`], as the scan
+    /// moves the objects while it traces them.
+    pub fn try_collect(&mut self) -> Result<(), CollectError> {
+        self.fallible_trace = true;
+        self.collect_reserving(0);
+        self.fallible_trace = false;
+        if let Some(err) = self.trace_error.take() {
+            return Err(CollectError::Trace(err));
+        }
+        if self.to_space_stale {
+            self.release_to_space();
+        }
+        Ok(())
+    }
+
+    /// Report that the object at `object` can't be traced, from a mark callback or the root
+    /// enumerator. During [`GCAlloc::try_collect`], the first error abandons the collection and
+    /// the callback should return without marking anything else.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the error anywhere else, e.g. in a regular collection, a minor collection or
+    /// a rewrite callback, where the collection can't be undone.
+    pub fn report_trace_error(&mut self, object: *const u8, reason: &'static str) {
+        let err = TraceError { object, reason };
+        let marking = matches!(self.phase, GCPhase::MarkingRoots | GCPhase::Marking);
+        if !(self.fallible_trace && marking) {
+            panic!("Tracing failed: {}", err);
+        }
+        self.emit(GcEvent::TraceFailed { object, reason });
+        self.trace_error.get_or_insert(err);
+    }
+
+    /// Collect, treating every word on the current thread's stack that points into an object as
+    /// a root, in addition to the handles. This finds roots held in local variables of native
+    /// frames without having to acquire handles for them.
+    ///
+    /// The scan covers the callee-saved registers and the stack from the current frame up to the
+    /// end of the thread's stack, which is found automatically on Linux, and otherwise must be set
+    /// with [`GCAlloc::set_stack_bottom`]. A word counts as a pointer to an object if it points
+    /// anywhere into the object's payload. Any such word, pointer or not, keeps the object alive.
+    ///
+    /// The words found on the stack can't be rewritten, so objects found there are pinned in
+    /// place for this collection and the next, the same as objects that were just unpinned. See
+    /// [`GCAlloc::pin`] for the consequences. Registers are only spilled on x86-64 and AArch64;
+    /// elsewhere, pointers held only in registers may be missed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the end of the stack is unknown.
+    pub fn collect_conservative(&mut self) {
+        let bottom = self
+            .stack_bottom
+            .or_else(stack::thread_stack_bottom)
+            .expect("Unknown stack bottom, set it with GCAlloc::set_stack_bottom");
+        let objects = self.scannable_objects();
+        let mut roots = vec![];
+        unsafe {
+            stack::scan(bottom, |word| {
+                if let Some(hdr) = self.find_object(&objects, word) {
+                    roots.push(hdr);
+                }
+            })
+        };
+        roots.sort();
+        roots.dedup();
+        debug!("Found {} conservative roots", roots.len());
+        self.conservative_roots = roots
+            .into_iter()
+            .map(|hdr| NonNull::new(ptr_from_header::<u8>(hdr) as *mut u8).unwrap())
+            .collect();
+
+        self.collect();
+
+        // The roots stayed in place, so those in the from-space now sit in the to-space.
+        for root in core::mem::take(&mut self.conservative_roots) {
+            if self.in_to_space(root.as_ptr()) {
+                self.unpinned.push(root);
+            }
+        }
+    }
+
+    /// Set the end of the stack scanned by [`GCAlloc::collect_conservative`]: the address just
+    /// past the outermost frame that may hold pointers to the heap. Only the stack between the
+    /// current frame and this address is scanned.
+    ///
+    /// # Safety
+    ///
+    /// `bottom` must lie on the stack of every thread that later collects this heap
+    /// conservatively, above the frame that collects.
+    pub unsafe fn set_stack_bottom(&mut self, bottom: *const u8) {
+        self.stack_bottom = Some(bottom);
+    }
+
+    /// The headers of the objects a conservative root may point to, sorted by address: those in
+    /// the from-space, and those kept in place in the to-space. Large objects are looked up
+    /// separately.
+    fn scannable_objects(&self) -> Vec<*const GCHeader> {
+        let mut objects: Vec<_> = Blocks::new(self.from_half, self.from_cursor)
+            .filter(|&hdr| unsafe { !(*hdr).get_vt().is_free() })
+            .chain(
+                self.pinned
+                    .iter()
+                    .copied()
+                    .filter(|&hdr| self.in_to_space(hdr as *const u8)),
+            )
+            .collect();
+        objects.sort();
+        objects
+    }
+
+    /// The header of the object whose payload contains `addr`, given the sorted headers of the
+    /// candidates.
+    fn find_object(&self, objects: &[*const GCHeader], addr: usize) -> Option<*const GCHeader> {
+        if let Some(hdr) = self.large.find(addr) {
+            return Some(hdr);
+        }
+        let index = objects.partition_point(|&hdr| (hdr as usize) <= addr);
+        let hdr = *objects.get(index.checked_sub(1)?)?;
+        let start = ptr_from_header::<u8>(hdr) as usize;
+        let end = hdr as usize + unsafe { (*hdr).size() };
+        (addr >= start && addr < end).then_some(hdr)
+    }
+
+    /// Collect, growing a growable heap if the live set plus `reserve` bytes doesn't comfortably
+    /// fit.
+    fn collect_reserving(&mut self, reserve: usize) {
+        if self.in_gc {
+            panic!("Recursive GC");
+        }
+
+        self.emit(GcEvent::CollectStarted { minor: false });
+        self.run_hooks(false);
+        let allocated_before = self.from_cursor + self.large.bytes();
+        let start = Instant::now();
+
+        self.in_gc = true;
+        #[cfg(feature = "compressed-ptrs")]
+        {
+            self.prev_compress_base = self.compress_base;
+        }
+        let resumed = core::mem::take(&mut self.incremental_marking);
+        if !resumed {
+            self.start_marking();
+        }
+        self.gc_count += 1;
+        self.epoch += 1;
+        let unpinned = self.unpinned.clone();
+        self.collect_pinned();
+
+        if self.config.protect_during_gc {
+            self.protect_from_space();
+        }
+
+        if !resumed && self.cheney_applies() {
+            self.promoted = None;
+            self.remembered.clear();
+            self.retired_backing = None;
+            debug!("Cheney scan");
+            self.enter_phase(GCPhase::MarkingRoots);
+            let alloc_start_size = self.cheney_scan();
+            self.finish_collection(None, alloc_start_size, allocated_before, start);
+            return;
+        }
+
+        if resumed {
+            debug!("Finishing incremental mark");
+            self.enter_phase(GCPhase::Marking);
+            self.mark();
+        }
+        debug!("Mark roots");
+        self.enter_phase(GCPhase::MarkingRoots);
+        self.mark_roots();
+
+        debug!("Mark phase");
+        self.enter_phase(GCPhase::Marking);
+        self.mark();
+        // Everything from here on moves objects, so errors can't be undone.
+        self.fallible_trace = false;
+        if self.trace_error.is_some() {
+            self.emit(GcEvent::CollectAbandoned {
+                reason: AbandonReason::TraceError,
+            });
+            self.abandon_collection(unpinned);
+            return;
+        }
+
+        if self.config.mode == CollectorMode::Compacting {
+            self.compact(allocated_before, start);
+            return;
+        }
+
+        // When resizing, copy into the first half of a new mapping. The last mapping the heap
+        // was resized out of can go now.
+        self.retired_backing = None;
+        let can_grow = self.chunk_size.max(self.to_size) < self.max_size;
+        let mut resized = if (can_grow || self.shrink_due()) && self.pinned.is_empty() {
+            self.map_resized(reserve)
+        } else {
+            None
+        };
+        if resized.is_none() && !self.survivors_fit() {
+            self.emit(GcEvent::CollectAbandoned {
+                reason: AbandonReason::ToSpaceFull,
+            });
+            self.abandon_collection(unpinned);
+            return;
+        }
+        self.promoted = None;
+        self.remembered.clear();
+        if let Some((backing, size)) = &mut resized {
+            self.to_half = backing.as_mut_ptr();
+            #[cfg(feature = "compressed-ptrs")]
+            {
+                self.compress_base = self.to_half;
+            }
+            self.to_size = *size;
+            self.to_committed = 0;
+        }
+
+        debug!("Copy phase");
+        self.enter_phase(GCPhase::Copying);
+        let alloc_start_size =
+            self.copy(self.from_half, self.from_cursor, self.to_half, self.to_size);
+        self.unprotect_from_space();
+        self.sweep_pinned();
+        let large_survivors = self.sweep_large();
+
+        debug!("Rewrite pointers");
+        self.enter_phase(GCPhase::Rewriting);
+        // Objects allocated by the rewrite callbacks go after the copied ones and aren't
+        // rewritten themselves.
+        self.rewrite_cursor = Some(alloc_start_size);
+        self.rewrite_ptrs(self.to_half, alloc_start_size);
+        self.rewrite_pinned();
+        self.rewrite_large(large_survivors);
+        let alloc_start_size = self.rewrite_cursor.take().unwrap();
+        self.rewrite_handles();
+        self.rewrite_weak_handles();
+
+        self.finish_collection(resized, alloc_start_size, allocated_before, start);
+    }
+
+    /// Swap the spaces once everything has been copied and rewritten, with the first
+    /// `alloc_start_size` bytes of the to-space in use, and wrap up the collection.
+    fn finish_collection(
+        &mut self,
+        resized: Option<(Box<dyn HeapBacking + Send>, usize)>,
+        alloc_start_size: usize,
+        allocated_before: usize,
+        start: Instant,
+    ) {
+        debug!("Swapping spaces");
+        core::mem::swap(&mut self.from_half, &mut self.to_half);
+        core::mem::swap(&mut self.from_committed, &mut self.to_committed);
+        if let Some((mut backing, size)) = resized {
+            self.to_half = unsafe { backing.as_mut_ptr().add(second_half_offset(size)) };
+            self.to_committed = 0;
+            self.chunk_size = size;
+            self.to_size = size;
+            self.retired_backing = Some(core::mem::replace(&mut self._backing, backing));
+            self.low_occupancy_collections = 0;
+        } else {
+            core::mem::swap(&mut self.chunk_size, &mut self.to_size);
+        }
+        self.end_full_collection(alloc_start_size, allocated_before, start);
+    }
+
+    /// Slide the marked objects down to the start of the from-space, see
+    /// [`CollectorMode::Compacting`], and wrap up the collection. This is a minor collection
+    /// whose nursery is the whole space.
+    fn compact(&mut self, allocated_before: usize, start: Instant) {
+        self.remembered.clear();
+        // Pinned large objects are rewritten along with the other large objects.
+        let large = &self.large;
+        self.pinned.retain(|&hdr| !large.contains(hdr));
+        self.old_end = 0;
+
+        debug!("Plan phase");
+        self.enter_phase(GCPhase::Copying);
+        self.plan_promotion();
+        self.unprotect_from_space();
+        let large_survivors = self.sweep_large();
+
+        debug!("Rewrite pointers");
+        self.enter_phase(GCPhase::Rewriting);
+        self.rewrite_promoted();
+        self.rewrite_large(large_survivors);
+        self.rewrite_handles();
+        self.rewrite_weak_handles();
+
+        debug!("Slide phase");
+        self.enter_phase(GCPhase::Copying);
+        let (cursor, _) = self.slide_nursery();
+        self.end_full_collection(cursor, allocated_before, start);
+    }
+
+    /// Wrap up a full collection, which left the first `alloc_start_size` bytes of the
+    /// from-space in use.
+    fn end_full_collection(
+        &mut self,
+        alloc_start_size: usize,
+        allocated_before: usize,
+        start: Instant,
+    ) {
+        self.from_cursor = alloc_start_size;
+        self.old_end = alloc_start_size;
+        if self.config.incremental_commit {
+            self.release_from_space_tail();
+        }
+        self.to_space_stale = self.return_memory;
+        self.mark_bits = None;
+        self.in_gc = false;
+        self.enter_phase(GCPhase::Idle);
+        self.record_gc_time(start);
+        if cfg!(debug_assertions) {
+            // The pointers were already checked as they were marked.
+            self.verify_blocks();
+        }
+        self.record_survivors(allocated_before);
+        if self
+            .shrink_policy
+            .is_some_and(|policy| self.meta_occupancy < policy.occupancy)
+        {
+            self.low_occupancy_collections += 1;
+        } else {
+            self.low_occupancy_collections = 0;
+        }
+        self.emit_finished(false);
+        self.run_hooks(true);
+
+        self.run_finalizers();
+    }
+
+    /// Whether this collection can run as a Cheney scan, see [`GCConfig::separate_passes`].
+    fn cheney_applies(&self) -> bool {
+        #[cfg(feature = "parallel-mark")]
+        if self.mark_threads > 1 {
+            return false;
+        }
+        !self.config.separate_passes
+            && !self.fallible_trace
+            && !self.config.protect_during_gc
+            && self.config.mode == CollectorMode::Copying
+            && self.pinned.is_empty()
+            && self.chunk_size >= self.max_size
+            && self.to_size >= self.chunk_size
+            && !self.shrink_due()
+    }
+
+    /// Copy the live objects to the to-space in a single scan, rewriting their pointers along
+    /// the way, and free the dead ones. Returns the size of the to-space in use.
+    fn cheney_scan(&mut self) -> usize {
+        self.scanning = true;
+        self.rewrite_cursor = Some(0);
+        let roots: Vec<_> = self
+            .handles
+            .values()
+            .chain(&self.root_stack)
+            .map(|root| header_from_ptr(root.as_ptr()) as *const GCHeader)
+            .collect();
+        for hdr in roots {
+            self.evacuate(hdr);
+        }
+        self.run_root_callback(false);
+        self.enter_phase(GCPhase::Copying);
+
+        let mut scan = 0;
+        let mut next_skip = 0;
+        loop {
+            if scan < self.rewrite_cursor.unwrap() {
+                if let Some(&(skip_start, skip_end)) = self.scan_skip.get(next_skip) {
+                    if skip_start == scan {
+                        scan = skip_end;
+                        next_skip += 1;
+                        continue;
+                    }
+                }
+                let hdr = unsafe { &*(self.to_half.add(scan) as *const GCHeader) };
+                scan += hdr.size();
+                let vt = hdr.get_vt();
+                if vt.is_free() {
+                    continue;
+                }
+                trace!("Scanning {:p}", hdr);
+                let payload = ptr_from_header(hdr);
+                self.scan_marking = true;
+                unsafe { self.mark_fields(vt.ptr(), payload) };
+                self.scan_marking = false;
+                unsafe { self.rewrite_fields(vt.ptr(), payload) };
+            } else if let Some(hdr) = self.work_list.pop_front() {
+                // Large objects don't move, so they are rewritten after the scan.
+                trace!("Scanning large {:p}", hdr);
+                self.scan_marking = true;
+                unsafe { self.mark_fields((*hdr).get_vt().ptr(), ptr_from_header(hdr)) };
+                self.scan_marking = false;
+            } else {
+                break;
+            }
+        }
+        self.scanning = false;
+        self.scan_skip.clear();
+
+        for weak in core::mem::take(&mut self.deferred_weak) {
+            self.rewrite_weak(unsafe { &*weak });
+        }
+
+        for hdr_ptr in Blocks::new(self.from_half, self.from_cursor) {
+            let hdr = unsafe { &*hdr_ptr };
+            if self.forwarded(ptr_from_header(hdr)).is_some() || hdr.get_vt().is_free() {
+                continue;
+            }
+            trace!("Freeing {:p} as it wasn't reached", hdr_ptr);
+            self.free_object(hdr);
+        }
+        self.unprotect_from_space();
+
+        let large_survivors = self.sweep_large();
+        self.enter_phase(GCPhase::Rewriting);
+        self.rewrite_large(large_survivors);
+        let alloc_start_size = self.rewrite_cursor.take().unwrap();
+        self.commit_to_space(alloc_start_size + core::mem::size_of::<GCHeader>());
+        unsafe {
+            write_free_block(
+                self.to_half.add(alloc_start_size),
+                self.to_size - alloc_start_size,
+            )
+        };
+        self.rewrite_handles();
+        self.rewrite_weak_handles();
+        alloc_start_size
+    }
+
+    /// Copy the object at `hdr` to the end of the to-space during a Cheney scan, unless it has
+    /// been copied already. Large objects are queued to be scanned in place instead.
+    fn evacuate(&mut self, hdr_ptr: *const GCHeader) {
+        if cfg!(debug_assertions) {
+            self.verify_pointer(core::ptr::null(), hdr_ptr);
+        }
+        let ptr = ptr_from_header::<u8>(hdr_ptr);
+        if !self.in_young_gen(Gc::new(ptr)) {
+            if !self.mark_object(hdr_ptr) {
+                self.work_list.push_back(hdr_ptr);
+            }
+            return;
+        }
+        if self.forwarded(ptr).is_some() {
+            return;
+        }
+
+        let hdr = unsafe { &*hdr_ptr };
+        let sz = hdr.size();
+        let vt = unsafe { &*hdr.get_vt().ptr() };
+        let align = self.object_align(vt);
+        let reserved = sz + align_slack(align);
+        let to_cursor = self.rewrite_cursor.unwrap();
+        // The to-space is at least as large as the from-space.
+        assert!(
+            to_cursor + reserved <= self.to_size,
+            "To-space overflow copying {:p}",
+            hdr_ptr
+        );
+        self.commit_to_space(to_cursor + reserved);
+        let (to_ptr, _) = unsafe { write_padded(self.to_half.add(to_cursor), align, sz) };
+        self.report_move(hdr_ptr, to_ptr as *const GCHeader, sz);
+        unsafe { core::ptr::copy_nonoverlapping(hdr_ptr as *const u8, to_ptr, sz) };
+        let fwd = ptr_from_header(to_ptr as *const GCHeader);
+        self.write_header(hdr, || unsafe { hdr.set_fwd_ptr(fwd) });
+        self.rewrite_cursor = Some(to_cursor + reserved);
+    }
+
+    /// Collect the nursery only: the objects allocated since the last collection, and those that
+    /// haven't survived enough minor collections to be promoted yet, see
+    /// [`GCAlloc::set_tenure_threshold`]. The live ones are promoted to the old generation once
+    /// they have, and the old objects are neither traced nor moved, so
+    /// the cost of the collection depends on the size of the nursery rather than of the heap.
+    ///
+    /// Old objects are assumed to be alive and aren't traced, apart from the ones recorded by
+    /// [`GCAlloc::write_barrier`]: whenever an old object is given a pointer to a nursery object,
+    /// the barrier must be run, or the nursery object may be collected. Objects allocated in the
+    /// large object space count as old, and are recorded when they are allocated.
+    ///
+    /// Rewrite callbacks can't allocate during a minor collection. If an object in the nursery is
+    /// pinned, the whole heap is collected instead.
+    pub fn minor_collect(&mut self) {
+        if self.in_gc {
+            panic!("Recursive GC");
+        }
+        if self.pins.values().any(|pin| self.in_nursery(pin.as_ptr())) {
+            debug!("Nursery has pinned objects, collecting the whole heap instead");
+            self.collect_reserving(0);
+            return;
+        }
+        if self.incremental_marking {
+            debug!("Incremental mark in progress, collecting the whole heap instead");
+            self.collect_reserving(0);
+            return;
+        }
+
+        self.emit(GcEvent::CollectStarted { minor: true });
+        self.run_hooks(false);
+        let allocated_before = self.from_cursor + self.large.bytes();
+        let start = Instant::now();
+
+        self.in_gc = true;
+        #[cfg(feature = "compressed-ptrs")]
+        {
+            self.prev_compress_base = self.compress_base;
+        }
+        self.start_marking();
+        self.minor_gc = true;
+        self.gc_count += 1;
+        self.epoch += 1;
+        self.minor_gc_count += 1;
+
+        if self.config.protect_during_gc {
+            self.protect_from_space();
+        }
+
+        debug!("Mark roots");
+        self.enter_phase(GCPhase::MarkingRoots);
+        self.mark_roots();
+        let remembered: Vec<_> = core::mem::take(&mut self.remembered).into_iter().collect();
+        for &hdr in &remembered {
+            trace!("Marking from remembered {:p}", hdr);
+            unsafe { self.mark_fields((*hdr).get_vt().ptr(), ptr_from_header(hdr)) };
+        }
+
+        debug!("Mark phase");
+        self.enter_phase(GCPhase::Marking);
+        self.mark();
+
+        debug!("Promote phase");
+        self.enter_phase(GCPhase::Copying);
+        self.plan_promotion();
+        self.unprotect_from_space();
+
+        debug!("Rewrite pointers");
+        self.enter_phase(GCPhase::Rewriting);
+        self.rewrite_promoted();
+        for &hdr in &remembered {
+            unsafe { self.rewrite_fields((*hdr).get_vt().ptr(), ptr_from_header(hdr)) };
+        }
+        self.rewrite_handles();
+        self.rewrite_weak_handles();
+        self.enter_phase(GCPhase::Copying);
+        let (cursor, old_end) = self.slide_nursery();
+
+        if old_end < cursor {
+            // Young survivors are left in the nursery, which the remembered objects and the
+            // ones just promoted may point to.
+            self.remembered.extend(remembered);
+            let promoted = unsafe { self.from_half.add(self.old_end) };
+            for hdr in Blocks::new(promoted, old_end - self.old_end) {
+                if !unsafe { (*hdr).get_vt() }.is_free() {
+                    self.remembered.insert(hdr);
+                }
+            }
+        }
+        self.from_cursor = cursor;
+        self.old_end = old_end;
+        if self.config.incremental_commit {
+            self.release_from_space_tail();
+        }
+        self.minor_gc = false;
+        self.mark_bits = None;
+        self.in_gc = false;
+        self.enter_phase(GCPhase::Idle);
+        self.record_gc_time(start);
+        if cfg!(debug_assertions) {
+            self.verify_blocks();
+        }
+        self.record_survivors(allocated_before);
+        self.emit_finished(true);
+        self.run_hooks(true);
+
+        self.run_finalizers();
+    }
+
+    /// Mark up to `budget` objects of the heap, and return whether marking is complete. The first
+    /// step starts an incremental mark from the roots, and the next collection finishes it,
+    /// copying the objects it marked. This spreads the marking work of a collection over several
+    /// short pauses.
+    ///
+    /// Objects are white before they are reached, gray while they wait in the work list, and
+    /// black once they are marked and their mark callback has queued what they point to. While
+    /// the mark is in progress, the program runs between the steps and must keep black objects
+    /// from pointing to white ones: every pointer stored into an object must be reported to
+    /// [`GCAlloc::write_barrier`], e.g. through [`Gc::store`]. Objects allocated in the meantime
+    /// start out gray, and roots added in the meantime are marked by the collection.
+    ///
+    /// The step that returns true leaves nothing gray, but the collection still marks from the
+    /// roots and the barrier before copying. Steps after that return true right away. A minor
+    /// collection during the mark collects the whole heap instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a collection, e.g. from a callback.
+    pub fn mark_step(&mut self, budget: usize) -> bool {
+        if self.in_gc {
+            panic!("Mark step during GC");
+        }
+        if !self.incremental_marking {
+            debug!("Starting incremental mark");
+            self.start_marking();
+            self.phase = GCPhase::MarkingRoots;
+            self.mark_roots();
+            self.incremental_marking = true;
+        }
+        self.in_gc = true;
+        self.phase = GCPhase::Marking;
+        for _ in 0..budget {
+            let Some(ptr) = self.work_list.pop_front() else {
+                break;
+            };
+            self.mark_gray(ptr);
+        }
+        self.in_gc = false;
+        self.phase = GCPhase::Idle;
+        self.work_list.is_empty()
+    }
+
+    /// Record that the old object `container` was given a pointer to `new_child`, which has to
+    /// be done for [`GCAlloc::minor_collect`] to find nursery objects that only old objects
+    /// point to, and for an incremental mark to find objects only reachable through objects it
+    /// already marked. See [`GCAlloc::mark_step`].
+    ///
+    /// See [`Gc::store`] for a helper that writes the pointer and runs the barrier.
+    pub fn write_barrier<T, U>(&mut self, container: Gc<T>, new_child: Gc<U>) {
+        let container = container.get() as *const u8;
+        if self.incremental_marking && self.is_marked(header_from_ptr(container)) {
+            // A black object now points to a possibly white one, which turns gray.
+            trace!("Shading {:p} stored into {:p}", new_child.get(), container);
+            self.work_list
+                .push_back(header_from_ptr(new_child.get() as *const u8));
+        }
+        if self.in_nursery(container) || !self.in_nursery(new_child.get() as *const u8) {
+            return;
+        }
+        trace!("Remembering {:p}", container);
+        self.remembered.insert(header_from_ptr(container));
+    }
+
+    /// Store `value` in the cell `field` picks out of `container`, running the write barrier.
+    /// Unlike [`GcCell::set`], this doesn't need a reference to the cell while the heap is
+    /// borrowed mutably.
+    pub fn set_cell<C, T: CellValue>(
+        &mut self,
+        container: Gc<C>,
+        field: impl FnOnce(&C) -> &GcCell<T>,
+        value: T,
+    ) {
+        // The closure can't reach the heap, so nothing moves the container while it runs.
+        let cell = field(unsafe { &*container.get() }) as *const GcCell<T>;
+        unsafe { (*cell).set(self, container, value) };
+    }
+
+    /// Free the dead objects in the nursery, and work out where the live ones go once the
+    /// nursery is slid down onto the end of the old generation. When compacting, pinned
+    /// objects stay where they are, and the ones after them go after them.
+    fn plan_promotion(&mut self) {
+        let mut promoted = vec![];
+        let mut cursor = self.old_end;
+        let nursery = unsafe { self.from_half.add(self.old_end) };
+        for hdr_ptr in Blocks::new(nursery, self.from_cursor - self.old_end) {
+            let hdr = unsafe { &*hdr_ptr };
+            if hdr.get_vt().is_free() {
+                continue;
+            }
+            if !self.is_marked(hdr_ptr) {
+                trace!("Freeing {:p} as it's not marked", hdr_ptr);
+                self.free_object(hdr);
+                continue;
+            }
+            if !self.minor_gc && self.is_pinned(hdr_ptr) {
+                let ptr = ptr_from_header::<u8>(hdr_ptr) as usize;
+                promoted.push((ptr, ptr));
+                cursor = hdr_ptr as usize - self.from_half as usize + hdr.size();
+                continue;
+            }
+            let align = self.object_align(hdr.get_vt().ptr());
+            let to = self.from_half as usize + cursor;
+            let to_hdr = (to + align_pad(to, align)) as *const GCHeader;
+            promoted.push((
+                ptr_from_header::<u8>(hdr_ptr) as usize,
+                ptr_from_header::<u8>(to_hdr) as usize,
+            ));
+            // Objects keep their reserved space, so none ends up past where it was.
+            cursor += hdr.size() + align_slack(align);
+        }
+        self.promoted = Some(promoted);
+    }
+
+    /// Rewrite the pointers in the live nursery objects, which haven't moved yet.
+    fn rewrite_promoted(&mut self) {
+        for (from, _) in self.promoted.clone().unwrap() {
+            let hdr = header_from_ptr(from as *const u8);
+            unsafe { self.rewrite_fields((*hdr).get_vt().ptr(), from as *const u8) };
+        }
+    }
+
+    /// Move the live nursery objects to where [`GCAlloc::plan_promotion`] put them, in address
+    /// order so none is overwritten before it moves, and clear their marks. In a minor
+    /// collection, they age by one. Returns the new allocation cursor, and the end of the old
+    /// generation, which extends up to the first object below the tenure threshold.
+    fn slide_nursery(&mut self) -> (usize, usize) {
+        let mut cursor = self.old_end;
+        let mut old_end = None;
+        let promoted = self.promoted.take().unwrap();
+        for &(from, to) in &promoted {
+            let from_hdr = header_from_ptr(from as *const u8);
+            let to_hdr = header_from_ptr(to as *const u8);
+            if !self.minor_gc && self.is_pinned(from_hdr) {
+                // A pinned object, which the objects before it slid up to.
+                let gap = to_hdr as usize - (self.from_half as usize + cursor);
+                unsafe {
+                    if gap > 0 {
+                        write_free_block(self.from_half.add(cursor), gap);
+                    }
+                    (*to_hdr).unmark();
+                    cursor += gap + (*to_hdr).size();
+                }
+                continue;
+            }
+            unsafe {
+                let sz = (*from_hdr).size();
+                let align = self.object_align((*from_hdr).get_vt().ptr());
+                self.report_move(from_hdr, to_hdr, sz);
+                core::ptr::copy(from_hdr as *const u8, to_hdr as *mut u8, sz);
+                (*to_hdr).unmark();
+                if self.minor_gc {
+                    let age = ((*to_hdr).age() + 1).min(MAX_AGE);
+                    (*to_hdr).set_age(age);
+                    if age < self.tenure_threshold && old_end.is_none() {
+                        old_end = Some(cursor);
+                    }
+                }
+                let (_, end) = write_padded(self.from_half.add(cursor), align, sz);
+                cursor = end as usize - self.from_half as usize;
+            }
+        }
+        unsafe { write_free_block(self.from_half.add(cursor), self.chunk_size - cursor) };
+        self.promoted = Some(promoted);
+        (cursor, old_end.unwrap_or(cursor))
+    }
+
+    /// Check the heap for corruption, panicking with the offending address on the first
+    /// problem found.
+    ///
+    /// The blocks of the from-space must tile it exactly, ending in the free block at the
+    /// allocation cursor. The fields of every object, large objects included, are visited with
+    /// its [`VTable::visit_cb`] to check that they point to 16-byte aligned objects in the heap.
+    /// Objects without one have their mark callback run instead, which is why the heap is
+    /// borrowed mutably; it runs as it would during a collection, so it can't allocate.
+    ///
+    /// Objects that are garbage but haven't been collected yet are checked too, so this doesn't
+    /// work for heaps where dead objects may hold dangling pointers.
+    ///
+    /// In debug builds, collections run the same checks on their own: the pointers as they are
+    /// marked, and the blocks at the end.
+    pub fn verify(&mut self) {
+        assert!(!self.in_gc, "Heap verified during GC");
+        let cursor = unsafe { self.from_half.add(self.from_cursor) } as *const GCHeader;
+        let blocks = self.verify_blocks();
+        let blocks = blocks.into_iter().filter(|&hdr| hdr < cursor);
+        for hdr in blocks.chain(self.large.headers()) {
+            let vt = unsafe { (*hdr).get_vt() };
+            if vt.is_free() {
+                continue;
+            }
+            assert!(
+                self.incremental_marking || !self.is_marked(hdr),
+                "Object {:p} is marked outside of GC",
+                hdr
+            );
+            for target in self.pointers_of(hdr) {
+                self.verify_pointer(hdr, target);
+            }
+        }
+    }
+
+    /// The headers of the objects the object at `hdr` points to, as visited by its `visit_cb`, or
+    /// reported by its mark callback if it has none. Only valid outside of a collection.
+    fn pointers_of(&mut self, hdr: *const GCHeader) -> Vec<*const GCHeader> {
+        let vt = unsafe { (*hdr).get_vt().ptr() };
+        if let Some(visit) = unsafe { (*vt).visit_cb } {
+            let mut visitor = CollectVisitor(Vec::new());
+            unsafe { visit(ptr_from_header(hdr), &mut visitor) };
+            return visitor.0;
+        }
+        // The work list holds the gray objects of an incremental mark in progress.
+        let gray = self.work_list.len();
+        self.in_gc = true;
+        unsafe { ((*vt).mark_cb)(self, ptr_from_header(hdr)) };
+        self.in_gc = false;
+        self.work_list.drain(gray..).collect()
+    }
+
+    /// Dump the objects in the from-space, and the pointers between them, for offline analysis
+    /// with a [`SnapshotReader`]. Vtable pointers aren't meaningful outside of the process, so
+    /// `type_id` gives a stable id for each vtable instead.
+    ///
+    /// The pointers are found by visiting the fields of each object with its
+    /// [`VTable::visit_cb`], or by running its mark callback if it has none, which is why the heap
+    /// is borrowed mutably.
+    /// Objects that are garbage but haven't been collected yet are included. Large objects, and
+    /// objects pinned in the to-space, are left out, along with pointers to them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a collection, e.g. from a callback.
+    ///
+    /// [`SnapshotReader`]: crate::snapshot::SnapshotReader
+    pub fn snapshot(&mut self, mut type_id: impl FnMut(&VTable) -> u64) -> Vec<u8> {
+        assert!(!self.in_gc, "Heap snapshot during GC");
+        let base = self.from_half as usize;
+        let region = unsafe { core::slice::from_raw_parts(self.from_half, self.from_cursor) };
+        let mut writer = SnapshotWriter::new(base, region);
+        let len = self.from_cursor;
+        let offset = move |ptr: *const u8| {
+            let hdr = header_from_ptr(ptr) as usize;
+            (hdr.wrapping_sub(base) < len).then(|| (hdr - base) as u64)
+        };
+
+        let roots: Vec<_> = self
+            .handles
+            .values()
+            .chain(&self.root_stack)
+            .filter_map(|ptr| offset(ptr.as_ptr()))
+            .collect();
+        writer.word(roots.len() as u64);
+        for root in roots {
+            writer.word(root);
+        }
+
+        let objects: Vec<_> = Blocks::new(self.from_half, self.from_cursor)
+            .filter(|&hdr| unsafe { !(*hdr).get_vt().is_free() })
+            .collect();
+        writer.word(objects.len() as u64);
+        for hdr in objects {
+            let pointers: Vec<_> = self
+                .pointers_of(hdr)
+                .into_iter()
+                .filter_map(|target| offset(ptr_from_header(target)))
+                .collect();
+            writer.word((hdr as usize - base) as u64);
+            writer.word(unsafe { (*hdr).size() } as u64);
+            writer.word(type_id(unsafe { &*(*hdr).get_vt().ptr() }));
+            writer.word(pointers.len() as u64);
+            for pointer in pointers {
+                writer.word(pointer);
+            }
+        }
+        writer.finish()
+    }
+
+    /// Check that `ptr` points to a live object: 16-byte aligned, in the heap, and not into a
+    /// free block left by a collection. Panics otherwise, e.g. for a pointer that missed a
+    /// rewrite or was read from a stale handle.
+    ///
+    /// In debug builds, collections run this check on every pointer they mark.
+    pub fn assert_live<T>(&self, ptr: Gc<T>) {
+        self.verify_pointer(core::ptr::null(), header_from_ptr(ptr.get()));
+    }
+
+    /// Check that the blocks of the from-space tile it exactly up to the allocation cursor.
+    /// Returns the headers of all blocks.
+    fn verify_blocks(&self) -> Vec<*const GCHeader> {
+        // The blocks iterator panics on sizes that are too small or overrun the cursor, so the
+        // last block ends exactly at it.
+        Blocks::new(self.from_half, self.from_cursor).collect()
+    }
+
+    /// Check that `target` is the header of an aligned object in the heap. `source` is the
+    /// object pointing to it, if known, and only used for reporting.
+    fn verify_pointer(&self, source: *const GCHeader, target: *const GCHeader) {
+        let ptr = ptr_from_header::<u8>(target);
+        let from = if source.is_null() {
+            String::new()
+        } else {
+            format!(" from object {:p}", source)
+        };
+        assert!(
+            (ptr as usize).is_multiple_of(ALIGNMENT),
+            "Misaligned pointer {:p}{}",
+            ptr,
+            from
+        );
+        assert!(
+            self.contains(ptr),
+            "Pointer {:p}{} is outside of the heap",
+            ptr,
+            from
+        );
+        assert!(
+            !unsafe { (*target).get_vt() }.is_free(),
+            "Pointer {:p}{} points into a free block",
+            ptr,
+            from
+        );
+    }
+
+    /// Free every object and empty the heap, keeping its memory for reuse, e.g. between
+    /// isolated scripts of an interpreter.
+    ///
+    /// All objects are freed as if they were garbage, with finalizers running at the end, where
+    /// they may already allocate into the empty heap. Handles, pins and roots are all released:
+    /// using them afterwards panics, weak handles are cleared, and handles rooted in a
+    /// [`HandleScope`] must not be used. The metadata starts over as for a new heap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a collection, e.g. from a callback.
+    pub fn reset(&mut self) {
+        assert!(!self.in_gc, "Heap reset during GC");
+        debug!("Resetting heap");
+        let kept_in_place = self
+            .pinned
+            .iter()
+            .copied()
+            .filter(|&hdr| self.in_to_space(hdr as *const u8));
+        let objects: Vec<_> = Blocks::new(self.from_half, self.from_cursor)
+            .chain(kept_in_place)
+            .filter(|&hdr| unsafe { !(*hdr).get_vt().is_free() })
+            .collect();
+        for hdr in objects {
+            self.free_object(unsafe { &*hdr });
+        }
+        for hdr in self.large.headers() {
+            self.free_object(unsafe { &*hdr });
+            self.large.unmap(hdr);
+        }
+        self.large.reset_sweep_count();
+
+        self.handles.clear();
+        for keys in self.handle_sets.values_mut() {
+            keys.clear();
+        }
+        self.root_stack.clear();
+        for weak in self.weak_handles.values_mut() {
+            *weak = None;
+        }
+        self.pins.clear();
+        self.unpinned.clear();
+        self.pinned.clear();
+        self.remembered.clear();
+        self.promoted = None;
+        self.retired_backing = None;
+        self.incremental_marking = false;
+        self.work_list.clear();
+        self.mark_bits = None;
+
+        self.from_cursor = 0;
+        self.old_end = 0;
+        unsafe { write_free_block(self.from_half, self.chunk_size) };
+        if self.config.incremental_commit {
+            self.release_from_space_tail();
+        }
+        self.gc_count = 0;
+        self.epoch += 1;
+        self.minor_gc_count = 0;
+        self.meta_total_allocated = 0;
+        #[cfg(feature = "stats")]
+        self.meta_type_stats.clear();
+        self.meta_high_water_mark = 0;
+        self.meta_last_reclaimed = 0;
+        self.meta_last_survivors = 0;
+        self.meta_live_bytes = 0;
+        self.meta_occupancy = 0.0;
+        self.meta_allocated_at_gc = 0;
+        self.meta_allocation_rate = 0.0;
+        self.meta_total_gc_time = Duration::ZERO;
+        self.meta_last_gc_time = Duration::ZERO;
+        self.meta_last_phase_timings = PhaseTimings::default();
+        self.meta_peak_work_list = 0;
+
+        self.run_finalizers();
+    }
+
+    /// Collect into a brand-new heap sized to hold exactly the live set plus `target_slack`
+    /// bytes, e.g. to create a compact snapshot of a VM's initial state.
+    ///
+    /// The live set is marked from the roots and copied into the new heap, with pointers
+    /// rewritten as in a regular collection. All handles move to the new heap and stay valid
+    /// there. Large objects are not copied, but handed over to the new heap as they are, and
+    /// don't count towards its size. This heap is consumed in the process: dead objects are
+    /// freed, and it is left empty with no handles, ready to be reused or dropped.
+    ///
+    /// The slack is rounded up to the alignment, and always leaves room for the header of the
+    /// trailing free block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new heap cannot be mapped.
+    #[cfg(feature = "std")]
+    pub fn collect_into_new(&mut self, target_slack: usize) -> GCAlloc {
+        if self.in_gc {
+            panic!("Recursive GC");
+        }
+        assert!(
+            self.pins.is_empty() && self.unpinned.is_empty(),
+            "Pinned objects can't move into a new heap"
+        );
+        self.pinned.clear();
+
+        trace!("Starting GC into new heap");
+        self.emit(GcEvent::CollectStarted { minor: false });
+        let allocated_before = self.from_cursor + self.large.bytes();
+        let start = Instant::now();
+        self.in_gc = true;
+        #[cfg(feature = "compressed-ptrs")]
+        {
+            self.prev_compress_base = self.compress_base;
+        }
+        if core::mem::take(&mut self.incremental_marking) {
+            debug!("Finishing incremental mark");
+            self.enter_phase(GCPhase::Marking);
+            self.mark();
+        } else {
+            self.start_marking();
+        }
+        self.gc_count += 1;
+        self.epoch += 1;
+        self.promoted = None;
+        self.remembered.clear();
+
+        debug!("Mark roots");
+        self.enter_phase(GCPhase::MarkingRoots);
+        self.mark_roots();
+
+        debug!("Mark phase");
+        self.enter_phase(GCPhase::Marking);
+        self.mark();
+
+        let live_bytes = self.live_bytes();
+        let size = live_bytes
+            + target_slack
+                .max(core::mem::size_of::<GCHeader>())
+                .next_multiple_of(ALIGNMENT);
+        debug!(
+            "Live set is {} bytes, new heap is {} bytes",
+            live_bytes, size
+        );
+        let mut new = GCAlloc::with_config(size, self.config.clone());
+
+        // Borrow the new heap's from-space as our to-space for the copy.
+        core::mem::swap(&mut self.to_half, &mut new.from_half);
+        core::mem::swap(&mut self.to_committed, &mut new.from_committed);
+        #[cfg(feature = "compressed-ptrs")]
+        core::mem::swap(&mut self.compress_base, &mut new.compress_base);
+        let to_size = core::mem::replace(&mut self.to_size, size);
+
+        debug!("Copy phase");
+        self.enter_phase(GCPhase::Copying);
+        let alloc_start_size = self.copy(self.from_half, self.from_cursor, self.to_half, size);
+        let large_survivors = self.sweep_large();
+
+        debug!("Rewrite pointers");
+        self.enter_phase(GCPhase::Rewriting);
+        self.rewrite_cursor = Some(alloc_start_size);
+        self.rewrite_ptrs(self.to_half, alloc_start_size);
+        self.rewrite_large(large_survivors);
+        let alloc_start_size = self.rewrite_cursor.take().unwrap();
+        self.rewrite_handles();
+        self.rewrite_weak_handles();
+
+        core::mem::swap(&mut self.to_half, &mut new.from_half);
+        core::mem::swap(&mut self.to_committed, &mut new.from_committed);
+        #[cfg(feature = "compressed-ptrs")]
+        core::mem::swap(&mut self.compress_base, &mut new.compress_base);
+        self.to_size = to_size;
+
+        new.from_cursor = alloc_start_size;
+        new.old_end = alloc_start_size;
+        new.meta_total_allocated = alloc_start_size;
+        new.meta_allocated_at_gc = alloc_start_size;
+        new.meta_high_water_mark = alloc_start_size;
+        new.gc_threshold = self.gc_threshold;
+        new.tenure_threshold = self.tenure_threshold;
+        new.epoch = self.epoch;
+        new.handles = core::mem::take(&mut self.handles);
+        new.handle_sets = core::mem::take(&mut self.handle_sets);
+        new.root_stack = core::mem::take(&mut self.root_stack);
+        new.root_enumerator = self.root_enumerator.take();
+        new.root_rewriter = self.root_rewriter.take();
+        new.frame_walker = self.frame_walker.take();
+        new.stack_maps = core::mem::take(&mut self.stack_maps);
+        new.move_observer = self.move_observer.take();
+        new.oom_handler = self.oom_handler.take();
+        new.weak_handles = core::mem::take(&mut self.weak_handles);
+        new.large = core::mem::take(&mut self.large);
+
+        // Everything in this heap has been freed or moved.
+        self.from_cursor = 0;
+        self.old_end = 0;
+        unsafe { write_free_block(self.from_half, self.chunk_size) };
+        self.mark_bits = None;
+        self.in_gc = false;
+        self.enter_phase(GCPhase::Idle);
+        self.record_gc_time(start);
+        let survivors = new.from_cursor + new.large.bytes();
+        self.emit(GcEvent::CollectFinished {
+            minor: false,
+            reclaimed: allocated_before.saturating_sub(survivors),
+            survivors,
+            time: self.meta_last_gc_time,
+        });
+
+        self.run_finalizers();
+        new
+    }
+
+    /// Move the collection to `phase`, adding the time since the last change to the phase it
+    /// leaves. Leaving [`GCPhase::Idle`] starts the timings of a new collection.
+    fn enter_phase(&mut self, phase: GCPhase) {
+        let elapsed = self.phase_start.elapsed();
+        self.phase_start = Instant::now();
+        let timings = &mut self.meta_last_phase_timings;
+        match self.phase {
+            GCPhase::Idle => *timings = PhaseTimings::default(),
+            GCPhase::MarkingRoots => timings.mark_roots += elapsed,
+            GCPhase::Marking => timings.mark += elapsed,
+            GCPhase::Copying => timings.copy += elapsed,
+            GCPhase::Rewriting => timings.rewrite += elapsed,
+        }
+        self.phase = phase;
+    }
+
+    /// Account for a collection that started at `start` and just finished.
+    fn record_gc_time(&mut self, start: Instant) {
+        self.meta_last_gc_time = start.elapsed();
+        self.meta_total_gc_time += self.meta_last_gc_time;
+        debug!("GC took {:?}", self.meta_last_gc_time);
+    }
+
+    /// Account for the bytes that survived the collection that just finished, and those it
+    /// reclaimed out of the `allocated_before` bytes in use when it started.
+    fn record_survivors(&mut self, allocated_before: usize) {
+        self.meta_last_survivors = self.from_cursor + self.large.bytes();
+        self.meta_live_bytes = self.from_cursor;
+        self.meta_occupancy = self.from_cursor as f64 / self.chunk_size as f64;
+        let allocated = self.bytes_allocated_since_last_gc() as f64;
+        self.meta_allocation_rate = if self.gc_count == 1 {
+            allocated
+        } else {
+            ALLOCATION_RATE_WEIGHT * allocated
+                + (1.0 - ALLOCATION_RATE_WEIGHT) * self.meta_allocation_rate
+        };
+        self.meta_allocated_at_gc = self.meta_total_allocated;
+        // Objects allocated by rewrite callbacks may outweigh the garbage.
+        self.meta_last_reclaimed = allocated_before.saturating_sub(self.meta_last_survivors);
+    }
+
+    /// Run the free callback of a dead object, or queue it for finalization if its vtable asks
+    /// for it.
+    fn free_object(&mut self, hdr: &GCHeader) {
+        let vt = hdr.get_vt().ptr();
+        if unsafe { (*vt).needs_finalize } {
+            trace!("Queueing {:p} for finalization", hdr);
+            self.queue_finalizer(hdr);
+        } else {
+            unsafe { ((*vt).free_cb)(self, ptr_from_header(hdr)) };
+        }
+    }
+
+    /// Move the payload of a dead object out of the heap into the finalization queue.
+    fn queue_finalizer(&mut self, hdr: &GCHeader) {
+        let vt = hdr.get_vt().ptr();
+        let payload = FinalizePayload::copy_from(hdr, self.object_align(vt));
+        self.finalize_queue.push((vt, payload));
+    }
+
+    /// Run the free callbacks of the dead objects queued during the last collection, in
+    /// allocation order. The collector is idle at this point, so finalizers may allocate.
+    fn run_finalizers(&mut self) {
+        let queue = core::mem::take(&mut self.finalize_queue);
+        if !queue.is_empty() {
+            debug!("Running {} finalizers", queue.len());
+        }
+        for (vt, payload) in queue {
+            unsafe { ((*vt).free_cb)(self, payload.ptr.as_ptr()) };
+        }
+    }
+
+    fn mark_roots(&mut self) {
+        debug_assert!(
+            self.work_list.is_empty(),
+            "Work list left over from an earlier collection"
+        );
+        // Expect as much marking work as last time, so the list doesn't grow while marking.
+        self.work_list.reserve(self.meta_peak_work_list);
+        for handle in self.handles.values() {
+            trace!("Adding handle {:p} to work list", handle.as_ptr());
+            self.work_list.push_back(header_from_ptr(handle.as_ptr()));
+        }
+        for root in &self.root_stack {
+            trace!("Adding root {:p} to work list", root.as_ptr());
+            self.work_list.push_back(header_from_ptr(root.as_ptr()));
+        }
+        for pin in self.pins.values().chain(&self.conservative_roots) {
+            trace!("Adding pinned {:p} to work list", pin.as_ptr());
+            self.work_list.push_back(header_from_ptr(pin.as_ptr()));
+        }
+        self.run_root_callback(false);
+    }
+
+    /// Gather the objects this collection keeps in place.
+    fn collect_pinned(&mut self) {
+        self.pinned = self
+            .pins
+            .values()
+            .chain(&self.unpinned)
+            .chain(&self.conservative_roots)
+            .map(|ptr| header_from_ptr(ptr.as_ptr()) as *const GCHeader)
+            .collect();
+        self.pinned.sort();
+        self.pinned.dedup();
+        self.unpinned.clear();
+    }
+
+    fn is_pinned(&self, hdr: *const GCHeader) -> bool {
+        !self.pinned.is_empty() && self.pinned.binary_search(&hdr).is_ok()
+    }
+
+    /// Free the unreachable objects kept in place, which were only kept in place after being
+    /// unpinned, leaving a free block in their place.
+    fn sweep_pinned(&mut self) {
+        for hdr_ptr in self.pinned.clone() {
+            if !self.is_marked(hdr_ptr) {
+                trace!("Freeing unreachable {:p} kept in place", hdr_ptr);
+                let hdr = unsafe { &*hdr_ptr };
+                let sz = hdr.size();
+                self.free_object(hdr);
+                unsafe { write_free_block(hdr_ptr as *mut u8, sz) };
+            }
+        }
+    }
+
+    /// Rewrite the pointers in the objects kept in place that survived
+    /// [`GCAlloc::sweep_pinned`], and clear their marks.
+    fn rewrite_pinned(&mut self) {
+        for hdr_ptr in self.pinned.clone() {
+            let hdr = unsafe { &*hdr_ptr };
+            if hdr.get_vt().is_free() {
+                continue;
+            }
+            unsafe { self.rewrite_fields(hdr.get_vt().ptr(), ptr_from_header(hdr)) };
+            self.unmark(hdr_ptr);
+        }
+    }
+
+    /// Free the unmarked large objects, and return the headers of the marked ones.
+    fn sweep_large(&mut self) -> Vec<*const GCHeader> {
+        let mut survivors = vec![];
+        for hdr_ptr in self.large.headers() {
+            let hdr = unsafe { &*hdr_ptr };
+            if self.is_marked(hdr_ptr) {
+                survivors.push(hdr_ptr);
+                continue;
+            }
+            trace!("Freeing large object {:p} as it's not marked", hdr_ptr);
+            self.free_object(hdr);
+            self.large.unmap(hdr_ptr);
+        }
+        self.large.reset_sweep_count();
+        survivors
+    }
+
+    /// Rewrite the pointers in the surviving large objects, and clear their marks. Pinned ones
+    /// have already been rewritten.
+    fn rewrite_large(&mut self, survivors: Vec<*const GCHeader>) {
+        for hdr in survivors {
+            if self.is_pinned(hdr) {
+                continue;
+            }
+            unsafe { self.rewrite_fields((*hdr).get_vt().ptr(), ptr_from_header(hdr)) };
+            self.unmark(hdr);
+        }
+    }
+
+    fn mark(&mut self) {
+        self.meta_peak_work_list = self.work_list.len();
+        #[cfg(feature = "parallel-mark")]
+        if self.parallel_mark_applies() {
+            self.parallel_mark();
+            return;
+        }
+        self.bounded_marking = true;
+        self.drain_work_list();
+        while self.work_list_overflowed && self.trace_error.is_none() {
+            debug!("Mark work list overflowed, rescanning the heap");
+            self.work_list_overflowed = false;
+            self.rescan_marked();
+        }
+        self.bounded_marking = false;
+    }
+
+    #[cfg(feature = "parallel-mark")]
+    fn parallel_mark_applies(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        if self.protection.is_some() {
+            return false;
+        }
+        self.mark_threads > 1 && self.mark_bits.is_none() && !self.minor_gc && !self.scanning
+    }
+
+    /// Mark from the work list on `mark_threads` threads.
+    #[cfg(feature = "parallel-mark")]
+    fn parallel_mark(&mut self) {
+        debug!(
+            "Marking from {} roots on {} threads",
+            self.work_list.len(),
+            self.mark_threads
+        );
+        let queue = MarkQueue::new(self.work_list.drain(..), self.mark_threads);
+        let workers: Vec<_> = (1..self.mark_threads)
+            .map(|_| self.mark_worker_heap())
+            .collect();
+        std::thread::scope(|scope| {
+            let threads: Vec<_> = workers
+                .into_iter()
+                .map(|mut worker| {
+                    let queue = &queue;
+                    scope.spawn(move || {
+                        worker.mark_shared(queue);
+                        worker.trace_error
+                    })
+                })
+                .collect();
+            self.mark_shared(&queue);
+            for thread in threads {
+                let result = thread.join();
+                if let Some(err) = result.unwrap_or_else(|panic| std::panic::resume_unwind(panic)) {
+                    self.trace_error.get_or_insert(err);
+                }
+            }
+        });
+    }
+
+    /// A heap for the mark callbacks of another thread, which sees the objects of this one.
+    #[cfg(feature = "parallel-mark")]
+    fn mark_worker_heap(&self) -> GCAlloc {
+        let mut worker = GCAlloc::from_backing(Box::new(NoBacking), 0, 0, 0, self.config.clone())
+            .expect("Failed to create mark worker heap");
+        worker.from_half = self.from_half;
+        #[cfg(feature = "compressed-ptrs")]
+        {
+            worker.compress_base = self.compress_base;
+        }
+        worker.chunk_size = self.chunk_size;
+        worker.pinned = self.pinned.clone();
+        worker.in_gc = true;
+        worker.phase = GCPhase::Marking;
+        worker.fallible_trace = self.fallible_trace;
+        worker
+    }
+
+    /// Mark the headers taken from `queue`, sharing the work list with the other threads when
+    /// they run out of work.
+    #[cfg(feature = "parallel-mark")]
+    fn mark_shared(&mut self, queue: &MarkQueue) {
+        /// Stops the other threads from waiting forever when a callback panics.
+        struct AbortOnPanic<'a>(&'a MarkQueue);
+
+        impl Drop for AbortOnPanic<'_> {
+            fn drop(&mut self) {
+                if std::thread::panicking() {
+                    self.0.abort();
+                }
+            }
+        }
+
+        let _guard = AbortOnPanic(queue);
+        loop {
+            let batch = queue.take(MARK_BATCH);
+            if batch.is_empty() {
+                return;
+            }
+            self.work_list.extend(batch);
+            while let Some(hdr) = self.work_list.pop_back() {
+                let Some(vt) = (unsafe { (*hdr).mark_atomic() }) else {
+                    continue;
+                };
+                if vt.is_free() {
+                    panic!("Free block in work list");
+                }
+                trace!("Marking {:p}", hdr);
+                unsafe { self.mark_fields(vt.ptr(), ptr_from_header(hdr)) };
+                if self.trace_error.is_some() {
+                    self.work_list.clear();
+                    queue.abort();
+                    return;
+                }
+                if self.work_list.len() > MARK_BATCH && queue.wants_work() {
+                    let half = self.work_list.len() / 2;
+                    queue.share(self.work_list.drain(..half));
+                }
+            }
+        }
+    }
+
+    /// Queue the object at `hdr` for marking, unless the work list is full.
+    fn push_work(&mut self, hdr: *const GCHeader) {
+        if self.scanning {
+            self.evacuate(hdr);
+            return;
+        }
+        if self.bounded_marking && self.work_list.len() >= self.work_list_limit() {
+            if !self.work_list_overflowed {
+                self.emit(GcEvent::WorkListOverflow);
+            }
+            self.work_list_overflowed = true;
+            return;
+        }
+        self.work_list.push_back(hdr);
+        self.meta_peak_work_list = self.meta_peak_work_list.max(self.work_list.len());
+    }
+
+    fn work_list_limit(&self) -> usize {
+        self.config
+            .work_list_limit
+            .unwrap_or(DEFAULT_WORK_LIST_LIMIT)
+            .max(1)
+    }
+
+    /// Run the mark callbacks of all marked objects again, so the pointers dropped from a full
+    /// work list are followed. Callbacks of objects whose pointers are all marked already don't
+    /// queue anything new.
+    fn rescan_marked(&mut self) {
+        let kept_in_place = self
+            .pinned
+            .iter()
+            .copied()
+            .filter(|&hdr| self.in_to_space(hdr as *const u8));
+        let objects: Vec<_> = Blocks::new(self.from_half, self.from_cursor)
+            .chain(kept_in_place)
+            .chain(self.large.headers())
+            .collect();
+        for hdr in objects {
+            let vt = unsafe { (*hdr).get_vt() };
+            if vt.is_free() || !self.is_marked(hdr) {
+                continue;
+            }
+            unsafe { self.mark_fields(vt.ptr(), ptr_from_header(hdr)) };
+            self.drain_work_list();
+        }
+    }
+
+    fn drain_work_list(&mut self) {
+        while let Some(ptr) = self.work_list.pop_front() {
+            if self.trace_error.is_some() {
+                self.work_list.clear();
+                self.work_list_overflowed = false;
+                return;
+            }
+            self.mark_gray(ptr);
+        }
+    }
+
+    /// Mark an object taken from the work list, and queue the objects it points to.
+    fn mark_gray(&mut self, ptr: *const GCHeader) {
+        if cfg!(debug_assertions) {
+            self.verify_pointer(core::ptr::null(), ptr);
+        }
+        if self.minor_gc && !self.in_nursery(ptr as *const u8) {
+            return;
+        }
+        let hdr = unsafe { ptr.as_ref().unwrap() };
+
+        if self.mark_object(ptr) {
+            return;
+        }
+        trace!("Marking {:p}", ptr);
+
+        // Call the mark callback
+        let vt = hdr.get_vt();
+        if vt.is_free() {
+            panic!("Free block in work list");
+        }
+        let vt = vt.ptr();
+        unsafe {
+            self.mark_fields(vt, ptr_from_header(ptr));
+        }
+    }
+
+    fn copy(
+        &mut self,
+        from_space: *mut u8,
+        from_size: usize,
+        to_space: *mut u8,
+        to_size: usize,
+    ) -> usize {
+        // Copy phase
+        let mut to_cursor = 0;
+        // Offsets and sizes of the pinned blocks in the to-space, which the copies go around.
+        let to_pins: Vec<_> = self
+            .pinned
+            .iter()
+            .map(|&hdr| (hdr as usize).wrapping_sub(to_space as usize))
+            .filter(|&offset| offset < to_size)
+            .map(|offset| {
+                (offset, unsafe {
+                    (*to_space.add(offset).cast::<GCHeader>()).size()
+                })
+            })
+            .collect();
+        let mut next_pin = 0;
+        let mut skip_pins = |gc: &mut Self, to_cursor: &mut usize, reserved: usize| {
+            while let Some(&(start, len)) = to_pins.get(next_pin) {
+                if *to_cursor + reserved <= start {
+                    break;
+                }
+                if start > *to_cursor {
+                    gc.commit_to_space(start);
+                    unsafe { write_free_block(to_space.add(*to_cursor), start - *to_cursor) };
+                }
+                *to_cursor = start + len;
+                next_pin += 1;
+            }
+        };
+
+        trace!("Copying objects");
+        for hdr in Blocks::new(from_space, from_size) {
+            let from_ptr = hdr as *mut u8;
+            let hdr_ptr = hdr;
+            let hdr = unsafe { &*hdr };
+            let sz = hdr.size();
+
+            if hdr.get_vt().is_free() {
+                trace!("Skipping free block {:p}, size {}", from_ptr, sz);
+                continue;
+            }
+            if self.is_pinned(hdr_ptr) {
+                trace!("Leaving pinned {:p} in place", from_ptr);
+                continue;
+            }
+
+            if !self.is_marked(hdr_ptr) {
+                trace!("Freeing {:p} as it's not marked", from_ptr);
+                self.free_object(hdr);
+                continue;
+            }
+
+            // The vtable knows how large the object is, which must agree with the header.
+            let vt = unsafe { &*hdr.get_vt().ptr() };
+            let raw_sz = unsafe { vt.object_size(ptr_from_header(hdr)) };
+            assert_eq!(
+                cell_size(raw_sz),
+                Some(sz),
+                "Object size changed since allocation at {:p}",
+                from_ptr
+            );
+
+            // The object takes up the same reserved space wherever it goes. The to-space may be
+            // smaller than the from-space, but the survivors were checked to fit before copying.
+            let align = self.object_align(vt);
+            let reserved = sz + align_slack(align);
+            skip_pins(self, &mut to_cursor, reserved);
+            assert!(
+                to_cursor + reserved <= to_size,
+                "To-space overflow copying {:p}",
+                from_ptr
+            );
+            self.commit_to_space(to_cursor + reserved);
+            let (to_ptr, _) = unsafe { write_padded(to_space.add(to_cursor), align, sz) };
+            self.report_move(hdr, to_ptr as *const GCHeader, sz);
+            unsafe {
+                core::ptr::copy_nonoverlapping(from_ptr, to_ptr, sz);
+            }
+            let fwd = ptr_from_header(to_ptr as *const GCHeader);
+            self.write_header(hdr, || unsafe { hdr.set_fwd_ptr(fwd) });
+            let to_hdr = unsafe { (to_ptr as *const GCHeader).as_ref().unwrap() };
+            to_hdr.unmark();
+
+            to_cursor += reserved;
+        }
+        // Allocation continues after the last pinned block.
+        skip_pins(self, &mut to_cursor, to_size);
+
+        // Write free block at the end
+        self.commit_to_space(to_cursor + core::mem::size_of::<GCHeader>());
+        unsafe { write_free_block(to_space.add(to_cursor), to_size - to_cursor) };
+
+        to_cursor
+    }
+
+    fn rewrite_ptrs(&mut self, space: *mut u8, space_size: usize) {
+        // Rewrite pointers
+        trace!("Rewriting pointers");
+        for hdr in Blocks::new(space, space_size) {
+            let vt = unsafe { (*hdr).get_vt() };
+            // Pinned objects are rewritten separately, wherever they are.
+            if vt.is_free() || self.is_pinned(hdr) {
+                continue;
+            }
+
+            unsafe {
+                self.rewrite_fields(vt.ptr(), ptr_from_header(hdr));
+            }
+        }
+    }
+
+    fn protect_from_space(&mut self) {
+        #[cfg(all(target_os = "linux", feature = "std"))]
+        {
+            match crate::protect::Protection::new(self.from_half, self.chunk_size) {
+                Ok(protection) => self.protection = protection,
+                Err(crate::protect::TooManyRegions) => self.emit(GcEvent::ProtectionUnavailable),
+            }
+        }
+        #[cfg(not(all(target_os = "linux", feature = "std")))]
+        self.emit(GcEvent::ProtectionUnavailable);
+    }
+
+    fn unprotect_from_space(&mut self) {
+        #[cfg(all(target_os = "linux", feature = "std"))]
+        {
+            self.protection = None;
+        }
+    }
+
+    /// Mark the objects the object at `obj` points to, through the `visit_cb` of its vtable `vt`,
+    /// or its `mark_cb` if it has none.
+    unsafe fn mark_fields(&mut self, vt: *const VTable, obj: *const u8) {
+        match unsafe { (*vt).visit_cb } {
+            Some(visit) => unsafe { visit(obj, &mut MarkVisitor(self)) },
+            None => unsafe { ((*vt).mark_cb)(self, obj) },
+        }
+    }
+
+    /// Rewrite the pointers in the object at `obj`, through the `visit_cb` of its vtable `vt`, or
+    /// its `rewrite_cb` if it has none.
+    unsafe fn rewrite_fields(&mut self, vt: *const VTable, obj: *const u8) {
+        match unsafe { (*vt).visit_cb } {
+            Some(visit) => unsafe { visit(obj, &mut RewriteVisitor(self)) },
+            None => unsafe { ((*vt).rewrite_cb)(self, obj) },
+        }
+    }
+
+    /// Mark the object at `hdr`. Returns true if it was already marked.
+    fn mark_object(&mut self, hdr: *const GCHeader) -> bool {
+        match &mut self.mark_bits {
+            Some(bits) => bits.mark(hdr),
+            None => self.write_header(hdr, || unsafe { (*hdr).mark() }),
+        }
+    }
+
+    fn unmark(&mut self, hdr: *const GCHeader) {
+        match &mut self.mark_bits {
+            Some(bits) => bits.unmark(hdr),
+            None => unsafe { (*hdr).unmark() },
+        }
+    }
+
+    fn is_marked(&self, hdr: *const GCHeader) -> bool {
+        match &self.mark_bits {
+            Some(bits) => bits.is_marked(hdr),
+            None => unsafe { (*hdr).get_vt().is_marked() },
+        }
+    }
+
+    /// Set up the side mark bits for a collection, if configured.
+    fn start_marking(&mut self) {
+        if self.config.side_mark_bits {
+            self.mark_bits = Some(MarkBits::new(
+                self.from_half,
+                self.chunk_size,
+                self.to_half,
+                self.to_size,
+            ));
+        }
+    }
+
+    /// Run `f`, which writes to the header at `hdr`, with the header's page temporarily made
+    /// writable if the from-space is protected.
+    fn write_header<R>(&self, hdr: *const GCHeader, f: impl FnOnce() -> R) -> R {
+        #[cfg(all(target_os = "linux", feature = "std"))]
+        if let Some(protection) = &self.protection {
+            return protection.unprotected(hdr as *const u8, f);
+        }
+        let _ = hdr;
+        f()
+    }
+
+    /// Heap space taken up by the marked objects in the from-space.
+    fn live_bytes(&self) -> usize {
+        Blocks::new(self.from_half, self.from_cursor)
+            .filter(|&hdr| unsafe { !(*hdr).get_vt().is_free() } && self.is_marked(hdr))
+            .map(|hdr| unsafe { &*hdr })
+            .map(|hdr| hdr.size() + align_slack(self.object_align(hdr.get_vt().ptr())))
+            .sum()
+    }
+
+    /// Whether the marked objects fit in the to-space, along with the objects kept in place
+    /// there.
+    fn survivors_fit(&self) -> bool {
+        let kept_in_place: usize = self
+            .pinned
+            .iter()
+            .filter(|&&hdr| self.in_to_space(hdr as *const u8))
+            .map(|&hdr| unsafe { (*hdr).size() })
+            .sum();
+        self.live_bytes() + kept_in_place <= self.to_size
+    }
+
+    /// Undo a collection that has marked the heap but not moved anything yet, leaving the heap
+    /// as it was before. `unpinned` are the objects that were unpinned before the collection.
+    fn abandon_collection(&mut self, unpinned: Vec<NonNull<u8>>) {
+        self.unprotect_from_space();
+        let marked: Vec<_> = Blocks::new(self.from_half, self.from_cursor)
+            .filter(|&hdr| unsafe { !(*hdr).get_vt().is_free() })
+            .chain(self.pinned.iter().copied())
+            .chain(self.large.headers())
+            .collect();
+        for hdr in marked {
+            self.unmark(hdr);
+        }
+        self.pinned.clear();
+        self.unpinned = unpinned;
+        // No forward pointers were written, so callers must not rewrite through them.
+        self.gc_count -= 1;
+        self.epoch -= 1;
+        self.mark_bits = None;
+        self.in_gc = false;
+        self.enter_phase(GCPhase::Idle);
+    }
+
+    /// Whether the survivors stayed small for long enough that the heap should shrink.
+    fn shrink_due(&self) -> bool {
+        self.shrink_policy.is_some_and(|policy| {
+            self.low_occupancy_collections >= policy.collections && self.to_size == self.chunk_size
+        })
+    }
+
+    /// Decide from the marked objects whether the heap should be resized, and if so, map a
+    /// region for the new halves. The heap grows when the live set plus `reserve` bytes would
+    /// fill more than [`GROW_OCCUPANCY`] of it, doubling until it doesn't or the maximum size is
+    /// reached. When a shrink is due, it halves for as long as the live set stays below that
+    /// fraction and the halves above the policy's minimum size. Returns `None` if the heap keeps
+    /// its size, including when the mapping fails. Without std there is no memory to move
+    /// into, so the heap keeps its size.
+    fn map_resized(&mut self, reserve: usize) -> Option<(Box<dyn HeapBacking + Send>, usize)> {
+        let live_bytes = self.live_bytes();
+        let needed = (live_bytes + reserve) as f64 / GROW_OCCUPANCY;
+        let mut size = self.chunk_size;
+        while (size as f64) < needed && size < self.max_size {
+            size = (2 * size).min(self.max_size);
+        }
+        if size == self.chunk_size && self.shrink_due() {
+            let min_size = self.shrink_policy.map_or(size, |policy| policy.min_size);
+            loop {
+                let half = size / 2 / ALIGNMENT * ALIGNMENT;
+                if half < min_size || (half as f64) < needed {
+                    break;
+                }
+                size = half;
+            }
+        }
+        if size == self.chunk_size {
+            return None;
+        }
+        debug!("Resizing heap from {} to {} bytes", self.chunk_size, size);
+        #[cfg(feature = "std")]
+        match map_halves(size, size) {
+            Ok(mmap) => Some((Box::new(mmap), size)),
+            Err(err) => {
+                debug!("Failed to resize heap: {}", err);
+                self.emit(GcEvent::ResizeFailed { size });
+                None
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        None
+    }
+
+    /// Make sure the first `end` bytes of the from-space are committed.
+    fn commit_from_space(&mut self, end: usize) {
+        self.from_committed =
+            self.commit(self.from_half, self.from_committed, end, self.chunk_size);
+    }
+
+    /// Make sure the first `end` bytes of the to-space are committed.
+    fn commit_to_space(&mut self, end: usize) {
+        self.to_committed = self.commit(self.to_half, self.to_committed, end, self.to_size);
+    }
+
+    /// Extend the committed prefix of the half of `size` bytes starting at `base` to cover `end`
+    /// bytes, in chunks of [`COMMIT_CHUNK`]. Returns the new committed size.
+    fn commit(&self, base: *mut u8, committed: usize, end: usize, size: usize) -> usize {
+        if end <= committed {
+            return committed;
+        }
+        let new_committed = end
+            .next_multiple_of(COMMIT_CHUNK.max(mem::page_size()))
+            .min(size);
+        if self.config.incremental_commit {
+            unsafe { mem::populate(base.add(committed), new_committed - committed) };
+        }
+        new_committed
+    }
+
+    /// Write to every page of both halves, and count them as committed.
+    #[cfg(feature = "std")]
+    fn prefault(&mut self) {
+        for (base, size) in [
+            (self.from_half, self.chunk_size),
+            (self.to_half, self.to_size),
+        ] {
+            for offset in (0..size).step_by(mem::page_size()) {
+                // The pages of a fresh mapping read as zero, so this only faults them in.
+                unsafe { core::ptr::write_volatile(base.add(offset), 0) };
+            }
+        }
+        self.from_committed = self.chunk_size;
+        self.to_committed = self.to_size;
+    }
+
+    /// Release the pages of the from-space after the trailing free block header.
+    fn release_from_space_tail(&mut self) {
+        let keep = (self.from_cursor + core::mem::size_of::<GCHeader>())
+            .next_multiple_of(mem::page_size())
+            .min(self.chunk_size);
+        if self.from_committed > keep {
+            unsafe { mem::release(self.from_half.add(keep), self.from_committed - keep) };
+            self.from_committed = keep;
+        }
+    }
+
+    /// Release the to-space back to the OS, apart from the pages of objects kept in place there.
+    fn release_to_space(&mut self) {
+        self.to_space_stale = false;
+        let mut kept: Vec<_> = self
+            .pins
+            .values()
+            .chain(&self.unpinned)
+            .chain(&self.conservative_roots)
+            .map(|ptr| header_from_ptr(ptr.as_ptr()))
+            .filter(|&hdr| self.in_to_space(hdr as *const u8))
+            .map(|hdr| {
+                let sz = unsafe { (*hdr).size() };
+                (hdr as usize - self.to_half as usize, sz)
+            })
+            .collect();
+        kept.sort();
+        let mut start = 0;
+        for (offset, sz) in kept {
+            if offset > start {
+                unsafe { mem::release(self.to_half.add(start), offset - start) };
+            }
+            start = start.max(offset + sz);
+        }
+        unsafe { mem::release(self.to_half.add(start), self.to_size - start) };
+        self.to_committed = 0;
+    }
+
+    /// The new location of a live object after copying: its forward pointer, or the object
+    /// itself if it is pinned or large. After a minor collection, only promoted objects moved.
+    fn relocated(&self, ptr: *const u8) -> *const u8 {
+        if let Some(promoted) = &self.promoted {
+            return Self::promoted_to(promoted, ptr).unwrap_or(ptr);
+        }
+        let header = header_from_ptr(ptr);
+        if self.is_pinned(header) || self.large.contains(header) {
+            return ptr;
+        }
+        unsafe { (*header).fwd_ptr() }
+    }
+
+    /// Like [`GCAlloc::forwarded`], but also counts pinned objects and large objects as surviving
+    /// in place. Dead large objects have already been swept at this point. In a minor collection,
+    /// old objects all survive.
+    fn surviving(&self, ptr: *const u8) -> Option<*const u8> {
+        if let Some(promoted) = &self.promoted {
+            // Outside of the nursery, only dead large objects are gone, and only a compacting
+            // collection sweeps them.
+            let old = !self.in_nursery(ptr)
+                && (self.minor_gc || self.large.contains(header_from_ptr(ptr)));
+            return match Self::promoted_to(promoted, ptr) {
+                Some(to) => Some(to),
+                None => old.then_some(ptr),
+            };
+        }
+        let header = header_from_ptr(ptr);
+        if self.large.contains(header) {
+            return Some(ptr);
+        }
+        if self.is_pinned(header) {
+            // Dead objects kept in place have been swept, and live ones may be unmarked already.
+            let dead = unsafe { (*header).get_vt().is_free() };
+            return (!dead).then_some(ptr);
+        }
+        self.forwarded(ptr)
+    }
+
+    /// Where the last minor collection moved the object at `ptr`, if it was promoted.
+    fn promoted_to(promoted: &[(usize, usize)], ptr: *const u8) -> Option<*const u8> {
+        let index = promoted
+            .binary_search_by_key(&(ptr as usize), |&(from, _)| from)
+            .ok()?;
+        Some(promoted[index].1 as *const u8)
+    }
+
+    fn rewrite_handles(&mut self) {
+        // rewrite handles
+        let mut handles = core::mem::take(&mut self.handles);
+        for handle in handles.values_mut() {
+            let ptr = handle.as_ptr();
+            let fwd_ptr = self.relocated(ptr);
+            trace!("Rewriting handle {:p} to {:p}", ptr, fwd_ptr);
             *handle = NonNull::new(fwd_ptr as *mut u8).unwrap();
         }
+        self.handles = handles;
+
+        let mut root_stack = core::mem::take(&mut self.root_stack);
+        for root in &mut root_stack {
+            let fwd_ptr = self.relocated(root.as_ptr());
+            trace!("Rewriting root {:p} to {:p}", root.as_ptr(), fwd_ptr);
+            *root = NonNull::new(fwd_ptr as *mut u8).unwrap();
+        }
+        self.root_stack = root_stack;
+        self.run_root_callback(true);
+    }
+
+    fn rewrite_weak_handles(&mut self) {
+        let mut weak_handles = core::mem::take(&mut self.weak_handles);
+        for weak in weak_handles.values_mut() {
+            let Some(ptr) = *weak else {
+                continue;
+            };
+            let fwd = self.surviving(ptr.as_ptr());
+            trace!("Rewriting weak handle {:p} to {:?}", ptr, fwd);
+            *weak = fwd.map(|fwd| NonNull::new(fwd as *mut u8).unwrap());
+        }
+        self.weak_handles = weak_handles;
+    }
+
+    /// Acquire a weak handle to a pointer of type T. Unlike [`GCAlloc::acquire_handle`], this
+    /// does not keep the object alive: once the object dies, [`GCAlloc::upgrade`] returns
+    /// `None`.
+    pub fn acquire_weak<T>(&mut self, ptr: Gc<T>) -> WeakHandle<T> {
+        let ptr = ptr.get();
+        self.check_root("Weak handle", ptr as *const u8);
+        let key = self
+            .weak_handles
+            .insert(Some(NonNull::new(ptr as *mut u8).unwrap()));
+        WeakHandle {
+            key,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Acquire a weak root handle to an object, the weak counterpart of
+    /// [`GCAlloc::acquire_handle`] and the same as [`GCAlloc::acquire_weak`]. The handle isn't
+    /// walked when marking roots, so [`GCAlloc::upgrade`] resolves it to the object's new
+    /// location if another root kept it alive, and to `None` once it dies.
+    pub fn acquire_weak_handle<T>(&mut self, ptr: Gc<T>) -> WeakHandle<T> {
+        self.acquire_weak(ptr)
+    }
+
+    /// Get the object a weak handle refers to, or `None` if it has been collected.
+    pub fn upgrade<T>(&self, handle: &WeakHandle<T>) -> Option<Gc<T>> {
+        self.weak_handles
+            .get(handle.key)
+            .copied()
+            .flatten()
+            .map(|ptr| Gc::new(ptr.as_ptr() as *const T))
+    }
+
+    /// Release a weak handle.
+    pub fn release_weak<T>(&mut self, handle: WeakHandle<T>) {
+        self.weak_handles.remove(handle.key);
+    }
+
+    /// Call this from a rewrite callback on each [`Weak`] pointer in the object, instead of
+    /// [`GCAlloc::rewrite_ptr`]. Weak pointers must not be passed to
+    /// [`GCAlloc::mark_accessible`]. The pointer is updated if its object survived the
+    /// collection, and cleared otherwise.
+    pub fn rewrite_weak<T>(&mut self, weak: &Weak<T>) {
+        let Some(ptr) = weak.get() else {
+            return;
+        };
+        if self.scanning
+            && self.in_young_gen(ptr.clone())
+            && self.forwarded(ptr.get() as *const u8).is_none()
+        {
+            // The object may still be reached later in the scan.
+            self.deferred_weak
+                .push(weak as *const Weak<T> as *const Weak<u8>);
+            return;
+        }
+        let fwd = self.surviving(ptr.get() as *const u8);
+        trace!("Rewriting weak {:p} to {:?}", ptr.get(), fwd);
+        weak.set(fwd.map(|fwd| Gc::new(fwd as *const T)));
     }
 
     /// Call this to mark a pointer as accessible.
     pub fn mark_accessible<T>(&mut self, ptr: Gc<T>) {
-        self.work_list.push_back(header_from_ptr(ptr.get()));
+        self.push_work(header_from_ptr(ptr.get()));
     }
 
     /// Call this to rewrite a pointer.
     pub fn rewrite_ptr<T>(&mut self, ptr: &Gc<T>) {
-        let header = header_from_ptr(ptr);
-        let fwd = unsafe { (*header).fwd_ptr() };
+        let fwd = self.relocated(ptr.get() as *const u8);
         trace!("Rewriting {:p} to {:p}", ptr.get(), fwd);
         ptr.set(fwd as *const T);
     }
 
+    /// Like [`GCAlloc::mark_accessible`], but for a bare pointer to an object allocated by
+    /// [`GCAlloc::allocate`]. Use this for pointer storage that isn't wrapped in [`Gc`].
+    pub fn mark_raw(&mut self, ptr: *const u8) {
+        self.push_work(header_from_ptr(ptr));
+    }
+
+    /// Like [`GCAlloc::rewrite_ptr`], but for a bare pointer slot. The slot must hold a pointer
+    /// previously passed to [`GCAlloc::mark_raw`] in this collection.
+    pub fn rewrite_raw(&mut self, slot: &mut *const u8) {
+        let fwd = self.relocated(*slot);
+        trace!("Rewriting {:p} to {:p}", *slot, fwd);
+        *slot = fwd;
+    }
+
+    /// Compress a pointer to an object of this heap into a 32-bit offset, see [`CompressedGc`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object is a large object, which lies outside of the heap's mapping, or if
+    /// the heap is too large for its offsets to fit in 32 bits.
+    #[cfg(feature = "compressed-ptrs")]
+    pub fn compress<T>(&self, ptr: Gc<T>) -> CompressedGc<T> {
+        assert!(
+            !self.large.contains(header_from_ptr(ptr.get())),
+            "Large object {:p} can't be compressed",
+            ptr.get()
+        );
+        CompressedGc::from_offset(self.offset_of(ptr.get() as *const u8, self.compress_base))
+    }
+
+    /// The object a compressed pointer refers to, or `None` if it is null.
+    #[cfg(feature = "compressed-ptrs")]
+    pub fn decompress<T>(&self, ptr: &CompressedGc<T>) -> Option<Gc<T>> {
+        (!ptr.is_null()).then(|| Gc::new(self.compress_base.wrapping_add(ptr.offset()) as *const T))
+    }
+
+    /// Like [`GCAlloc::mark_accessible`], for a compressed pointer. Null pointers are ignored.
+    #[cfg(feature = "compressed-ptrs")]
+    pub fn mark_compressed<T>(&mut self, ptr: &CompressedGc<T>) {
+        if let Some(ptr) = self.decompress(ptr) {
+            self.mark_accessible(ptr);
+        }
+    }
+
+    /// Like [`GCAlloc::rewrite_ptr`], for a compressed pointer. Null pointers are left alone.
+    /// The heap may have moved to a new mapping, in which case the offset is recomputed from
+    /// the new one.
+    #[cfg(feature = "compressed-ptrs")]
+    pub fn rewrite_compressed<T>(&mut self, ptr: &CompressedGc<T>) {
+        if ptr.is_null() {
+            return;
+        }
+        let mut slot = self.prev_compress_base.wrapping_add(ptr.offset());
+        self.rewrite_raw(&mut slot);
+        ptr.set_offset(self.offset_of(slot, self.compress_base));
+    }
+
+    #[cfg(feature = "compressed-ptrs")]
+    fn offset_of(&self, ptr: *const u8, base: *const u8) -> u32 {
+        let offset = (ptr as usize).wrapping_sub(base as usize);
+        u32::try_from(offset)
+            .unwrap_or_else(|_| panic!("{:p} is too far from the heap at {:p}", ptr, base))
+    }
+
+    /// Whether `ptr` lies in the old generation, which minor collections leave alone. See
+    /// [`GCAlloc::minor_collect`].
+    pub fn in_old_gen<T>(&self, ptr: Gc<T>) -> bool {
+        (ptr.get() as usize) >= (self.from_half as usize)
+            && (ptr.get() as usize) < (self.from_half as usize + self.old_end)
+    }
+
     pub fn in_young_gen<T>(&self, ptr: Gc<T>) -> bool {
         (ptr.get() as usize) >= (self.from_half as usize)
             && (ptr.get() as usize) < (self.from_half as usize + self.chunk_size)
     }
+
+    /// Call `f` with the header and vtable of every object in the from-space, in address order,
+    /// followed by the large objects. Free blocks are skipped. Objects pinned in the to-space are
+    /// not visited.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a collection, e.g. from a callback.
+    pub fn for_each_object(&self, mut f: impl FnMut(*const GCHeader, VTPtr)) {
+        assert!(!self.in_gc, "Heap walked during GC");
+        // Everything past the cursor is one free block up to the end of the space.
+        for hdr in Blocks::new(self.from_half, self.from_cursor) {
+            let vt = unsafe { (*hdr).get_vt() };
+            if !vt.is_free() {
+                f(hdr, vt);
+            }
+        }
+        for hdr in self.large.headers() {
+            f(hdr, unsafe { (*hdr).get_vt() });
+        }
+    }
+
+    /// Whether `ptr` points to an object in the heap: either in the from-space, pinned in place
+    /// in the to-space, or in the large object space.
+    fn contains(&self, ptr: *const u8) -> bool {
+        self.in_young_gen(Gc::new(ptr))
+            || self.is_pinned(header_from_ptr(ptr))
+            || self.large.contains(header_from_ptr(ptr))
+    }
+
+    /// Whether `ptr` lies in the nursery part of the from-space.
+    fn in_nursery(&self, ptr: *const u8) -> bool {
+        (ptr as usize) >= (self.from_half as usize + self.old_end)
+            && (ptr as usize) < (self.from_half as usize + self.from_cursor)
+    }
+
+    fn in_to_space(&self, ptr: *const u8) -> bool {
+        (ptr as usize) >= (self.to_half as usize)
+            && (ptr as usize) < (self.to_half as usize + self.to_size)
+    }
+
+    /// Get the location of the copy of the object at `ptr`, if it has already been copied in
+    /// the current collection.
+    fn forwarded(&self, ptr: *const u8) -> Option<*const u8> {
+        if !self.in_gc || !self.in_young_gen(Gc::new(ptr)) {
+            return None;
+        }
+        // Before copying, the header word holds a vtable pointer, which never points into the
+        // heap. After copying, it holds a forward pointer into the to-space.
+        let fwd = unsafe { (*header_from_ptr(ptr)).fwd_ptr() };
+        self.in_to_space(fwd).then_some(fwd)
+    }
+
+    /// Resolve a pointer to the location of its copy if the object has already been copied in
+    /// the current collection. Returns the pointer unchanged otherwise.
+    fn resolve_forwarded(&self, ptr: *const u8) -> *const u8 {
+        self.forwarded(ptr).unwrap_or(ptr)
+    }
+
+    /// Check whether two pointers refer to the same object.
+    ///
+    /// Outside of a collection, this is a plain address comparison.
+    ///
+    /// During a collection, an object that has already been copied lives at two addresses: its
+    /// original location in the from-space, whose header now holds a forward pointer, and its
+    /// copy in the to-space. Both addresses are considered the same object, so callbacks can
+    /// detect shared structure regardless of which side of the copy a pointer is on. Pointers
+    /// into the from-space must point to objects (live or dead), never into free blocks.
+    pub fn same_object<T, U>(&self, a: Gc<T>, b: Gc<U>) -> bool {
+        let a = self.resolve_forwarded(a.get() as *const u8);
+        let b = self.resolve_forwarded(b.get() as *const u8);
+        a == b
+    }
 }
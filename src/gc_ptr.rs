@@ -1,5 +1,16 @@
-use std::{cell::Cell, ptr::NonNull};
+use core::{cell::Cell, ptr::NonNull};
 
+use crate::GCAlloc;
+
+/// A pointer to an object in the heap.
+///
+/// Collections move objects, and only update the pointers they can find: those in the
+/// objects' fields, through the rewrite callbacks, and those held by roots such as [`Handle`]s.
+/// A `Gc` anywhere else, e.g. a local variable or a `Vec` the heap doesn't know about, silently
+/// dangles after the next collection. Keep it in a [`Handle`] to have it rewritten, or in an
+/// [`Unrooted`] to catch its use after a collection.
+///
+/// [`Handle`]: crate::Handle
 #[repr(transparent)]
 pub struct Gc<T>(Cell<NonNull<T>>);
 
@@ -10,6 +21,22 @@ impl<T> Gc<T> {
         ))
     }
 
+    /// A placeholder pointer to no object, for a field that is set before it is ever read, so it
+    /// doesn't have to be an `Option<Gc<T>>`. Its address is [`NonNull::dangling`].
+    ///
+    /// **A dangling pointer must never be traced.** Marking or rewriting it, or passing it to
+    /// the heap in any other way, is undefined behavior. The object holding it must set it with
+    /// [`Gc::set`] or [`Gc::store`] before the next collection, or its mark callback must skip
+    /// it, see [`Gc::is_dangling`].
+    pub fn dangling() -> Self {
+        Self(Cell::new(NonNull::dangling()))
+    }
+
+    /// Whether this is a pointer made by [`Gc::dangling`] that hasn't been set since.
+    pub fn is_dangling(&self) -> bool {
+        self.0.get() == NonNull::dangling()
+    }
+
     pub fn get(&self) -> *const T {
         self.0.get().as_ptr()
     }
@@ -19,6 +46,31 @@ impl<T> Gc<T> {
             .set(NonNull::new(ptr as *mut T).expect("ptr cannot be null"));
     }
 
+    /// Whether both pointers refer to the same object, by address.
+    ///
+    /// Identity is only meaningful between pointers that are both up to date: a collection moves
+    /// objects, so a pointer that wasn't rewritten no longer compares equal to one that was.
+    /// During a collection, use [`GCAlloc::same_object`] instead.
+    pub fn ptr_eq(&self, other: &Gc<T>) -> bool {
+        self.get() == other.get()
+    }
+
+    /// Point this pointer, a field of `container`, to `value`, running the write barrier of
+    /// generational and incremental collection. See [`GCAlloc::write_barrier`].
+    pub fn store<U>(&self, gc: &mut GCAlloc, container: Gc<U>, value: Gc<T>) {
+        gc.write_barrier(container, value.clone());
+        self.set(value.get());
+    }
+
+    /// The pointer with its type erased, e.g. to pass it to a [`Visitor`]. Setting the erased
+    /// pointer sets this one.
+    ///
+    /// [`Visitor`]: crate::trace::Visitor
+    pub fn erase(&self) -> &Gc<()> {
+        // Sound as `Gc<T>` has the same layout for every sized `T`.
+        unsafe { &*(self as *const Gc<T> as *const Gc<()>) }
+    }
+
     /// Cast the pointer to a different type.
     ///
     /// # Safety
@@ -34,3 +86,237 @@ impl<T> Clone for Gc<T> {
         Gc::new(self.get())
     }
 }
+
+/// Prints the address only, like `Gc(0x7f...)`, so it is safe to format any pointer, even one
+/// whose object is being moved. See [`Gc::debug_deref`] to print the object.
+impl<T> core::fmt::Debug for Gc<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Gc({:p})", self.get())
+    }
+}
+
+impl<T> core::fmt::Pointer for Gc<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Pointer::fmt(&self.get(), f)
+    }
+}
+
+impl<T: core::fmt::Debug> Gc<T> {
+    /// Format the object behind the pointer with its own `Debug` implementation.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be up to date and its object alive for as long as the result is
+    /// formatted, i.e. no collection may run in between.
+    pub unsafe fn debug_deref(&self) -> DebugDeref<'_, T> {
+        DebugDeref(self)
+    }
+}
+
+/// Formats the object behind a [`Gc`], see [`Gc::debug_deref`].
+pub struct DebugDeref<'a, T>(&'a Gc<T>);
+
+impl<T: core::fmt::Debug> core::fmt::Debug for DebugDeref<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        unsafe { &*self.0.get() }.fmt(f)
+    }
+}
+
+/// A pointer that does not keep its object alive.
+///
+/// A weak pointer in a GC object must not be marked in the mark callback. Instead, the rewrite
+/// callback passes it to [`GCAlloc::rewrite_weak`], which updates it if its object survived the
+/// collection and clears it otherwise.
+///
+/// [`GCAlloc::rewrite_weak`]: crate::GCAlloc::rewrite_weak
+#[repr(transparent)]
+pub struct Weak<T>(Cell<Option<NonNull<T>>>);
+
+impl<T> Weak<T> {
+    pub fn new(ptr: Gc<T>) -> Self {
+        Self(Cell::new(NonNull::new(ptr.get() as *mut T)))
+    }
+
+    /// A weak pointer that doesn't point to anything.
+    pub fn empty() -> Self {
+        Self(Cell::new(None))
+    }
+
+    /// Get the object, or `None` if it has been collected.
+    pub fn get(&self) -> Option<Gc<T>> {
+        self.0.get().map(|ptr| Gc::new(ptr.as_ptr()))
+    }
+
+    pub fn set(&self, ptr: Option<Gc<T>>) {
+        self.0
+            .set(ptr.and_then(|ptr| NonNull::new(ptr.get() as *mut T)));
+    }
+}
+
+impl<T> Clone for Weak<T> {
+    fn clone(&self) -> Self {
+        Self(Cell::new(self.0.get()))
+    }
+}
+
+/// A [`Gc`] kept outside of the heap and its roots, stamped with the number of collections the
+/// heap had run when it was made.
+///
+/// Nothing rewrites the pointer, so it is only valid until the next collection, like a plain
+/// `Gc` in a local variable. Unlike one, it can't be used by mistake after that: create it with
+/// [`GCAlloc::unrooted`] and take the pointer back with [`GCAlloc::get_unrooted`], which panics
+/// if the heap has collected since. Use a [`Handle`] instead to keep the object across
+/// collections.
+///
+/// [`Handle`]: crate::Handle
+pub struct Unrooted<T> {
+    pub(crate) ptr: Gc<T>,
+    pub(crate) epoch: usize,
+}
+
+impl<T> Clone for Unrooted<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ptr: self.ptr.clone(),
+            epoch: self.epoch,
+        }
+    }
+}
+
+impl<T> core::fmt::Debug for Unrooted<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Unrooted({:p})", self.ptr.get())
+    }
+}
+
+/// A field of a GC object holding pointers, which can be replaced through a shared reference.
+///
+/// [`GcCell::set`] runs the write barrier for the pointers stored, so objects can be mutated in
+/// place under generational and incremental collection. Trace the cell through its [`Trace`]
+/// implementation, e.g. with `#[trace]` on a derived field.
+///
+/// [`Trace`]: crate::Trace
+#[repr(transparent)]
+pub struct GcCell<T: CellValue>(Cell<T>);
+
+impl<T: CellValue> GcCell<T> {
+    pub fn new(value: T) -> Self {
+        Self(Cell::new(value))
+    }
+
+    /// A copy of the pointers in the cell.
+    pub fn get(&self) -> T {
+        // The clone of a `CellValue` copies pointers and can't reach the cell.
+        unsafe { (*self.0.as_ptr()).clone() }
+    }
+
+    /// Store `value` in the cell, a field of `container`, running the write barrier. See
+    /// [`GCAlloc::set_cell`] to reach the cell without holding a reference into the heap.
+    pub fn set<U>(&self, gc: &mut GCAlloc, container: Gc<U>, value: T) {
+        value.barrier(gc, container);
+        self.0.set(value);
+    }
+}
+
+impl<T: CellValue> crate::Trace for GcCell<T> {
+    fn visit_fields(&self, visitor: &mut dyn crate::Visitor) {
+        // Visits the pointers in the cell in place, so rewriting visitors can set them, as `Gc`
+        // is itself a cell.
+        unsafe { (*self.0.as_ptr()).visit(visitor) };
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The values a [`GcCell`] can hold: a [`Gc`], or an optional one.
+pub trait CellValue: sealed::Sealed + Clone {
+    #[doc(hidden)]
+    fn visit(&self, visitor: &mut dyn crate::Visitor);
+    #[doc(hidden)]
+    fn barrier<U>(&self, gc: &mut GCAlloc, container: Gc<U>);
+}
+
+impl<T> sealed::Sealed for Gc<T> {}
+
+impl<T> CellValue for Gc<T> {
+    fn visit(&self, visitor: &mut dyn crate::Visitor) {
+        visitor.visit(self.erase());
+    }
+
+    fn barrier<U>(&self, gc: &mut GCAlloc, container: Gc<U>) {
+        gc.write_barrier(container, self.clone());
+    }
+}
+
+impl<T> sealed::Sealed for Option<Gc<T>> {}
+
+impl<T> CellValue for Option<Gc<T>> {
+    fn visit(&self, visitor: &mut dyn crate::Visitor) {
+        if let Some(ptr) = self {
+            visitor.visit(ptr.erase());
+        }
+    }
+
+    fn barrier<U>(&self, gc: &mut GCAlloc, container: Gc<U>) {
+        if let Some(ptr) = self {
+            gc.write_barrier(container, ptr.clone());
+        }
+    }
+}
+
+/// A pointer stored as a 32-bit offset into the heap's mapping, half the size of a [`Gc`]. It
+/// may be null, which takes the place of `Option<Gc<T>>`.
+///
+/// The offset only means something to the heap that made it: create one with
+/// [`GCAlloc::compress`] and resolve it with [`GCAlloc::decompress`]. Mark and rewrite callbacks
+/// pass compressed pointers to [`GCAlloc::mark_compressed`] and
+/// [`GCAlloc::rewrite_compressed`]. Large objects live outside of the mapping, so they can't be
+/// referred to this way.
+#[cfg(feature = "compressed-ptrs")]
+#[repr(transparent)]
+pub struct CompressedGc<T>(Cell<u32>, core::marker::PhantomData<*const T>);
+
+#[cfg(feature = "compressed-ptrs")]
+impl<T> CompressedGc<T> {
+    /// A pointer that doesn't point to anything.
+    pub fn null() -> Self {
+        Self::from_offset(0)
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.0.get() == 0
+    }
+
+    pub(crate) fn from_offset(offset: u32) -> Self {
+        Self(Cell::new(offset), core::marker::PhantomData)
+    }
+
+    pub(crate) fn offset(&self) -> usize {
+        self.0.get() as usize
+    }
+
+    pub(crate) fn set_offset(&self, offset: u32) {
+        self.0.set(offset);
+    }
+
+    /// Whether both pointers refer to the same object, or are both null. See [`Gc::ptr_eq`].
+    pub fn ptr_eq(&self, other: &CompressedGc<T>) -> bool {
+        self.0.get() == other.0.get()
+    }
+}
+
+#[cfg(feature = "compressed-ptrs")]
+impl<T> Clone for CompressedGc<T> {
+    fn clone(&self) -> Self {
+        Self::from_offset(self.0.get())
+    }
+}
+
+#[cfg(feature = "compressed-ptrs")]
+impl<T> core::fmt::Debug for CompressedGc<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "CompressedGc({:#x})", self.offset())
+    }
+}
@@ -0,0 +1,125 @@
+//! The large object space, for objects too big to be copied around the semispaces.
+//!
+//! Every large object lives in its own mapping, with a regular [`GCHeader`] in front of it. Large
+//! objects never move: they are marked along with the rest of the heap, and the dead ones are
+//! unmapped by a sweep after marking.
+//!
+//! Without std, the blocks come from the global allocator instead.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+#[cfg(feature = "std")]
+use memmap2::MmapMut;
+
+use crate::GCHeader;
+
+#[cfg(feature = "std")]
+type Block = MmapMut;
+
+#[cfg(feature = "std")]
+fn map_block(len: usize) -> Option<(Block, *mut u8)> {
+    match MmapMut::map_anon(len) {
+        Ok(mut mmap) => {
+            let ptr = mmap.as_mut_ptr();
+            Some((mmap, ptr))
+        }
+        Err(err) => {
+            debug!("Failed to map large object: {}", err);
+            None
+        }
+    }
+}
+
+/// A block from the global allocator, aligned like the heap.
+#[cfg(not(feature = "std"))]
+struct Block {
+    ptr: *mut u8,
+    layout: core::alloc::Layout,
+}
+
+#[cfg(not(feature = "std"))]
+unsafe impl Send for Block {}
+
+#[cfg(not(feature = "std"))]
+impl Drop for Block {
+    fn drop(&mut self) {
+        unsafe { alloc::alloc::dealloc(self.ptr, self.layout) };
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn map_block(len: usize) -> Option<(Block, *mut u8)> {
+    let layout = core::alloc::Layout::from_size_align(len, crate::gc::ALIGNMENT).ok()?;
+    let ptr = unsafe { alloc::alloc::alloc_zeroed(layout) };
+    if ptr.is_null() {
+        debug!("Failed to allocate large object");
+        return None;
+    }
+    Some((Block { ptr, layout }, ptr))
+}
+
+/// The large objects of a heap, each mapped separately.
+#[derive(Default)]
+pub(crate) struct LargeObjects {
+    /// The mappings, keyed by the address of the header of their object.
+    blocks: BTreeMap<usize, Block>,
+    /// Total size of the blocks, headers included.
+    bytes: usize,
+    /// Bytes mapped since the last sweep.
+    bytes_since_sweep: usize,
+}
+
+impl LargeObjects {
+    /// Map a block of `sz` bytes, header included, starting `offset` bytes into a fresh mapping.
+    /// Returns the location of the header, or `None` if the mapping fails.
+    pub fn map(&mut self, offset: usize, sz: usize) -> Option<*mut GCHeader> {
+        let (block, ptr) = map_block(offset + sz)?;
+        let hdr = unsafe { ptr.add(offset) } as *mut GCHeader;
+        trace!("Mapped large object of {} bytes at {:p}", sz, hdr);
+        self.blocks.insert(hdr as usize, block);
+        self.bytes += sz;
+        self.bytes_since_sweep += sz;
+        Some(hdr)
+    }
+
+    /// Unmap the block of the object whose header is at `hdr`.
+    pub fn unmap(&mut self, hdr: *const GCHeader) {
+        let sz = unsafe { (*hdr).size() };
+        trace!("Unmapping large object at {:p}", hdr);
+        self.blocks.remove(&(hdr as usize));
+        self.bytes -= sz;
+    }
+
+    /// Whether `hdr` is the header of a large object.
+    pub fn contains(&self, hdr: *const GCHeader) -> bool {
+        !self.blocks.is_empty() && self.blocks.contains_key(&(hdr as usize))
+    }
+
+    /// The header of the large object whose payload contains `addr`, if any.
+    pub fn find(&self, addr: usize) -> Option<*const GCHeader> {
+        let (&hdr, _) = self.blocks.range(..=addr).next_back()?;
+        let sz = unsafe { (*(hdr as *const GCHeader)).size() };
+        (addr >= hdr + core::mem::size_of::<GCHeader>() && addr < hdr + sz)
+            .then_some(hdr as *const GCHeader)
+    }
+
+    /// The headers of all large objects, in address order.
+    pub fn headers(&self) -> Vec<*const GCHeader> {
+        self.blocks
+            .keys()
+            .map(|&hdr| hdr as *const GCHeader)
+            .collect()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn bytes_since_sweep(&self) -> usize {
+        self.bytes_since_sweep
+    }
+
+    pub fn reset_sweep_count(&mut self) {
+        self.bytes_since_sweep = 0;
+    }
+}
@@ -1,16 +1,64 @@
-use std::cell::Cell;
-use std::num::NonZeroUsize;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use tag_ptr::TaggedPtr;
+extern crate alloc;
 
+#[macro_use]
+mod diag;
+
+use core::cell::Cell;
+
+pub mod array;
+mod backing;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
 pub mod gc;
 pub mod gc_ptr;
+mod large;
+mod mark_bits;
+mod mem;
+pub mod observer;
+#[cfg(feature = "parallel-mark")]
+mod parallel;
+#[cfg(all(target_os = "linux", feature = "std"))]
+mod protect;
+pub mod snapshot;
+mod stack;
+pub mod stack_map;
+mod string;
 mod tag_ptr;
+pub mod trace;
 mod vtable;
 
+pub use array::{GcArray, GcSlice};
+pub use backing::HeapBacking;
+pub use gc::AllocError;
+pub use gc::CollectError;
+pub use gc::CollectorMode;
 pub use gc::GCAlloc;
+#[cfg(feature = "std")]
+pub use gc::GCAllocBuilder;
+pub use gc::GCConfig;
+pub use gc::GCInitError;
+pub use gc::GCPhase;
 pub use gc::Handle;
+pub use gc::HandleScope;
+pub use gc::HandleSetId;
+pub use gc::PinnedHandle;
+pub use gc::RootIndex;
+pub use gc::ShrinkPolicy;
+pub use gc::TraceError;
+pub use gc::WeakHandle;
+pub use gc::DEFAULT_WORK_LIST_LIMIT;
+#[cfg(feature = "derive")]
+pub use ike_gc_derive::Trace;
+pub use observer::{AbandonReason, GcEvent, GcObserver};
+pub use stack_map::{Frame, StackMap};
+pub use string::GcStr;
+pub use trace::{Trace, Visitor};
 pub use vtable::SizeKind;
+pub use vtable::VTPtr;
 pub use vtable::VTable;
 
 /// The pointer part of the GC header.
@@ -33,25 +81,42 @@ impl From<vtable::VTPtr> for VTablePtrUnion {
 
 /// A GC object header that's exactly 2 pointers wide.
 #[repr(C)]
-struct GCHeader {
+pub struct GCHeader {
     /// Table to the vtable and mark bit.
     ///
     /// This field will be occupied as a forward pointer during GC. As this is rarely used,
     /// the usual vtable pointer operations are marked **without** the `unsafe` keyword and assumed
     /// as the default operation. Take care when using this field during GC.
     vt: Cell<VTablePtrUnion>,
-    /// The total size of the cell, including the header.
+    /// The total size of the cell, including the header. For free blocks, this is the size of
+    /// the whole free region starting at the header.
+    ///
+    /// Sizes are multiples of 16, so the low bits of an object's size hold its age instead, see
+    /// [`GCHeader::age`]. Read the size through [`GCHeader::size`].
     sz: usize,
 }
 
+/// The oldest an object gets, see [`GCHeader::age`].
+pub(crate) const MAX_AGE: u8 = 3;
+
+/// The bits of [`GCHeader::sz`] that hold the age.
+const AGE_MASK: usize = MAX_AGE as usize;
+
+const _: () = assert!(AGE_MASK < gc::ALIGNMENT);
+
 impl GCHeader {
     /// Get the vtable pointer from the header.
     pub fn get_vt(&self) -> vtable::VTPtr {
         unsafe { self.vt.get().vt }
     }
 
+    /// The total size of the block, including the header.
+    pub fn size(&self) -> usize {
+        self.sz & !AGE_MASK
+    }
+
     /// Mark the object as accessible. Returns true if the object was already marked.
-    pub fn mark(&self) -> bool {
+    pub(crate) fn mark(&self) -> bool {
         let mut vt = unsafe { self.vt.get().vt };
         let was_marked = vt.is_marked();
         if was_marked {
@@ -62,18 +127,40 @@ impl GCHeader {
         false
     }
 
-    pub fn unmark(&self) {
+    /// Like [`GCHeader::mark`], but safe to race with other threads marking the same header.
+    /// Returns the vtable pointer if the object was not marked yet, or `None` if it was.
+    #[cfg(feature = "parallel-mark")]
+    pub(crate) fn mark_atomic(&self) -> Option<vtable::VTPtr> {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        // The header word is a pointer whose lowest bit is the mark bit.
+        let word = unsafe { AtomicUsize::from_ptr(self.vt.as_ptr() as *mut usize) };
+        let prev = word.fetch_or(1, Ordering::AcqRel);
+        (prev & 1 == 0).then(|| vtable::VTPtr::from_word(prev))
+    }
+
+    pub(crate) fn unmark(&self) {
         let mut vt = unsafe { self.vt.get().vt };
         vt.unmark();
         self.vt.set(vt.into());
     }
 
+    /// The number of minor collections the object has survived in the nursery without being
+    /// promoted, up to [`MAX_AGE`].
+    pub(crate) fn age(&self) -> u8 {
+        (self.sz & AGE_MASK) as u8
+    }
+
+    pub(crate) fn set_age(&mut self, age: u8) {
+        assert!(age <= MAX_AGE);
+        self.sz = self.sz & !AGE_MASK | age as usize;
+    }
+
     /// Write a new forward pointer to the header.
     ///
     /// # Safety
     ///
     /// Only valid during GC.
-    pub unsafe fn set_fwd_ptr(&self, ptr: *const u8) {
+    pub(crate) unsafe fn set_fwd_ptr(&self, ptr: *const u8) {
         let mut vt = self.vt.get();
         vt.fwd = ptr;
         self.vt.set(vt);
@@ -84,7 +171,7 @@ impl GCHeader {
     /// # Safety
     ///
     /// Only valid during GC.
-    pub unsafe fn fwd_ptr(&self) -> *const u8 {
+    pub(crate) unsafe fn fwd_ptr(&self) -> *const u8 {
         unsafe { self.vt.get().fwd }
     }
 }
@@ -0,0 +1,89 @@
+//! Mark bits kept outside of the objects, see [`GCConfig::side_mark_bits`].
+//!
+//! [`GCConfig::side_mark_bits`]: crate::GCConfig::side_mark_bits
+
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+
+use crate::{gc::ALIGNMENT, GCHeader};
+
+/// One bit per [`ALIGNMENT`] bytes of a space, for the headers that start there.
+struct Bitmap {
+    base: usize,
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl Bitmap {
+    fn new(base: *const u8, len: usize) -> Self {
+        Bitmap {
+            base: base as usize,
+            len,
+            words: vec![0; (len / ALIGNMENT).div_ceil(64)],
+        }
+    }
+
+    /// The word and bit for the header at `addr`, if it lies in the space.
+    fn locate(&self, addr: usize) -> Option<(usize, u64)> {
+        let offset = addr.wrapping_sub(self.base);
+        if offset >= self.len {
+            return None;
+        }
+        let index = offset / ALIGNMENT;
+        Some((index / 64, 1 << (index % 64)))
+    }
+}
+
+/// The mark bits of one collection, covering both semispaces. Objects elsewhere, i.e. large
+/// objects, are marked in a set instead.
+pub(crate) struct MarkBits {
+    spaces: [Bitmap; 2],
+    others: BTreeSet<usize>,
+}
+
+impl MarkBits {
+    pub fn new(from: *const u8, from_size: usize, to: *const u8, to_size: usize) -> Self {
+        MarkBits {
+            spaces: [Bitmap::new(from, from_size), Bitmap::new(to, to_size)],
+            others: BTreeSet::new(),
+        }
+    }
+
+    fn locate(&mut self, hdr: *const GCHeader) -> Option<(&mut u64, u64)> {
+        let addr = hdr as usize;
+        self.spaces.iter_mut().find_map(|space| {
+            let (word, bit) = space.locate(addr)?;
+            Some((&mut space.words[word], bit))
+        })
+    }
+
+    /// Mark the object at `hdr`. Returns true if it was already marked.
+    pub fn mark(&mut self, hdr: *const GCHeader) -> bool {
+        match self.locate(hdr) {
+            Some((word, bit)) => {
+                let was_marked = *word & bit != 0;
+                *word |= bit;
+                was_marked
+            }
+            None => !self.others.insert(hdr as usize),
+        }
+    }
+
+    pub fn unmark(&mut self, hdr: *const GCHeader) {
+        match self.locate(hdr) {
+            Some((word, bit)) => *word &= !bit,
+            None => {
+                self.others.remove(&(hdr as usize));
+            }
+        }
+    }
+
+    pub fn is_marked(&self, hdr: *const GCHeader) -> bool {
+        let addr = hdr as usize;
+        for space in &self.spaces {
+            if let Some((word, bit)) = space.locate(addr) {
+                return space.words[word] & bit != 0;
+            }
+        }
+        self.others.contains(&addr)
+    }
+}
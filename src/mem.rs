@@ -0,0 +1,105 @@
+//! Page-level operations on the heap mapping.
+//!
+//! These are thin wrappers over the OS memory APIs. They are hints: on platforms where an
+//! operation is not available, or without std, they do nothing, and the collector stays correct
+//! either way.
+
+/// The size of a memory page.
+pub fn page_size() -> usize {
+    #[cfg(all(unix, feature = "std"))]
+    {
+        unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+    }
+    #[cfg(not(all(unix, feature = "std")))]
+    {
+        4096
+    }
+}
+
+/// Commit the pages in `[ptr, ptr + len)` so the first write to them doesn't fault.
+///
+/// # Safety
+///
+/// The range must lie within a writable mapping owned by the caller.
+pub unsafe fn populate(ptr: *mut u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    trace!("Committing {} bytes at {:p}", len, ptr);
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    unsafe {
+        // madvise wants a page-aligned start; extending the range downwards is harmless.
+        let offset = ptr as usize % page_size();
+        let (ptr, len) = (ptr.wrapping_sub(offset), len + offset);
+        // MADV_POPULATE_WRITE is only available since Linux 5.14. On older kernels the pages
+        // are simply committed on first touch instead.
+        libc::madvise(ptr as *mut libc::c_void, len, libc::MADV_POPULATE_WRITE);
+    }
+}
+
+/// Release the whole pages in `[ptr, ptr + len)` back to the OS.
+/// Partial pages at either end of the range are left untouched.
+///
+/// # Safety
+///
+/// The range must lie within a private anonymous mapping owned by the caller, and must not hold
+/// any data the caller still needs.
+pub unsafe fn release(ptr: *mut u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    let start = (ptr as usize).next_multiple_of(page_size());
+    let end = (ptr as usize + len) / page_size() * page_size();
+    if end <= start {
+        return;
+    }
+    let (ptr, len) = (start as *mut u8, end - start);
+    trace!("Releasing {} bytes at {:p}", len, ptr);
+    #[cfg(all(unix, feature = "std"))]
+    unsafe {
+        libc::madvise(ptr as *mut libc::c_void, len, libc::MADV_DONTNEED);
+    }
+}
+
+/// Change the protection of the pages in `[ptr, ptr + len)` to read-write or read-only.
+///
+/// # Safety
+///
+/// The range must be page-aligned and lie within a mapping owned by the caller. While read-only,
+/// any write into the range faults.
+#[cfg(all(unix, feature = "std"))]
+pub unsafe fn protect(ptr: *mut u8, len: usize, writable: bool) {
+    let prot = if writable {
+        libc::PROT_READ | libc::PROT_WRITE
+    } else {
+        libc::PROT_READ
+    };
+    trace!(
+        "Protecting {} bytes at {:p}, writable: {}",
+        len,
+        ptr,
+        writable
+    );
+    let res = unsafe { libc::mprotect(ptr as *mut libc::c_void, len, prot) };
+    assert_eq!(
+        res,
+        0,
+        "mprotect failed: {}",
+        std::io::Error::last_os_error()
+    );
+}
+
+/// Make the pages in `[ptr, ptr + len)` inaccessible, so that any access to them faults.
+///
+/// # Safety
+///
+/// The range must be page-aligned and lie within a mapping owned by the caller, which must not
+/// access it afterwards.
+#[cfg(feature = "debug-guard")]
+pub unsafe fn guard(ptr: *mut u8, len: usize) {
+    trace!("Guarding {} bytes at {:p}", len, ptr);
+    #[cfg(unix)]
+    unsafe {
+        libc::mprotect(ptr as *mut libc::c_void, len, libc::PROT_NONE);
+    }
+}
@@ -0,0 +1,138 @@
+//! Events of the collector, which embedders can route to their own tracing or metrics, see
+//! [`GCAlloc::set_observer`].
+//!
+//! [`GCAlloc::set_observer`]: crate::GCAlloc::set_observer
+
+use core::time::Duration;
+
+#[cfg(feature = "log")]
+use log::{info, warn};
+
+use crate::GCHeader;
+
+/// Something the collector did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GcEvent {
+    /// A collection started. `minor` is set for a [`GCAlloc::minor_collect`].
+    ///
+    /// [`GCAlloc::minor_collect`]: crate::GCAlloc::minor_collect
+    CollectStarted { minor: bool },
+    /// A collection finished, leaving `survivors` bytes of the heap in use after freeing
+    /// `reclaimed` bytes, as in [`GCMeta`]. Abandoned collections don't finish.
+    ///
+    /// [`GCMeta`]: crate::gc::GCMeta
+    CollectFinished {
+        minor: bool,
+        reclaimed: usize,
+        survivors: usize,
+        time: Duration,
+    },
+    /// An object of `size` bytes, header included, was moved from the header at `from` to the
+    /// header at `to`.
+    ObjectCopied {
+        from: *const GCHeader,
+        to: *const GCHeader,
+        size: usize,
+    },
+    /// An allocation failed, as `size` bytes of heap were not available for it.
+    OutOfMemory { size: usize },
+    /// A collection was given up before it moved any object, leaving the heap as it was.
+    CollectAbandoned { reason: AbandonReason },
+    /// A callback reported an error for the object at `object` during
+    /// [`GCAlloc::try_collect`], see [`GCAlloc::report_trace_error`].
+    ///
+    /// [`GCAlloc::try_collect`]: crate::GCAlloc::try_collect
+    /// [`GCAlloc::report_trace_error`]: crate::GCAlloc::report_trace_error
+    TraceFailed {
+        object: *const u8,
+        reason: &'static str,
+    },
+    /// The mark work list reached [`GCConfig::work_list_limit`], so the pointers that didn't fit
+    /// are found again by rescanning the heap, which is slower.
+    ///
+    /// [`GCConfig::work_list_limit`]: crate::GCConfig::work_list_limit
+    WorkListOverflow,
+    /// [`GCConfig::protect_during_gc`] is set, but the from-space could not be protected, either
+    /// because the platform doesn't support it or too many heaps are protected already.
+    ///
+    /// [`GCConfig::protect_during_gc`]: crate::GCConfig::protect_during_gc
+    ProtectionUnavailable,
+    /// Mapping a heap of `size` bytes to grow or shrink into failed, so the heap kept its size.
+    ResizeFailed { size: usize },
+    /// Huge pages could not be mapped for [`GCAlloc::with_huge_pages`], so the heap uses regular
+    /// pages.
+    ///
+    /// [`GCAlloc::with_huge_pages`]: crate::GCAlloc::with_huge_pages
+    HugePagesUnavailable,
+}
+
+/// Why a collection was abandoned, see [`GcEvent::CollectAbandoned`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AbandonReason {
+    /// A callback reported an error while marking, see [`GcEvent::TraceFailed`].
+    TraceError,
+    /// The survivors don't fit in the to-space, and the heap couldn't grow.
+    ToSpaceFull,
+}
+
+// SAFETY: The pointers in events are only addresses to report. Dereferencing them is unsafe
+// anyway, and only valid on the heap's thread during the event.
+unsafe impl Send for GcEvent {}
+unsafe impl Sync for GcEvent {}
+
+/// Receives the events of a heap. Observers are called in the middle of collections, so they
+/// only see the events, not the heap.
+pub trait GcObserver {
+    fn event(&mut self, event: GcEvent);
+}
+
+/// Drops all events, which is what a heap does until it is given an observer when the `log`
+/// feature is off.
+#[cfg(not(feature = "log"))]
+pub(crate) struct NoObserver;
+
+#[cfg(not(feature = "log"))]
+impl GcObserver for NoObserver {
+    fn event(&mut self, _event: GcEvent) {}
+}
+
+/// Writes events to the `log` crate, which is what a heap does until it is given an observer.
+#[cfg(feature = "log")]
+pub struct LogObserver;
+
+#[cfg(feature = "log")]
+impl GcObserver for LogObserver {
+    fn event(&mut self, event: GcEvent) {
+        match event {
+            GcEvent::CollectStarted { minor: false } => trace!("Starting GC"),
+            GcEvent::CollectStarted { minor: true } => trace!("Starting minor GC"),
+            GcEvent::CollectFinished { minor: false, .. } => info!("GC done"),
+            GcEvent::CollectFinished { minor: true, .. } => info!("Minor GC done"),
+            GcEvent::ObjectCopied { from, to, .. } => trace!("Copying {:p} to {:p}", from, to),
+            GcEvent::OutOfMemory { size } => {
+                warn!("Out of memory: No space for allocation of {} bytes", size)
+            }
+            GcEvent::CollectAbandoned {
+                reason: AbandonReason::TraceError,
+            } => warn!("Tracing failed, abandoning GC"),
+            GcEvent::CollectAbandoned {
+                reason: AbandonReason::ToSpaceFull,
+            } => warn!("Survivors don't fit in the to-space, abandoning GC"),
+            GcEvent::TraceFailed { object, reason } => {
+                warn!("Tracing failed at object {:p}: {}", object, reason)
+            }
+            GcEvent::WorkListOverflow => {
+                warn!("Mark work list is full, dropping pointers to rescan later")
+            }
+            GcEvent::ProtectionUnavailable => warn!("Heap protection is not available"),
+            GcEvent::ResizeFailed { size } => {
+                warn!("Failed to resize heap to {} bytes", size)
+            }
+            GcEvent::HugePagesUnavailable => {
+                warn!("Failed to map huge pages, using regular pages")
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! The work shared between the threads of a parallel mark, see [`GCAlloc::set_mark_threads`].
+//!
+//! Each thread marks from a work list of its own, and hands half of it over to the shared queue
+//! when another thread has run out of work. Marking is done once every thread is out of work and
+//! the queue is empty.
+//!
+//! [`GCAlloc::set_mark_threads`]: crate::GCAlloc::set_mark_threads
+
+use alloc::vec::Vec;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+
+use crate::GCHeader;
+
+/// Headers waiting to be marked, which idle threads take from busy ones.
+pub(crate) struct MarkQueue {
+    state: Mutex<State>,
+    wake: Condvar,
+    /// Number of threads waiting for work, read without the lock to decide whether to share.
+    idle: AtomicUsize,
+    threads: usize,
+}
+
+struct State {
+    /// The addresses of the headers, as raw pointers can't be shared between threads.
+    headers: Vec<usize>,
+    done: bool,
+}
+
+impl MarkQueue {
+    pub fn new(headers: impl IntoIterator<Item = *const GCHeader>, threads: usize) -> Self {
+        MarkQueue {
+            state: Mutex::new(State {
+                headers: headers.into_iter().map(|hdr| hdr as usize).collect(),
+                done: false,
+            }),
+            wake: Condvar::new(),
+            idle: AtomicUsize::new(0),
+            threads,
+        }
+    }
+
+    /// Whether some thread is waiting for work.
+    pub fn wants_work(&self) -> bool {
+        self.idle.load(Ordering::Relaxed) > 0
+    }
+
+    /// Hand headers over to the other threads.
+    pub fn share(&self, headers: impl IntoIterator<Item = *const GCHeader>) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .headers
+            .extend(headers.into_iter().map(|hdr| hdr as usize));
+        self.wake.notify_all();
+    }
+
+    /// Take up to `max` headers to mark, waiting for the other threads to share some if there
+    /// are none. Returns nothing once all threads are waiting, which ends the mark.
+    pub fn take(&self, max: usize) -> Vec<*const GCHeader> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.done {
+                return Vec::new();
+            }
+            if !state.headers.is_empty() {
+                let at = state.headers.len().saturating_sub(max);
+                return state
+                    .headers
+                    .drain(at..)
+                    .map(|hdr| hdr as *const GCHeader)
+                    .collect();
+            }
+            if self.idle.fetch_add(1, Ordering::Relaxed) + 1 == self.threads {
+                state.done = true;
+                self.wake.notify_all();
+                return Vec::new();
+            }
+            state = self.wake.wait(state).unwrap();
+            self.idle.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    /// End the mark early, so the other threads stop waiting when one of them panics or fails
+    /// to trace an object.
+    pub fn abort(&self) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        state.done = true;
+        self.wake.notify_all();
+    }
+}
@@ -0,0 +1,131 @@
+//! Write protection of the from-space during collection, see [`GCConfig::protect_during_gc`].
+//!
+//! Protected regions are recorded in a fixed-size global table, which a `SIGSEGV` handler
+//! consults to tell a write into a protected heap apart from an unrelated fault. Writes into a
+//! protected heap abort the process with a clear message; anything else is passed on to the
+//! previously installed handler.
+//!
+//! [`GCConfig::protect_during_gc`]: crate::GCConfig::protect_during_gc
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Once, OnceLock};
+
+use crate::mem;
+
+const MAX_REGIONS: usize = 64;
+
+/// Marks a slot that is being filled in, so the handler skips it.
+const RESERVED: usize = usize::MAX;
+
+/// `(start, end)` of each protected region. A slot is free when `start` is 0.
+static REGIONS: [(AtomicUsize, AtomicUsize); MAX_REGIONS] =
+    [const { (AtomicUsize::new(0), AtomicUsize::new(0)) }; MAX_REGIONS];
+
+static INSTALL: Once = Once::new();
+static PREV_ACTION: OnceLock<libc::sigaction> = OnceLock::new();
+
+const MESSAGE: &[u8] = b"ike-gc: heap object mutated during garbage collection\n";
+
+/// All slots of the region table are taken, see [`Protection::new`].
+pub struct TooManyRegions;
+
+/// A write-protected region of memory. The protection is lifted on drop.
+pub struct Protection {
+    slot: usize,
+    start: usize,
+    end: usize,
+}
+
+impl Protection {
+    /// Write-protect the whole pages within `[ptr, ptr + len)`. Returns `None` if there is nothing
+    /// to protect, or an error if too many regions are already protected.
+    pub fn new(ptr: *mut u8, len: usize) -> Result<Option<Self>, TooManyRegions> {
+        let start = (ptr as usize).next_multiple_of(mem::page_size());
+        let end = (ptr as usize + len) / mem::page_size() * mem::page_size();
+        if end <= start {
+            return Ok(None);
+        }
+        install_handler();
+
+        let Some(slot) = REGIONS.iter().position(|(s, _)| {
+            s.compare_exchange(0, RESERVED, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        }) else {
+            return Err(TooManyRegions);
+        };
+        REGIONS[slot].1.store(end, Ordering::Release);
+        REGIONS[slot].0.store(start, Ordering::Release);
+
+        unsafe { mem::protect(start as *mut u8, end - start, false) };
+        Ok(Some(Protection { slot, start, end }))
+    }
+
+    /// Whether `ptr` lies in a protected page.
+    pub fn contains(&self, ptr: *const u8) -> bool {
+        (self.start..self.end).contains(&(ptr as usize))
+    }
+
+    /// Run `f` with the page containing `ptr` temporarily writable.
+    pub fn unprotected<R>(&self, ptr: *const u8, f: impl FnOnce() -> R) -> R {
+        if !self.contains(ptr) {
+            return f();
+        }
+        let page = (ptr as usize / mem::page_size() * mem::page_size()) as *mut u8;
+        unsafe { mem::protect(page, mem::page_size(), true) };
+        let result = f();
+        unsafe { mem::protect(page, mem::page_size(), false) };
+        result
+    }
+}
+
+impl Drop for Protection {
+    fn drop(&mut self) {
+        unsafe { mem::protect(self.start as *mut u8, self.end - self.start, true) };
+        REGIONS[self.slot].0.store(0, Ordering::Release);
+        REGIONS[self.slot].1.store(0, Ordering::Release);
+    }
+}
+
+fn install_handler() {
+    INSTALL.call_once(|| unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handle_fault as *const () as libc::sighandler_t;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+        libc::sigemptyset(&mut action.sa_mask);
+        let mut prev: libc::sigaction = std::mem::zeroed();
+        libc::sigaction(libc::SIGSEGV, &action, &mut prev);
+        let _ = PREV_ACTION.set(prev);
+    });
+}
+
+extern "C" fn handle_fault(sig: libc::c_int, info: *mut libc::siginfo_t, ctx: *mut libc::c_void) {
+    let addr = unsafe { (*info).si_addr() } as usize;
+    let ours = REGIONS.iter().any(|(s, e)| {
+        let start = s.load(Ordering::Acquire);
+        start != 0 && start != RESERVED && addr >= start && addr < e.load(Ordering::Acquire)
+    });
+    if ours {
+        unsafe {
+            libc::write(2, MESSAGE.as_ptr().cast(), MESSAGE.len());
+            libc::abort();
+        }
+    }
+
+    // Not a write into a protected heap, defer to whoever was there before.
+    let Some(prev) = PREV_ACTION.get() else {
+        return;
+    };
+    unsafe {
+        if prev.sa_sigaction == libc::SIG_DFL || prev.sa_sigaction == libc::SIG_IGN {
+            // Returning re-runs the faulting instruction, which now hits the default action.
+            libc::sigaction(libc::SIGSEGV, prev, std::ptr::null_mut());
+        } else if prev.sa_flags & libc::SA_SIGINFO != 0 {
+            let handler: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                std::mem::transmute(prev.sa_sigaction);
+            handler(sig, info, ctx);
+        } else {
+            let handler: extern "C" fn(libc::c_int) = std::mem::transmute(prev.sa_sigaction);
+            handler(sig);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Heap snapshots for offline analysis, see [`GCAlloc::snapshot`].
+//!
+//! A snapshot is a byte string of little-endian 64-bit words, apart from the raw heap bytes:
+//!
+//! - The magic bytes `IKESNAP1`.
+//! - The address of the from-space when the snapshot was taken, and the length of the region
+//!   of it in use, followed by the bytes of that region padded to a multiple of 8.
+//! - The number of roots, followed by the offset of each root object.
+//! - The number of objects, followed by each object's offset, size, type id, number of
+//!   pointers, and the offsets of the objects it points to.
+//!
+//! Offsets are those of object headers from the start of the from-space.
+//!
+//! [`GCAlloc::snapshot`]: crate::GCAlloc::snapshot
+
+use alloc::{vec, vec::Vec};
+
+const MAGIC: &[u8; 8] = b"IKESNAP1";
+
+/// Why a snapshot could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The data doesn't start with the snapshot magic bytes.
+    BadMagic,
+    /// The data ends in the middle of the snapshot.
+    Truncated,
+}
+
+impl core::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a heap snapshot"),
+            SnapshotError::Truncated => write!(f, "heap snapshot is truncated"),
+        }
+    }
+}
+
+impl core::error::Error for SnapshotError {}
+
+/// An object in a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotObject {
+    /// Offset of the object's header in the from-space.
+    pub offset: u64,
+    /// Size of the object, header included.
+    pub size: u64,
+    /// The type id given for the object's vtable when the snapshot was taken.
+    pub type_id: u64,
+    /// Offsets of the objects the object points to, in the order its fields were visited, or its
+    /// mark callback reported them.
+    pub pointers: Vec<u64>,
+}
+
+/// Builds the snapshot byte string.
+pub(crate) struct SnapshotWriter {
+    buf: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    pub fn new(base: usize, region: &[u8]) -> Self {
+        let mut writer = SnapshotWriter {
+            buf: MAGIC.to_vec(),
+        };
+        writer.word(base as u64);
+        writer.word(region.len() as u64);
+        writer.buf.extend_from_slice(region);
+        writer.buf.resize(writer.buf.len().next_multiple_of(8), 0);
+        writer
+    }
+
+    pub fn word(&mut self, word: u64) {
+        self.buf.extend_from_slice(&word.to_le_bytes());
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Reads a snapshot taken by [`GCAlloc::snapshot`].
+///
+/// [`GCAlloc::snapshot`]: crate::GCAlloc::snapshot
+pub struct SnapshotReader<'a> {
+    base: u64,
+    region: &'a [u8],
+    roots: Vec<u64>,
+    objects: Vec<SnapshotObject>,
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, SnapshotError> {
+        let rest = data.strip_prefix(MAGIC).ok_or(SnapshotError::BadMagic)?;
+        let mut cursor = Cursor(rest);
+        let base = cursor.word()?;
+        let region_len = cursor.word()? as usize;
+        let region = cursor.bytes(region_len)?;
+        cursor.bytes(region_len.next_multiple_of(8) - region_len)?;
+
+        let root_count = cursor.word()?;
+        let roots = (0..root_count)
+            .map(|_| cursor.word())
+            .collect::<Result<_, _>>()?;
+
+        let object_count = cursor.word()?;
+        let mut objects = vec![];
+        for _ in 0..object_count {
+            let offset = cursor.word()?;
+            let size = cursor.word()?;
+            let type_id = cursor.word()?;
+            if offset
+                .checked_add(size)
+                .is_none_or(|end| end > region_len as u64)
+            {
+                return Err(SnapshotError::Truncated);
+            }
+            let pointer_count = cursor.word()?;
+            let pointers = (0..pointer_count)
+                .map(|_| cursor.word())
+                .collect::<Result<_, _>>()?;
+            objects.push(SnapshotObject {
+                offset,
+                size,
+                type_id,
+                pointers,
+            });
+        }
+
+        Ok(SnapshotReader {
+            base,
+            region,
+            roots,
+            objects,
+        })
+    }
+
+    /// Address of the from-space when the snapshot was taken.
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Offsets of the objects held by handles and the root stack.
+    pub fn roots(&self) -> &[u64] {
+        &self.roots
+    }
+
+    /// The objects, in address order.
+    pub fn objects(&self) -> &[SnapshotObject] {
+        &self.objects
+    }
+
+    /// The object whose header is at `offset`.
+    pub fn object(&self, offset: u64) -> Option<&SnapshotObject> {
+        let index = self
+            .objects
+            .binary_search_by_key(&offset, |object| object.offset)
+            .ok()?;
+        Some(&self.objects[index])
+    }
+
+    /// The bytes of an object, header included.
+    pub fn bytes(&self, object: &SnapshotObject) -> &'a [u8] {
+        &self.region[object.offset as usize..(object.offset + object.size) as usize]
+    }
+}
+
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.0.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn word(&mut self) -> Result<u64, SnapshotError> {
+        let bytes = self.bytes(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
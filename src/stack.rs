@@ -0,0 +1,94 @@
+//! Conservative scanning of the native stack, see [`GCAlloc::collect_conservative`].
+//!
+//! The stack is assumed to grow downwards, as it does on all mainstream platforms. Callee-saved
+//! registers are spilled onto the stack before it is scanned on x86-64 and AArch64. On other
+//! architectures only the stack itself is scanned, so a pointer held only in a register can be
+//! missed.
+//!
+//! [`GCAlloc::collect_conservative`]: crate::GCAlloc::collect_conservative
+
+/// Call `f` with the callee-saved registers and every pointer-aligned word on the current
+/// thread's stack between the current stack pointer and `bottom`.
+///
+/// # Safety
+///
+/// `bottom` must lie on the current thread's stack, above the current frame.
+#[inline(never)]
+pub unsafe fn scan(bottom: *const u8, mut f: impl FnMut(usize)) {
+    let regs = spill_registers();
+    let top = regs.as_ptr() as usize;
+    assert!(
+        top <= bottom as usize,
+        "Stack bottom {:p} is below the current stack pointer",
+        bottom
+    );
+    let mut word = top;
+    while word + core::mem::size_of::<usize>() <= bottom as usize {
+        f(unsafe { core::ptr::read_volatile(word as *const usize) });
+        word += core::mem::size_of::<usize>();
+    }
+    // Keep the spilled registers alive until the stack has been scanned.
+    core::hint::black_box(&regs);
+}
+
+/// The end of the current thread's stack, if the OS can tell.
+pub fn thread_stack_bottom() -> Option<*const u8> {
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    unsafe {
+        let mut attr: libc::pthread_attr_t = core::mem::zeroed();
+        if libc::pthread_getattr_np(libc::pthread_self(), &mut attr) != 0 {
+            return None;
+        }
+        let mut addr = core::ptr::null_mut();
+        let mut size = 0;
+        let ok = libc::pthread_attr_getstack(&attr, &mut addr, &mut size) == 0;
+        libc::pthread_attr_destroy(&mut attr);
+        ok.then(|| (addr as *const u8).add(size))
+    }
+    #[cfg(not(all(target_os = "linux", feature = "std")))]
+    None
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn spill_registers() -> [usize; 6] {
+    let mut regs = [0usize; 6];
+    unsafe {
+        core::arch::asm!(
+            "mov [{0}], rbx",
+            "mov [{0} + 8], rbp",
+            "mov [{0} + 16], r12",
+            "mov [{0} + 24], r13",
+            "mov [{0} + 32], r14",
+            "mov [{0} + 40], r15",
+            in(reg) regs.as_mut_ptr(),
+            options(nostack, preserves_flags),
+        );
+    }
+    regs
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn spill_registers() -> [usize; 12] {
+    let mut regs = [0usize; 12];
+    unsafe {
+        core::arch::asm!(
+            "stp x19, x20, [{0}]",
+            "stp x21, x22, [{0}, #16]",
+            "stp x23, x24, [{0}, #32]",
+            "stp x25, x26, [{0}, #48]",
+            "stp x27, x28, [{0}, #64]",
+            "stp x29, x30, [{0}, #80]",
+            in(reg) regs.as_mut_ptr(),
+            options(nostack, preserves_flags),
+        );
+    }
+    regs
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline(always)]
+fn spill_registers() -> [usize; 1] {
+    [0]
+}
@@ -0,0 +1,42 @@
+//! Precise stack roots for code that emits stack maps, see [`GCAlloc::set_frame_walker`].
+//!
+//! A stack map lists the slots of a frame that hold object pointers, by their offset from the
+//! frame pointer. Maps are registered per return address, which identifies the call site a
+//! frame is suspended at. Unlike conservatively scanned roots, the slots are rewritten once
+//! their objects have moved, so nothing has to be pinned.
+//!
+//! [`GCAlloc::set_frame_walker`]: crate::GCAlloc::set_frame_walker
+
+use alloc::{boxed::Box, vec::Vec};
+
+/// The slots holding object pointers in a frame suspended at a call site.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StackMap {
+    slots: Vec<isize>,
+}
+
+impl StackMap {
+    /// A map of the slots at the given byte offsets from the frame pointer. Each slot holds a
+    /// pointer to an object payload, or null.
+    pub fn new(slots: impl IntoIterator<Item = isize>) -> Self {
+        StackMap {
+            slots: slots.into_iter().collect(),
+        }
+    }
+
+    pub fn slots(&self) -> &[isize] {
+        &self.slots
+    }
+}
+
+/// A frame reported by the frame walker.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    /// The address the frame returns to, which selects its [`StackMap`].
+    pub return_address: usize,
+    /// The address the slot offsets of the stack map are relative to.
+    pub frame_pointer: *mut u8,
+}
+
+/// Walks the frames of the mutator's stack, calling the given function on each of them.
+pub type FrameWalker = Box<dyn FnMut(&mut dyn FnMut(Frame)) + Send>;
@@ -0,0 +1,32 @@
+use crate::{array::GcArray, VTable};
+
+/// An immutable UTF-8 string in the heap, allocated by [`GCAlloc::alloc_str`].
+///
+/// It is stored as a [`GcArray`] of bytes, so the length prefix sizes the object when it is
+/// copied.
+///
+/// [`GCAlloc::alloc_str`]: crate::GCAlloc::alloc_str
+#[repr(transparent)]
+pub struct GcStr(GcArray<u8>);
+
+impl GcStr {
+    pub fn as_str(&self) -> &str {
+        // Only ever initialized from a `&str`.
+        unsafe { core::str::from_utf8_unchecked(self.0.as_slice()) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Strings hold no pointers, so there is nothing to mark or rewrite.
+pub(crate) static GC_STR_VTABLE: VTable = VTable {
+    size: GcArray::<u8>::SIZE,
+    align: core::mem::align_of::<GcStr>(),
+    ..VTable::DEFAULT
+};
@@ -1,18 +1,27 @@
-use std::fmt::Debug;
+use core::fmt::Debug;
 
 #[repr(transparent)]
 pub struct TaggedPtr<const TAG_BITS: usize, T> {
     ptr: usize,
-    _marker: std::marker::PhantomData<T>,
+    _marker: core::marker::PhantomData<T>,
 }
 
 impl<const TAG_BITS: usize, T> TaggedPtr<TAG_BITS, T> {
     pub fn new(ptr: *const T, tag: usize) -> Self {
         assert!(tag < (1 << TAG_BITS));
-        assert!(ptr as usize % (1 << TAG_BITS) == 0);
+        assert!((ptr as usize).is_multiple_of(1 << TAG_BITS));
         Self {
             ptr: ptr as usize | tag,
-            _marker: std::marker::PhantomData,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// A tagged pointer from its raw word, tags included.
+    #[cfg(feature = "parallel-mark")]
+    pub fn from_raw(ptr: usize) -> Self {
+        Self {
+            ptr,
+            _marker: core::marker::PhantomData,
         }
     }
 
@@ -29,14 +38,15 @@ impl<const TAG_BITS: usize, T> TaggedPtr<TAG_BITS, T> {
         self.ptr = (self.ptr & !((1 << TAG_BITS) - 1)) | tag;
     }
 
+    #[allow(dead_code)]
     pub fn set_ptr(&mut self, ptr: *const T) {
-        assert!(ptr as usize % (1 << TAG_BITS) == 0);
+        assert!((ptr as usize).is_multiple_of(1 << TAG_BITS));
         self.ptr = (ptr as usize) | self.tag();
     }
 }
 
-impl<T> Debug for TaggedPtr<1, T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<const TAG_BITS: usize, T> Debug for TaggedPtr<TAG_BITS, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:p} ({})", self.ptr(), self.tag())
     }
 }
@@ -57,19 +67,64 @@ impl<const TAG_BITS: usize, T> PartialEq for TaggedPtr<TAG_BITS, T> {
 impl<const TAG_BITS: usize, T> Eq for TaggedPtr<TAG_BITS, T> {}
 
 impl<const TAG_BITS: usize, T> PartialOrd for TaggedPtr<TAG_BITS, T> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.ptr.partial_cmp(&other.ptr)
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl<const TAG_BITS: usize, T> Ord for TaggedPtr<TAG_BITS, T> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.ptr.cmp(&other.ptr)
     }
 }
 
-impl<const TAG_BITS: usize, T> std::hash::Hash for TaggedPtr<TAG_BITS, T> {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+impl<const TAG_BITS: usize, T> core::hash::Hash for TaggedPtr<TAG_BITS, T> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.ptr.hash(state);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::TaggedPtr;
+
+    fn round_trip<const TAG_BITS: usize>() {
+        #[repr(align(64))]
+        struct Aligned([u8; 64]);
+        let storage = [Aligned([0; 64]), Aligned([0; 64])];
+        let a = storage[0].0.as_ptr();
+        let b = storage[1].0.as_ptr();
+
+        for tag in 0..(1 << TAG_BITS) {
+            let mut p = TaggedPtr::<TAG_BITS, u8>::new(a, tag);
+            assert_eq!(p.ptr(), a);
+            assert_eq!(p.tag(), tag);
+
+            let other = (1 << TAG_BITS) - 1 - tag;
+            p.set_tag(other);
+            assert_eq!(p.ptr(), a);
+            assert_eq!(p.tag(), other);
+
+            p.set_ptr(b);
+            assert_eq!(p.ptr(), b);
+            assert_eq!(p.tag(), other);
+        }
+    }
+
+    #[test]
+    fn round_trip_2_bits() {
+        round_trip::<2>();
+    }
+
+    #[test]
+    fn round_trip_3_bits() {
+        round_trip::<3>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn tag_out_of_range() {
+        let x = 0u64;
+        TaggedPtr::<2, u8>::new(&x as *const u64 as *const u8, 4);
+    }
+}
@@ -0,0 +1,82 @@
+//! A trait-based alternative to writing [`VTable`] callbacks by hand.
+//!
+//! Implement [`Trace`] for a type and allocate it with [`GCAlloc::alloc`]; the vtable is
+//! generated from the trait. The raw [`VTable`] path stays available for FFI users.
+
+use crate::{gc_ptr::Gc, GCAlloc, SizeKind, VTable};
+
+/// What to do with each pointer field of an object, see [`Trace::visit_fields`].
+pub trait Visitor {
+    /// Visit a pointer field of the object. Rewriting visitors update it in place, through
+    /// [`Gc::set`].
+    fn visit(&mut self, field: &Gc<()>);
+}
+
+/// Marks the objects behind the visited fields.
+pub(crate) struct MarkVisitor<'a>(pub &'a mut GCAlloc);
+
+impl Visitor for MarkVisitor<'_> {
+    fn visit(&mut self, field: &Gc<()>) {
+        self.0.mark_accessible(field.clone());
+    }
+}
+
+/// Points the visited fields to the new locations of their objects.
+pub(crate) struct RewriteVisitor<'a>(pub &'a mut GCAlloc);
+
+impl Visitor for RewriteVisitor<'_> {
+    fn visit(&mut self, field: &Gc<()>) {
+        self.0.rewrite_ptr(field);
+    }
+}
+
+/// A type that can live in the GC heap.
+///
+/// Its pointer fields are found by a single traversal, [`Trace::visit_fields`], which becomes
+/// the [`VTable::visit_cb`] of the type: marking, rewriting, [`GCAlloc::verify`] and
+/// [`GCAlloc::snapshot`] all run it with different visitors.
+///
+/// When an object dies, its `Drop` implementation runs, once. It runs during the collection, so
+/// it must not follow the object's `Gc` pointers, whose targets may already be gone.
+pub trait Trace: Sized {
+    /// Call `visitor` on every pointer in the object. Use [`Gc::erase`] to pass fields of any
+    /// type to the visitor.
+    fn visit_fields(&self, visitor: &mut dyn Visitor);
+
+    /// Whether dead objects are dropped. Defaults to whether the type needs dropping at all;
+    /// set it to `false` to leak the resources of dead objects instead.
+    const DROP: bool = core::mem::needs_drop::<Self>();
+
+    /// The [`VTable::type_id`] of this type.
+    const TYPE_ID: u32 = 0;
+
+    /// The vtable generated for this type.
+    const VTABLE: VTable = VTable {
+        size: SizeKind::of::<Self>(),
+        align: core::mem::align_of::<Self>(),
+        visit_cb: Some(visit_adapter::<Self>),
+        free_cb: if Self::DROP {
+            drop_adapter::<Self>
+        } else {
+            noop
+        },
+        type_id: Self::TYPE_ID,
+        ..VTable::DEFAULT
+    };
+}
+
+fn visit_adapter<T: Trace>(ptr: *const u8, visitor: &mut dyn Visitor) {
+    let obj = unsafe { &*(ptr as *const T) };
+    obj.visit_fields(visitor);
+}
+
+fn drop_adapter<T: Trace>(_gc: &mut GCAlloc, ptr: *const u8) {
+    unsafe { core::ptr::drop_in_place(ptr as *mut T) };
+}
+
+fn noop(_gc: &mut GCAlloc, _ptr: *const u8) {}
+
+/// An object without payload, which only has its identity, e.g. a sentinel.
+impl Trace for () {
+    fn visit_fields(&self, _visitor: &mut dyn Visitor) {}
+}
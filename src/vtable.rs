@@ -1,62 +1,157 @@
-use std::num::NonZeroUsize;
+use core::num::NonZeroUsize;
 
-use crate::{tag_ptr::TaggedPtr, GCAlloc};
+use crate::{tag_ptr::TaggedPtr, GCAlloc, Visitor};
 
-/// Variant to get the size of an object.
+/// Variant to get the size of an object, not including the GC header.
 pub enum SizeKind {
-    /// The size of the object is fixed.
-    Fixed(NonZeroUsize),
-    /// The size of the object is variable. The callback should return the size of the object.
+    /// The size of the object is fixed. It may be 0 for objects that only serve as a distinct
+    /// identity, e.g. sentinels.
+    Fixed(usize),
+    /// The size of the object is variable. The callback should return the size of the object,
+    /// computed from its contents. The pointer points to the object.
+    ///
+    /// The size must stay stable for the whole lifetime of the object, as the collector uses it
+    /// to relocate the object.
     Variable(unsafe fn(*const u8) -> NonZeroUsize),
 }
 
 impl SizeKind {
     pub const fn fixed(size: usize) -> Self {
-        if size == 0 {
-            panic!("Size must be greater than 0");
-        }
-        Self::Fixed(unsafe { NonZeroUsize::new_unchecked(size) })
+        Self::Fixed(size)
     }
 
     pub const fn of<T>() -> Self {
-        Self::fixed(std::mem::size_of::<T>())
+        Self::fixed(core::mem::size_of::<T>())
     }
 
-    pub fn callback(cb: unsafe fn(*const u8) -> NonZeroUsize) -> Self {
+    pub const fn callback(cb: unsafe fn(*const u8) -> NonZeroUsize) -> Self {
         Self::Variable(cb)
     }
 }
 
+/// The callbacks and layout of a type of object.
+///
+/// The low bit of a pointer to a vtable holds the mark bit of the objects using it, so vtables
+/// must be aligned to at least 2 bytes. The fields already align a `VTable` to a pointer, but
+/// one placed in a `#[repr(packed)]` struct, or a pointer cast from an arbitrary address, may
+/// not be; [`GCAlloc::allocate`] panics on such a vtable.
 #[repr(C)]
 pub struct VTable {
-    // /// The size of the object.
-    // pub size: SizeKind,
-    /// Callback on mark. The user is expected to call [`Sweeper::mark_accessible`] on all pointers
-    /// in the object. The pointer is guaranteed to be valid and points to a live object of the
-    /// expected type.
+    /// The size of the object.
+    pub size: SizeKind,
+    /// The alignment of the object, a power of two. Objects are always aligned to at least 16
+    /// bytes, so smaller values have no effect.
+    ///
+    /// Each object with a larger alignment reserves `align - 16` extra bytes of heap, which pad
+    /// it into place wherever the collector moves it.
+    pub align: usize,
+    /// Callback visiting every pointer in the object, see [`Visitor`]. The collector marks and
+    /// rewrites the object by running it with different visitors, and so do
+    /// [`GCAlloc::verify`] and [`GCAlloc::snapshot`] to find its pointers; `mark_cb` and
+    /// `rewrite_cb` are not called. The pointer is guaranteed to be valid and points to a live
+    /// object of the expected type.
+    ///
+    /// Leave it `None` for objects that need the heap while they are traced, e.g. to allocate
+    /// in the rewrite callback or to report a trace error, and set `mark_cb` and `rewrite_cb`
+    /// instead.
+    pub visit_cb: Option<unsafe fn(*const u8, &mut dyn Visitor)>,
+
+    /// Callback on mark, if there is no `visit_cb`. The user is expected to call
+    /// [`GCAlloc::mark_accessible`] on all pointers in the object. The pointer is guaranteed to be
+    /// valid and points to a live object of the expected type.
     pub mark_cb: unsafe fn(&mut GCAlloc, *const u8),
 
-    /// Callback on rewrite. The user is expected to call [`Sweeper::rewrite_ptr`] on all pointers
-    /// in the object, and update them accordingly. The pointer is guaranteed to be valid and points
-    /// to a live object of the expected type.
+    /// Callback on rewrite, if there is no `visit_cb`. The user is expected to call
+    /// [`GCAlloc::rewrite_ptr`] on all pointers in the object, and update them accordingly. The
+    /// pointer is guaranteed to be valid and points to a live object of the expected type.
     pub rewrite_cb: unsafe fn(&mut GCAlloc, *const u8),
 
     /// Callback on free. The user is expected to free all resources associated with the object.
+    /// The pointer points to the dead object, which must not be accessed after the callback.
     pub free_cb: unsafe fn(&mut GCAlloc, *const u8),
+
+    /// Run `free_cb` as a finalizer after the collection completes, instead of inline while the
+    /// collector is relocating objects.
+    ///
+    /// Finalizers run in allocation order once the heap is consistent again, so they may
+    /// allocate. The pointer passed to `free_cb` then points to a copy of the dead object
+    /// outside the heap. Any pointers in it may refer to other dead objects, and must not be
+    /// followed.
+    pub needs_finalize: bool,
+
+    /// A stable id for the type, which [`GCAlloc::downcast_handle`] checks before handing out a
+    /// typed pointer. Types that are never downcast can leave it at 0.
+    pub type_id: u32,
+}
+
+fn noop(_gc: &mut GCAlloc, _ptr: *const u8) {}
+
+impl VTable {
+    /// A vtable for zero-sized objects that hold no pointers or resources, with callbacks that do
+    /// nothing. Use it as the base of struct update syntax to only spell out the fields that
+    /// matter, e.g. `VTable { size, visit_cb, ..VTable::DEFAULT }`; such vtables keep compiling as
+    /// fields with a sensible default are added.
+    pub const DEFAULT: VTable = VTable {
+        size: SizeKind::Fixed(0),
+        align: 1,
+        visit_cb: None,
+        mark_cb: noop,
+        rewrite_cb: noop,
+        free_cb: noop,
+        needs_finalize: false,
+        type_id: 0,
+    };
+
+    /// Get the size of the object at `ptr`, not including the GC header.
+    ///
+    /// # Safety
+    ///
+    /// For [`SizeKind::Variable`], `ptr` must point to an initialized object of this type.
+    pub unsafe fn object_size(&self, ptr: *const u8) -> usize {
+        match self.size {
+            SizeKind::Fixed(sz) => sz,
+            SizeKind::Variable(cb) => unsafe { cb(ptr).get() },
+        }
+    }
 }
 
+/// Number of low bits of a vtable pointer used as tags.
+const TAG_BITS: usize = 1;
+
+const _: () = assert!(core::mem::align_of::<VTable>() >= 1 << TAG_BITS);
+
 /// A tagged pointer to a VTable, with a mark bit. A null pointer is used to represent a free block.
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct VTPtr(TaggedPtr<1, VTable>);
+pub struct VTPtr(TaggedPtr<TAG_BITS, VTable>);
 
 impl VTPtr {
+    /// # Panics
+    ///
+    /// Panics if `ptr` is not aligned to leave room for the tag bits.
     pub fn new(ptr: *const VTable) -> Self {
+        Self::check(ptr);
         Self(TaggedPtr::new(ptr, 0))
     }
 
+    /// Check that `ptr` leaves its low bits free for the tags, before it is dereferenced.
+    pub(crate) fn check(ptr: *const VTable) {
+        assert!(
+            (ptr as usize).is_multiple_of(1 << TAG_BITS),
+            "VTable pointer {:p} is not aligned to {} bytes, which its tag bits need",
+            ptr,
+            1 << TAG_BITS
+        );
+    }
+
+    /// The vtable pointer held by a header word, tags included.
+    #[cfg(feature = "parallel-mark")]
+    pub(crate) fn from_word(word: usize) -> Self {
+        Self(TaggedPtr::from_raw(word))
+    }
+
     pub fn new_free() -> Self {
-        Self(TaggedPtr::new(std::ptr::null(), 0))
+        Self(TaggedPtr::new(core::ptr::null(), 0))
     }
 
     pub fn ptr(&self) -> *const VTable {
@@ -67,11 +162,11 @@ impl VTPtr {
         self.0.ptr().is_null()
     }
 
-    pub fn mark(&mut self) {
+    pub(crate) fn mark(&mut self) {
         self.0.set_tag(1);
     }
 
-    pub fn unmark(&mut self) {
+    pub(crate) fn unmark(&mut self) {
         self.0.set_tag(0);
     }
 
@@ -0,0 +1,108 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc, Trace, Visitor};
+
+#[repr(align(64))]
+struct Aligned {
+    value: usize,
+    next: Option<Gc<Aligned>>,
+}
+
+impl Trace for Aligned {
+    fn visit_fields(&self, visitor: &mut dyn Visitor) {
+        if let Some(next) = &self.next {
+            visitor.visit(next.erase());
+        }
+    }
+}
+
+fn check_list(gc: &GCAlloc, head: Gc<Aligned>, len: usize) {
+    let mut node = Some(head);
+    for i in (0..len).rev() {
+        let n = node.expect("List too short");
+        assert!(gc.in_young_gen(n.clone()));
+        assert_eq!(n.get() as usize % 64, 0);
+        let n = unsafe { &*n.get() };
+        assert_eq!(n.value, i);
+        node = n.next.clone();
+    }
+    assert!(node.is_none());
+}
+
+#[test]
+fn aligned_objects_stay_aligned() {
+    let mut gc = GCAlloc::new(8192);
+
+    let mut head = None;
+    for i in 0..8 {
+        // Garbage of varying size shifts the following objects around.
+        for _ in 0..i % 3 {
+            Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        }
+        if i % 2 == 0 {
+            gc.alloc(Aligned {
+                value: 0,
+                next: None,
+            })
+            .expect("Malloc failed");
+        }
+        head = Some(
+            gc.alloc(Aligned {
+                value: i,
+                next: head,
+            })
+            .expect("Malloc failed"),
+        );
+    }
+    let head = gc.acquire_handle(head.unwrap());
+    check_list(&gc, gc.get_handle(&head), 8);
+
+    for _ in 0..3 {
+        gc.collect();
+        check_list(&gc, gc.get_handle(&head), 8);
+        // Interleave unaligned objects between collections.
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    gc.release_handle(head);
+}
+
+/// Lanes loaded with aligned SIMD instructions.
+#[repr(align(32))]
+struct Lanes([f32; 16]);
+
+impl Trace for Lanes {
+    fn visit_fields(&self, _visitor: &mut dyn Visitor) {}
+}
+
+/// Lanes bigger than half a chunk, which go to the large object space.
+#[repr(align(64))]
+struct BigLanes([f32; 1024]);
+
+impl Trace for BigLanes {
+    fn visit_fields(&self, _visitor: &mut dyn Visitor) {}
+}
+
+#[test]
+fn simd_payloads_stay_aligned() {
+    let mut gc = GCAlloc::new(8192);
+    let mut handles = vec![];
+    for i in 0..6 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        let lanes = gc.alloc(Lanes([i as f32; 16])).expect("Malloc failed");
+        handles.push(gc.acquire_handle(lanes));
+    }
+    let big = gc.alloc(BigLanes([1.0; 1024])).expect("Malloc failed");
+    assert_eq!(big.get() as usize % 64, 0);
+    assert_eq!(gc.get(big).0[1023], 1.0);
+
+    for collect in [GCAlloc::collect, GCAlloc::minor_collect] {
+        collect(&mut gc);
+        for (i, handle) in handles.iter().enumerate() {
+            let lanes = gc.get_handle(handle);
+            assert_eq!(lanes.get() as usize % 32, 0);
+            assert_eq!(gc.get(lanes).0, [i as f32; 16]);
+        }
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    for handle in handles {
+        gc.release_handle(handle);
+    }
+}
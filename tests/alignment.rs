@@ -0,0 +1,74 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc, GCConfig, GCInitError};
+
+fn check_alignment(align: usize) {
+    let mut gc = GCAlloc::with_alignment(4096, align);
+    let mut handles = vec![];
+    for i in 0..8 {
+        let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        assert!((cons.get() as usize).is_multiple_of(align));
+        if i % 2 == 0 {
+            handles.push(gc.acquire_handle(cons));
+        }
+    }
+    // Survivors keep their alignment wherever they are copied to.
+    gc.collect();
+    for handle in &handles {
+        let cons: Gc<Cons> = gc.get_handle(handle);
+        assert!((cons.get() as usize).is_multiple_of(align));
+    }
+    gc.verify();
+}
+
+#[test]
+fn alignment_8() {
+    check_alignment(8);
+}
+
+#[test]
+fn alignment_32() {
+    check_alignment(32);
+}
+
+#[test]
+fn invalid_alignment_is_rejected() {
+    for align in [4, 24] {
+        let config = GCConfig {
+            alignment: Some(align),
+            ..GCConfig::default()
+        };
+        assert!(matches!(
+            GCAlloc::try_with_config(4096, config),
+            Err(GCInitError::InvalidAlignment(a)) if a == align
+        ));
+    }
+}
+
+/// A pointer 16 bytes into an object of a 32-aligned heap.
+fn interior_pointer(gc: &mut GCAlloc) -> Gc<Cons> {
+    let cons = Cons::alloc(gc, None, None).expect("Malloc failed");
+    Gc::new(cons.get().wrapping_byte_add(16))
+}
+
+#[test]
+#[should_panic(expected = "Root to misaligned pointer")]
+fn root_checks_heap_alignment() {
+    let mut gc = GCAlloc::with_alignment(4096, 32);
+    let ptr = interior_pointer(&mut gc);
+    gc.push_root(ptr);
+}
+
+#[test]
+#[should_panic(expected = "Pin to misaligned pointer")]
+fn pin_checks_heap_alignment() {
+    let mut gc = GCAlloc::with_alignment(4096, 32);
+    let ptr = interior_pointer(&mut gc);
+    gc.pin(ptr);
+}
+
+#[test]
+#[should_panic(expected = "Weak handle to misaligned pointer")]
+fn weak_handle_checks_heap_alignment() {
+    let mut gc = GCAlloc::with_alignment(4096, 32);
+    let ptr = interior_pointer(&mut gc);
+    gc.acquire_weak(ptr);
+}
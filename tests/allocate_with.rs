@@ -0,0 +1,47 @@
+use ike_gc::{gc_ptr::Gc, GCAlloc, Trace};
+
+#[derive(Trace)]
+struct Leaf {
+    value: usize,
+}
+
+#[derive(Trace)]
+struct Big {
+    next: Option<Gc<Leaf>>,
+    data: [u64; 512],
+}
+
+#[test]
+fn initializes_large_struct_in_place() {
+    let mut gc = GCAlloc::new(64 * 1024);
+    let leaf = gc.alloc(Leaf { value: 7 }).expect("Malloc failed");
+    let leaf_handle = gc.acquire_handle(leaf.clone());
+
+    // Keep allocating until reserving a slot collects, which moves the leaf under the pointer
+    // the closure writes.
+    let big = loop {
+        let gc_count = gc.metadata().gc_count;
+        let big = gc
+            .allocate_with(&<Big as Trace>::VTABLE, |big: *mut Big| unsafe {
+                assert!((*big).data.iter().all(|&word| word == 0));
+                (*big).next = Some(leaf.clone());
+                for (i, word) in (*big).data.iter_mut().enumerate() {
+                    *word = i as u64;
+                }
+            })
+            .expect("Malloc failed");
+        if gc.metadata().gc_count != gc_count {
+            break big;
+        }
+    };
+
+    let big = gc.get(big);
+    let next = big.next.clone().unwrap();
+    assert_eq!(next.get(), gc.get_handle(&leaf_handle).get());
+    assert_eq!(gc.get(next).value, 7);
+    assert!(big
+        .data
+        .iter()
+        .enumerate()
+        .all(|(i, &word)| word == i as u64));
+}
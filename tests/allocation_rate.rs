@@ -0,0 +1,28 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn counts_allocation_between_collections() {
+    let mut gc = GCAlloc::new(4096);
+    let cons_size = 16 + std::mem::size_of::<Cons>().next_multiple_of(16);
+    for _ in 0..10 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    assert_eq!(gc.bytes_allocated_since_last_gc(), 10 * cons_size);
+    gc.collect();
+    assert_eq!(gc.bytes_allocated_since_last_gc(), 0);
+    assert_eq!(
+        gc.metadata().allocation_rate_bytes_per_gc,
+        (10 * cons_size) as f64
+    );
+
+    for _ in 0..20 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    assert_eq!(gc.bytes_allocated_since_last_gc(), 20 * cons_size);
+    gc.collect();
+    assert_eq!(gc.bytes_allocated_since_last_gc(), 0);
+
+    // The average moves towards the latest collection.
+    let rate = gc.metadata().allocation_rate_bytes_per_gc;
+    assert!(rate > (10 * cons_size) as f64 && rate < (20 * cons_size) as f64);
+}
@@ -0,0 +1,79 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, AllocError, GCAlloc, GcArray, VTable};
+
+fn array_mark(gc: &mut GCAlloc, ptr: *const u8) {
+    let array = unsafe { &*(ptr as *const GcArray<Gc<Cons>>) };
+    for elem in unsafe { array.as_slice() } {
+        gc.mark_accessible(elem.clone());
+    }
+}
+
+fn array_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let array = unsafe { &*(ptr as *const GcArray<Gc<Cons>>) };
+    for elem in unsafe { array.as_slice() } {
+        gc.rewrite_ptr(elem);
+    }
+}
+
+static CONS_ARRAY_VTABLE: VTable = VTable {
+    size: GcArray::<Gc<Cons>>::SIZE,
+    align: std::mem::align_of::<GcArray<Gc<Cons>>>(),
+    mark_cb: array_mark,
+    rewrite_cb: array_rewrite,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn array_of_pointers_survives_collection() {
+    let mut gc = GCAlloc::new(65536);
+    const LEN: usize = 20;
+
+    let array = gc
+        .allocate_array::<Gc<Cons>>(&CONS_ARRAY_VTABLE, LEN)
+        .expect("Malloc failed");
+    assert_eq!(unsafe { (*array.get()).len() }, LEN);
+    for i in 0..LEN {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        // Tag each element by the length of the list it heads.
+        let mut list = None;
+        for _ in 0..i {
+            list = Some(Cons::alloc(&mut gc, None, list).expect("Malloc failed"));
+        }
+        let head = Cons::alloc(&mut gc, None, list).expect("Malloc failed");
+        unsafe {
+            (*(array.get() as *mut GcArray<Gc<Cons>>))
+                .as_mut_ptr()
+                .add(i)
+                .write(head)
+        };
+    }
+    let handle = gc.acquire_handle(array);
+    let before = gc.metadata().currently_allocated;
+
+    gc.collect();
+    assert!(gc.metadata().currently_allocated < before);
+
+    let array = gc.get_handle(&handle);
+    assert!(gc.in_young_gen(array.clone()));
+    let array = unsafe { &*array.get() };
+    assert_eq!(array.len(), LEN);
+    for (i, elem) in unsafe { array.as_slice() }.iter().enumerate() {
+        let mut len = 0;
+        let mut cur = Some(elem.clone());
+        while let Some(cell) = cur {
+            assert!(gc.in_young_gen(cell.clone()));
+            cur = unsafe { (*cell.get()).cdr.clone() };
+            len += 1;
+        }
+        assert_eq!(len, i + 1);
+    }
+    gc.release_handle(handle);
+}
+
+#[test]
+fn oversized_array_fails() {
+    let mut gc = GCAlloc::new(4096);
+    assert!(matches!(
+        gc.allocate_array::<Gc<Cons>>(&CONS_ARRAY_VTABLE, usize::MAX / 4),
+        Err(AllocError::SizeOverflow)
+    ));
+}
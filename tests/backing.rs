@@ -0,0 +1,45 @@
+use ike_gc::{fixtures::Cons, GCAlloc, GCConfig, GCInitError, HeapBacking};
+
+#[test]
+fn cons_over_vec_backing() {
+    let mut gc = GCAlloc::with_backing(vec![0u8; 2 * 65536], GCConfig::default());
+
+    let alloc1 = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let alloc2 = Cons::alloc(&mut gc, Some(alloc1), None).expect("Malloc failed");
+    let alloc3 = Cons::alloc(&mut gc, Some(alloc2), None).expect("Malloc failed");
+    Cons::alloc(&mut gc, Some(alloc3.clone()), None).expect("Malloc failed");
+    let handle3 = gc.acquire_handle(alloc3);
+
+    gc.collect();
+
+    let cons3 = gc.get(gc.get_handle(&handle3));
+    assert!(cons3.cdr.is_none());
+    let cons2 = gc.get(cons3.car.clone().unwrap());
+    assert!(cons2.cdr.is_none());
+    let cons1 = gc.get(cons2.car.clone().unwrap());
+    assert!(cons1.car.is_none() && cons1.cdr.is_none());
+    gc.verify();
+
+    gc.release_handle(handle3);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+/// A backing that starts one byte into a buffer.
+struct Offset(Vec<u8>);
+
+unsafe impl HeapBacking for Offset {
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        unsafe { self.0.as_mut_ptr().add(1) }
+    }
+
+    fn len(&self) -> usize {
+        self.0.len() - 1
+    }
+}
+
+#[test]
+fn misaligned_backing() {
+    let result = GCAlloc::try_with_backing(Offset(vec![0; 4096]), GCConfig::default());
+    assert!(matches!(result, Err(GCInitError::Misaligned)));
+}
@@ -0,0 +1,56 @@
+use ike_gc::{fixtures::Cons, CollectorMode, GCAlloc, GCConfig, GCInitError};
+
+#[test]
+fn builder_applies_options() {
+    let mut gc = GCAlloc::builder()
+        .size(4096)
+        .alignment(32)
+        .gc_threshold(0.5)
+        .growable(1 << 16)
+        .handle_capacity(64)
+        .build()
+        .expect("Failed to build heap");
+    assert_eq!(gc.metadata().semispace_size, 4096);
+    assert!(gc.handle_capacity() >= 64);
+
+    let list = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    assert_eq!(list.get() as usize % 32, 0);
+    let mut list = gc.acquire_handle(list);
+    // Keeping everything alive outgrows the initial heap.
+    for _ in 0..200 {
+        let cdr = gc.get_handle(&list);
+        let cell = Cons::alloc(&mut gc, None, Some(cdr)).expect("Malloc failed");
+        assert_eq!(cell.get() as usize % 32, 0);
+        let cell = gc.acquire_handle(cell);
+        gc.release_handle(std::mem::replace(&mut list, cell));
+    }
+    assert!(gc.metadata().semispace_size > 4096);
+    // The threshold collects before the heap fills up.
+    assert!(gc.used_bytes() <= gc.metadata().semispace_size / 2 + 64);
+    gc.release_handle(list);
+}
+
+#[test]
+fn builder_takes_config() {
+    let gc = GCAlloc::builder()
+        .config(GCConfig {
+            mode: CollectorMode::Compacting,
+            ..GCConfig::default()
+        })
+        .size(8192)
+        .build()
+        .expect("Failed to build heap");
+    assert_eq!(gc.metadata().semispace_size, 8192);
+}
+
+#[test]
+fn builder_reports_invalid_options() {
+    let err = GCAlloc::builder().alignment(24).build().err();
+    assert!(matches!(err, Some(GCInitError::InvalidAlignment(24))));
+}
+
+#[test]
+#[should_panic(expected = "Maximum heap size 1024 is below the initial size 4096")]
+fn growable_below_size_panics() {
+    let _ = GCAlloc::builder().size(4096).growable(1024).build();
+}
@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ike_gc::{gc_ptr::Gc, AllocError, GCAlloc, SizeKind, VTable};
+
+/// An object that gets a fresh leaf allocated for it every time it is rewritten.
+struct Holder {
+    leaf: Option<Gc<Leaf>>,
+    rewrites: usize,
+}
+
+struct Leaf {
+    value: usize,
+}
+
+fn holder_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let holder = unsafe { &mut *(ptr as *mut Holder) };
+    holder.rewrites += 1;
+    holder.leaf = gc
+        .allocate_typed(
+            &LEAF_VTABLE,
+            Leaf {
+                value: holder.rewrites,
+            },
+        )
+        .ok();
+}
+
+static HOLDER_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Holder>(),
+    align: std::mem::align_of::<Holder>(),
+    rewrite_cb: holder_rewrite,
+    ..VTable::DEFAULT
+};
+
+static LEAF_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Leaf>(),
+    align: std::mem::align_of::<Leaf>(),
+    ..VTable::DEFAULT
+};
+
+static MARK_ALLOC_FAILED: AtomicBool = AtomicBool::new(false);
+
+fn allocating_mark(gc: &mut GCAlloc, _ptr: *const u8) {
+    let leaf = gc.allocate_typed(&LEAF_VTABLE, Leaf { value: 0 });
+    MARK_ALLOC_FAILED.store(
+        matches!(leaf, Err(AllocError::DuringCollection)),
+        Ordering::SeqCst,
+    );
+}
+
+static ALLOCATING_MARK_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Leaf>(),
+    align: std::mem::align_of::<Leaf>(),
+    mark_cb: allocating_mark,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn rewrite_callback_allocates_into_to_space() {
+    let mut gc = GCAlloc::new(4096);
+    let holder = gc
+        .allocate_typed(
+            &HOLDER_VTABLE,
+            Holder {
+                leaf: None,
+                rewrites: 0,
+            },
+        )
+        .expect("Malloc failed");
+    let handle = gc.acquire_handle(holder);
+
+    for round in 1..=3 {
+        gc.collect();
+        let holder = unsafe { &*gc.get_handle(&handle).get() };
+        assert_eq!(holder.rewrites, round);
+        // The leaf from the previous round was unreachable and is gone; the new one follows
+        // the holder.
+        let leaf = holder
+            .leaf
+            .clone()
+            .expect("Allocation in rewrite callback failed");
+        assert!(gc.in_young_gen(leaf.clone()));
+        assert_eq!(unsafe { (*leaf.get()).value }, round);
+        let holder_size = (16 + std::mem::size_of::<Holder>()).next_multiple_of(16);
+        let leaf_size = (16 + std::mem::size_of::<Leaf>()).next_multiple_of(16);
+        assert_eq!(gc.metadata().currently_allocated, holder_size + leaf_size);
+    }
+    gc.release_handle(handle);
+}
+
+#[test]
+fn rewrite_rerun_after_collection_allocates_without_collecting() {
+    let mut gc = GCAlloc::new(4096);
+    // Fill the heap with garbage, so the next allocation collects.
+    while gc.metadata().gc_count == 0 {
+        gc.allocate_typed(&LEAF_VTABLE, Leaf { value: 0 })
+            .expect("Malloc failed");
+    }
+    while gc.metadata().currently_allocated + 64 <= 4096 {
+        gc.allocate_typed(&LEAF_VTABLE, Leaf { value: 0 })
+            .expect("Malloc failed");
+    }
+    let gc_count = gc.metadata().gc_count;
+
+    let holder = gc
+        .allocate_typed(
+            &HOLDER_VTABLE,
+            Holder {
+                leaf: None,
+                rewrites: 0,
+            },
+        )
+        .expect("Malloc failed");
+
+    // `allocate_typed` re-ran the rewrite callback, whose allocation didn't collect again.
+    assert_eq!(gc.metadata().gc_count, gc_count + 1);
+    let holder = unsafe { &*holder.get() };
+    assert_eq!(holder.rewrites, 1);
+    let leaf = holder
+        .leaf
+        .clone()
+        .expect("Allocation in rewrite callback failed");
+    assert_eq!(unsafe { (*leaf.get()).value }, 1);
+}
+
+#[test]
+fn mark_callback_cannot_allocate() {
+    let mut gc = GCAlloc::new(4096);
+    let obj = gc
+        .allocate_typed(&ALLOCATING_MARK_VTABLE, Leaf { value: 0 })
+        .expect("Malloc failed");
+    let handle = gc.acquire_handle(obj);
+    gc.collect();
+    assert!(MARK_ALLOC_FAILED.load(Ordering::SeqCst));
+    gc.release_handle(handle);
+}
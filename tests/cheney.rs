@@ -0,0 +1,126 @@
+use ike_gc::{
+    fixtures::{Cons, GcTree},
+    gc_ptr::{Gc, Weak},
+    GCAlloc, GCConfig, SizeKind, VTable,
+};
+
+fn cheney_heap(sz: usize) -> GCAlloc {
+    GCAlloc::with_config(
+        sz,
+        GCConfig {
+            separate_passes: false,
+            ..Default::default()
+        },
+    )
+}
+
+fn sum(gc: &GCAlloc, tree: &Option<Gc<GcTree>>) -> usize {
+    match tree {
+        Some(node) => {
+            let node = gc.get(node.clone());
+            node.value + sum(gc, &node.left) + sum(gc, &node.right)
+        }
+        None => 0,
+    }
+}
+
+#[test]
+fn cheney_scan_matches_regular_collection() {
+    let mut allocated = vec![];
+    for separate_passes in [true, false] {
+        let mut gc = GCAlloc::with_config(
+            1 << 20,
+            GCConfig {
+                separate_passes,
+                ..Default::default()
+            },
+        );
+        let tree = GcTree::alloc_complete(&mut gc, 8, 0);
+        for _ in 0..100 {
+            Cons::alloc(&mut gc, None, None).unwrap();
+        }
+        let tree = gc.acquire_handle(tree.unwrap());
+        GcTree::alloc_complete(&mut gc, 4, 0);
+
+        gc.collect();
+        gc.verify();
+        gc.collect();
+        gc.verify();
+        assert_eq!(sum(&gc, &Some(gc.get_handle(&tree))), (0..255).sum());
+        allocated.push(gc.metadata().currently_allocated);
+        gc.release_handle(tree);
+    }
+    assert_eq!(allocated[0], allocated[1]);
+}
+
+#[test]
+fn cheney_scan_handles_cycles() {
+    let mut gc = cheney_heap(4096);
+    let a = Cons::alloc(&mut gc, None, None).unwrap();
+    let b = Cons::alloc(&mut gc, Some(a.clone()), None).unwrap();
+    unsafe { (*(a.get() as *mut Cons)).car = Some(b) };
+    let a = gc.acquire_handle(a);
+
+    gc.collect();
+    gc.verify();
+    let a_ptr = gc.get_handle(&a);
+    let b_ptr = gc.get(a_ptr.clone()).car.clone().unwrap();
+    let back = gc.get(b_ptr).car.clone().unwrap();
+    assert!(back.ptr_eq(&a_ptr));
+    gc.release_handle(a);
+}
+
+/// An object with a weak pointer.
+struct WeakBox {
+    weak: Weak<Cons>,
+}
+
+fn weak_box_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let weak_box = unsafe { &*(ptr as *const WeakBox) };
+    gc.rewrite_weak(&weak_box.weak);
+}
+
+static WEAK_BOX_VTABLE: VTable = VTable {
+    size: SizeKind::of::<WeakBox>(),
+    align: std::mem::align_of::<WeakBox>(),
+    rewrite_cb: weak_box_rewrite,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn weak_pointer_survives_target_scanned_later() {
+    let mut gc = cheney_heap(4096);
+    let alive = Cons::alloc(&mut gc, None, None).unwrap();
+    let dead = Cons::alloc(&mut gc, None, None).unwrap();
+    let to_alive = gc
+        .allocate_typed(
+            &WEAK_BOX_VTABLE,
+            WeakBox {
+                weak: Weak::new(alive.clone()),
+            },
+        )
+        .unwrap();
+    let to_dead = gc
+        .allocate_typed(
+            &WEAK_BOX_VTABLE,
+            WeakBox {
+                weak: Weak::new(dead),
+            },
+        )
+        .unwrap();
+    // The boxes are rooted first, so they are scanned before the object they point to is
+    // copied.
+    let to_alive = gc.acquire_handle(to_alive);
+    let to_dead = gc.acquire_handle(to_dead);
+    let alive = gc.acquire_handle(alive);
+
+    gc.collect();
+    gc.verify();
+    let weak = gc.get(gc.get_handle(&to_alive)).weak.get().unwrap();
+    assert!(weak.ptr_eq(&gc.get_handle(&alive)));
+    assert!(gc.get(gc.get_handle(&to_dead)).weak.get().is_none());
+
+    gc.release_handle(to_alive);
+    gc.release_handle(to_dead);
+    gc.release_handle(alive);
+}
@@ -0,0 +1,53 @@
+use ike_gc::{fixtures::GcTree, gc_ptr::Gc, AllocError, GCAlloc};
+
+fn count_nodes(tree: &Option<Gc<GcTree>>) -> usize {
+    match tree {
+        None => 0,
+        Some(node) => {
+            let node = unsafe { &*node.get() };
+            1 + count_nodes(&node.left) + count_nodes(&node.right)
+        }
+    }
+}
+
+#[test]
+fn snapshot_heap_holds_only_live_set() {
+    let mut gc = GCAlloc::new(65536);
+
+    GcTree::alloc_complete(&mut gc, 5, 0);
+    let kept = GcTree::alloc_complete(&mut gc, 4, 100).unwrap();
+    GcTree::alloc_complete(&mut gc, 5, 0);
+    let single = GcTree::alloc(&mut gc, 7, None, None).unwrap();
+    let kept = gc.acquire_handle(kept);
+    let single = gc.acquire_handle(single);
+
+    let mut snapshot = gc.collect_into_new(0);
+
+    // 16 nodes, each with a 16-byte header.
+    let node_size = (16 + std::mem::size_of::<GcTree>()).next_multiple_of(16);
+    assert_eq!(snapshot.metadata().currently_allocated, 16 * node_size);
+    let tree = snapshot.get_handle(&kept);
+    assert!(snapshot.in_young_gen(tree.clone()));
+    assert_eq!(unsafe { (*tree.get()).value }, 100);
+    assert_eq!(count_nodes(&Some(tree)), 15);
+    assert_eq!(unsafe { (*snapshot.get_handle(&single).get()).value }, 7);
+
+    // There's no room to spare in the snapshot.
+    assert!(matches!(
+        GcTree::alloc(&mut snapshot, 0, None, None),
+        Err(AllocError::OutOfMemory)
+    ));
+
+    // The original heap is empty and still usable.
+    assert_eq!(gc.metadata().currently_allocated, 0);
+    let fresh = GcTree::alloc(&mut gc, 1, None, None).unwrap();
+    let fresh = gc.acquire_handle(fresh);
+    gc.collect();
+    assert_eq!(unsafe { (*gc.get_handle(&fresh).get()).value }, 1);
+    gc.release_handle(fresh);
+
+    snapshot.release_handle(kept);
+    snapshot.release_handle(single);
+    snapshot.collect();
+    assert_eq!(snapshot.metadata().currently_allocated, 0);
+}
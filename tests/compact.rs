@@ -0,0 +1,113 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, CollectorMode, GCAlloc, GCConfig};
+
+fn compacting(size: usize) -> GCAlloc {
+    GCAlloc::with_config(
+        size,
+        GCConfig {
+            mode: CollectorMode::Compacting,
+            ..GCConfig::default()
+        },
+    )
+}
+
+fn cons_size() -> usize {
+    16 + std::mem::size_of::<Cons>().next_multiple_of(16)
+}
+
+/// A list of `len` cells, each with a leaf in its car, with garbage between all of them.
+fn build_list(gc: &mut GCAlloc, len: usize) -> Gc<Cons> {
+    let mut list = None;
+    for _ in 0..len {
+        Cons::alloc(gc, None, None).expect("Malloc failed");
+        let leaf = Cons::alloc(gc, None, None).expect("Malloc failed");
+        Cons::alloc(gc, None, None).expect("Malloc failed");
+        list = Some(Cons::alloc(gc, Some(leaf), list).expect("Malloc failed"));
+    }
+    list.unwrap()
+}
+
+fn check_list(gc: &GCAlloc, list: Gc<Cons>, len: usize) {
+    let mut cell = Some(list);
+    for _ in 0..len {
+        let cons = gc.get(cell.expect("List too short"));
+        let leaf = gc.get(cons.car.clone().expect("Missing leaf"));
+        assert!(leaf.car.is_none() && leaf.cdr.is_none());
+        cell = cons.cdr.clone();
+    }
+    assert!(cell.is_none());
+}
+
+#[test]
+fn survivors_are_slid_down() {
+    let mut gc = compacting(4096);
+    let list = build_list(&mut gc, 8);
+    let before = list.get();
+    let list = gc.acquire_handle(list);
+
+    gc.collect();
+    gc.verify();
+    let list_ptr = gc.get_handle(&list);
+    assert!((list_ptr.get() as usize) < before as usize);
+    check_list(&gc, list_ptr, 8);
+    // The survivors fill the start of the space, with no garbage between them.
+    let meta = gc.metadata();
+    assert_eq!(meta.currently_allocated, 16 * cons_size());
+    assert_eq!(gc.used_bytes(), 16 * cons_size());
+
+    gc.release_handle(list);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+fn compacting_heap_collects_when_full() {
+    let mut gc = compacting(4096);
+    let list = build_list(&mut gc, 4);
+    let list = gc.acquire_handle(list);
+    // Far more than fits in the space at once.
+    for _ in 0..1000 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    assert!(gc.metadata().gc_count > 0);
+    check_list(&gc, gc.get_handle(&list), 4);
+    gc.release_handle(list);
+}
+
+#[test]
+fn pinned_objects_stay_in_place() {
+    let mut gc = compacting(4096);
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let before = build_list(&mut gc, 2);
+    let before = gc.acquire_handle(before);
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let pinned = Cons::alloc(&mut gc, Some(leaf), None).expect("Malloc failed");
+    let address = pinned.get();
+    let pin = gc.pin(pinned);
+    let weak = gc.acquire_weak(gc.get_pinned(&pin));
+    let after = build_list(&mut gc, 2);
+    let after = gc.acquire_handle(after);
+
+    for _ in 0..3 {
+        gc.collect();
+        gc.verify();
+        assert_eq!(gc.get_pinned(&pin).get(), address);
+        assert!(gc.upgrade(&weak).unwrap().ptr_eq(&gc.get_pinned(&pin)));
+        let leaf = gc.get(gc.get_pinned(&pin)).car.clone().unwrap();
+        assert!((leaf.get() as usize) < address as usize);
+        check_list(&gc, gc.get_handle(&before), 2);
+        check_list(&gc, gc.get_handle(&after), 2);
+    }
+    // Five live cells slid down in front of the pinned one, which was the twelfth, leaving six
+    // cells of free space before it. The four cells after it slid up to it.
+    assert_eq!(gc.metadata().currently_allocated, 16 * cons_size());
+
+    gc.unpin(pin);
+    gc.collect();
+    assert!(gc.upgrade(&weak).is_none());
+    gc.release_handle(before);
+    gc.release_handle(after);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+    gc.release_weak(weak);
+}
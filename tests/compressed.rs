@@ -0,0 +1,107 @@
+use ike_gc::{
+    gc_ptr::{CompressedGc, Gc},
+    GCAlloc, Handle, SizeKind, VTable,
+};
+
+/// A cons cell of compressed pointers, half the size of one holding `Gc`s.
+struct Cons {
+    car: CompressedGc<Cons>,
+    cdr: CompressedGc<Cons>,
+    value: u32,
+}
+
+fn cons_mark(gc: &mut GCAlloc, ptr: *const u8) {
+    let cons = unsafe { &*(ptr as *const Cons) };
+    gc.mark_compressed(&cons.car);
+    gc.mark_compressed(&cons.cdr);
+}
+
+fn cons_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let cons = unsafe { &*(ptr as *const Cons) };
+    gc.rewrite_compressed(&cons.car);
+    gc.rewrite_compressed(&cons.cdr);
+}
+
+static CONS_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Cons>(),
+    align: 8,
+    mark_cb: cons_mark,
+    rewrite_cb: cons_rewrite,
+    ..VTable::DEFAULT
+};
+
+fn alloc(gc: &mut GCAlloc, car: Option<Gc<Cons>>, cdr: Option<Gc<Cons>>, value: u32) -> Gc<Cons> {
+    let car = car.map_or(CompressedGc::null(), |car| gc.compress(car));
+    let cdr = cdr.map_or(CompressedGc::null(), |cdr| gc.compress(cdr));
+    gc.allocate_typed(&CONS_VTABLE, Cons { car, cdr, value })
+        .expect("Malloc failed")
+}
+
+/// Check that `list` is a list of the values `0..len` in reverse, each with a leaf as its car.
+fn check_list(gc: &GCAlloc, list: Gc<Cons>, len: u32) {
+    let mut cell = Some(list);
+    for value in (0..len).rev() {
+        let cons = gc.get(cell.take().expect("List is too short"));
+        assert_eq!(cons.value, value);
+        let car = gc.decompress(&cons.car).expect("Missing car");
+        assert_eq!(gc.get(car).value, value + 100);
+        cell = gc.decompress(&cons.cdr);
+    }
+    assert!(cell.is_none());
+}
+
+fn build_list(gc: &mut GCAlloc, len: u32) -> Handle<Cons> {
+    let mut list: Option<Handle<Cons>> = None;
+    for value in 0..len {
+        // Garbage between the live cells, so they have to move.
+        alloc(gc, None, None, 0);
+        let leaf = alloc(gc, None, None, value + 100);
+        let leaf = gc.acquire_handle(leaf);
+        // Allocating may collect, after which the compressed pointers taken before it are
+        // rewritten.
+        let cdr = list.as_ref().map(|list| gc.get_handle(list));
+        let cons = alloc(gc, Some(gc.get_handle(&leaf)), cdr, value);
+        gc.release_handle(leaf);
+        if let Some(list) = list.replace(gc.acquire_handle(cons)) {
+            gc.release_handle(list);
+        }
+    }
+    list.unwrap()
+}
+
+#[test]
+fn compressed_pointers_are_half_size() {
+    assert_eq!(std::mem::size_of::<CompressedGc<Cons>>(), 4);
+    assert_eq!(std::mem::size_of::<Cons>(), 12);
+}
+
+#[test]
+fn compressed_list_survives_collection() {
+    let mut gc = GCAlloc::new(1 << 16);
+    let list = build_list(&mut gc, 50);
+    let before = gc.get_handle(&list);
+    gc.collect();
+    assert!(!before.ptr_eq(&gc.get_handle(&list)));
+    check_list(&gc, gc.get_handle(&list), 50);
+    gc.collect();
+    check_list(&gc, gc.get_handle(&list), 50);
+    gc.verify();
+}
+
+#[test]
+fn compressed_pointers_follow_heap_into_new_mapping() {
+    let mut gc = GCAlloc::with_growable(4096, 1 << 20);
+    let list = build_list(&mut gc, 200);
+    assert!(gc.metadata().semispace_size > 4096);
+    check_list(&gc, gc.get_handle(&list), 200);
+    gc.collect();
+    check_list(&gc, gc.get_handle(&list), 200);
+}
+
+#[test]
+#[should_panic(expected = "can't be compressed")]
+fn large_objects_cannot_be_compressed() {
+    let mut gc = GCAlloc::new(4096);
+    let large = gc.alloc_str(&"x".repeat(8192)).expect("Malloc failed");
+    gc.compress(large);
+}
@@ -0,0 +1,43 @@
+use std::hint::black_box;
+
+use ike_gc::{fixtures::GcTree, gc_ptr::Gc, GCAlloc};
+
+fn sum(gc: &GCAlloc, tree: &Option<Gc<GcTree>>) -> usize {
+    match tree {
+        None => 0,
+        Some(node) => {
+            let node = gc.get(node.clone());
+            node.value + sum(gc, &node.left) + sum(gc, &node.right)
+        }
+    }
+}
+
+#[inline(never)]
+fn build(gc: &mut GCAlloc) -> Gc<GcTree> {
+    GcTree::alloc_complete(gc, 4, 0);
+    let tree = GcTree::alloc_complete(gc, 3, 1).unwrap();
+    GcTree::alloc_complete(gc, 4, 0);
+    tree
+}
+
+#[test]
+fn root_on_stack_survives_in_place() {
+    let mut gc = GCAlloc::new(65536);
+    let tree = build(&mut gc);
+    let addr = tree.get();
+    let tree = black_box(tree);
+
+    gc.collect_conservative();
+    assert!(gc.metadata().last_reclaimed > 0);
+    // The root can't be rewritten, so it stays put, while its children move.
+    assert_eq!(tree.get(), addr);
+    assert_eq!(sum(&gc, &Some(tree.clone())), (1..=7).sum());
+    gc.verify();
+
+    // The stack still points to the root, so the next conservative collection keeps it too.
+    gc.collect_conservative();
+    assert_eq!(tree.get(), addr);
+    assert_eq!(sum(&gc, &Some(tree.clone())), (1..=7).sum());
+    gc.verify();
+    black_box(&tree);
+}
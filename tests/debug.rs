@@ -0,0 +1,31 @@
+use ike_gc::{fixtures::Cons, GCAlloc, Trace};
+
+#[derive(Trace, Debug)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn debug_prints_address() {
+    let mut gc = GCAlloc::new(4096);
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+    let formatted = format!("{:?}", cell);
+    assert_eq!(formatted, format!("Gc({:p})", cell.get()));
+    assert!(formatted.contains("0x"));
+    assert_eq!(format!("{:p}", cell), format!("{:p}", cell.get()));
+
+    let nested = format!("{:?}", Some(cell.clone()));
+    assert!(nested.contains(&format!("{:p}", cell.get())));
+}
+
+#[test]
+fn debug_deref_prints_object() {
+    let mut gc = GCAlloc::new(4096);
+    let point = gc.alloc(Point { x: 1, y: -2 }).unwrap();
+    assert_eq!(gc.get(point.clone()).x + gc.get(point.clone()).y, -1);
+    assert_eq!(
+        format!("{:?}", unsafe { point.debug_deref() }),
+        "Point { x: 1, y: -2 }"
+    );
+}
@@ -0,0 +1,59 @@
+use ike_gc::{gc_ptr::Gc, GCAlloc, Trace};
+
+#[derive(Trace)]
+struct Leaf {
+    value: usize,
+}
+
+/// Plain data holding pointers, embedded in a heap object rather than allocated on its own.
+#[derive(Trace)]
+struct Pair(Gc<Leaf>, Option<Gc<Leaf>>);
+
+#[derive(Trace)]
+struct Node {
+    id: usize,
+    next: Option<Gc<Node>>,
+    #[trace]
+    pair: Pair,
+}
+
+#[test]
+fn derived_struct_survives_collection() {
+    let mut gc = GCAlloc::new(4096);
+
+    let mut node = None;
+    for id in 0..3 {
+        // Garbage between the live objects, so they have to move.
+        gc.alloc(Leaf { value: 0 }).expect("Malloc failed");
+        let first = gc.alloc(Leaf { value: id * 10 }).expect("Malloc failed");
+        let second = gc
+            .alloc(Leaf { value: id * 10 + 1 })
+            .expect("Malloc failed");
+        node = Some(
+            gc.alloc(Node {
+                id,
+                next: node,
+                pair: Pair(first, Some(second)),
+            })
+            .expect("Malloc failed"),
+        );
+    }
+    let handle = gc.acquire_handle(node.unwrap());
+    let before = gc.metadata().currently_allocated;
+
+    gc.collect();
+    assert!(gc.metadata().currently_allocated < before);
+
+    let mut node = Some(gc.get_handle(&handle));
+    for id in (0..3).rev() {
+        let n = unsafe { &*node.unwrap().get() };
+        assert_eq!(n.id, id);
+        assert!(gc.in_young_gen(n.pair.0.clone()));
+        assert_eq!(unsafe { (*n.pair.0.get()).value }, id * 10);
+        let second = n.pair.1.clone().unwrap();
+        assert_eq!(unsafe { (*second.get()).value }, id * 10 + 1);
+        node = n.next.clone();
+    }
+    assert!(node.is_none());
+    gc.release_handle(handle);
+}
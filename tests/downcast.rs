@@ -0,0 +1,54 @@
+use ike_gc::{gc_ptr::Gc, GCAlloc, Trace, Visitor};
+
+const NUMBER: u32 = 1;
+const PAIR: u32 = 2;
+
+struct Number {
+    value: u64,
+}
+
+impl Trace for Number {
+    fn visit_fields(&self, _visitor: &mut dyn Visitor) {}
+
+    const TYPE_ID: u32 = NUMBER;
+}
+
+struct Pair {
+    left: Gc<Number>,
+    right: Gc<Number>,
+}
+
+impl Trace for Pair {
+    fn visit_fields(&self, visitor: &mut dyn Visitor) {
+        visitor.visit(self.left.erase());
+        visitor.visit(self.right.erase());
+    }
+
+    const TYPE_ID: u32 = PAIR;
+}
+
+#[test]
+fn downcast_checks_the_type_id() {
+    let mut gc = GCAlloc::new(65536);
+    let one = gc.alloc(Number { value: 1 }).unwrap();
+    let two = gc.alloc(Number { value: 2 }).unwrap();
+    let pair = gc
+        .alloc(Pair {
+            left: one.clone(),
+            right: two,
+        })
+        .unwrap();
+    let handles = [
+        gc.acquire_handle(one).erase(),
+        gc.acquire_handle(pair).erase(),
+    ];
+    gc.collect();
+
+    let number = gc.downcast_handle::<Number>(&handles[0], NUMBER).unwrap();
+    assert_eq!(gc.get(number).value, 1);
+    let pair = gc.downcast_handle::<Pair>(&handles[1], PAIR).unwrap();
+    assert_eq!(gc.get(gc.get(pair).right.clone()).value, 2);
+
+    assert!(gc.downcast_handle::<Pair>(&handles[0], PAIR).is_none());
+    assert!(gc.downcast_handle::<Number>(&handles[1], NUMBER).is_none());
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ike_gc::{gc_ptr::Gc, GCAlloc, Trace, Visitor};
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Trace)]
+struct Resource {
+    next: Option<Gc<Resource>>,
+    _buffer: Box<[u8; 64]>,
+}
+
+impl Drop for Resource {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+static LEAKED: AtomicUsize = AtomicUsize::new(0);
+
+/// Opts out of being dropped, so its drop counter never moves.
+struct Leaky(#[allow(dead_code)] u64);
+
+impl Drop for Leaky {
+    fn drop(&mut self) {
+        LEAKED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Trace for Leaky {
+    fn visit_fields(&self, _visitor: &mut dyn Visitor) {}
+
+    const DROP: bool = false;
+}
+
+#[test]
+fn dead_objects_are_dropped_once() {
+    let mut gc = GCAlloc::new(4096);
+    let mut list = None;
+    for _ in 0..3 {
+        let cell = gc
+            .alloc(Resource {
+                next: list,
+                _buffer: Box::new([0; 64]),
+            })
+            .expect("Malloc failed");
+        list = Some(cell);
+    }
+    let list = gc.acquire_handle(list.unwrap());
+    gc.alloc(Resource {
+        next: None,
+        _buffer: Box::new([0; 64]),
+    })
+    .expect("Malloc failed");
+    // Survivors are moved, not dropped.
+    gc.collect();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+    gc.collect();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+
+    gc.release_handle(list);
+    gc.collect();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 4);
+    gc.collect();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn opted_out_objects_are_not_dropped() {
+    let mut gc = GCAlloc::new(4096);
+    gc.alloc(Leaky(0)).expect("Malloc failed");
+    gc.collect();
+    assert_eq!(LEAKED.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ike_gc::{fixtures::Cons, GCAlloc, SizeKind, VTable};
+
+struct Resource {
+    id: usize,
+}
+
+static FINALIZED: AtomicUsize = AtomicUsize::new(0);
+static ID_SUM: AtomicUsize = AtomicUsize::new(0);
+
+fn resource_finalize(gc: &mut GCAlloc, ptr: *const u8) {
+    let resource = unsafe { &*(ptr as *const Resource) };
+    FINALIZED.fetch_add(1, Ordering::SeqCst);
+    ID_SUM.fetch_add(resource.id, Ordering::SeqCst);
+    // The collector is idle, so finalizers may allocate.
+    Cons::alloc(gc, None, None).expect("Allocation in finalizer failed");
+}
+
+static RESOURCE_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Resource>(),
+    align: std::mem::align_of::<Resource>(),
+    free_cb: resource_finalize,
+    needs_finalize: true,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn finalizers_run_after_collection() {
+    let mut gc = GCAlloc::new(65536);
+
+    let mut handles = Vec::new();
+    for id in 1..=6 {
+        let ptr = gc
+            .allocate_typed(&RESOURCE_VTABLE, Resource { id })
+            .expect("Malloc failed");
+        if id % 2 == 0 {
+            handles.push(gc.acquire_handle(ptr));
+        }
+    }
+
+    gc.collect();
+    assert_eq!(FINALIZED.load(Ordering::SeqCst), 3);
+    assert_eq!(ID_SUM.load(Ordering::SeqCst), 1 + 3 + 5);
+    assert_eq!(gc.metadata().gc_count, 1);
+
+    for handle in handles {
+        gc.release_handle(handle);
+    }
+    gc.collect();
+    assert_eq!(FINALIZED.load(Ordering::SeqCst), 6);
+    assert_eq!(ID_SUM.load(Ordering::SeqCst), 21);
+
+    // Only the cons cells allocated by the finalizers are left, and they're garbage.
+    gc.collect();
+    assert_eq!(FINALIZED.load(Ordering::SeqCst), 6);
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+/// A resource that must be dropped in place at its full alignment.
+#[repr(align(64))]
+struct AlignedResource {
+    id: usize,
+}
+
+static ALIGNED_FINALIZED: AtomicUsize = AtomicUsize::new(0);
+
+fn aligned_finalize(_gc: &mut GCAlloc, ptr: *const u8) {
+    assert_eq!(ptr as usize % 64, 0, "Finalizer got a misaligned object");
+    let resource = unsafe { &*(ptr as *const AlignedResource) };
+    assert!(resource.id < 4);
+    ALIGNED_FINALIZED.fetch_add(1, Ordering::SeqCst);
+}
+
+static ALIGNED_RESOURCE_VTABLE: VTable = VTable {
+    size: SizeKind::of::<AlignedResource>(),
+    align: std::mem::align_of::<AlignedResource>(),
+    free_cb: aligned_finalize,
+    needs_finalize: true,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn finalizers_see_over_aligned_objects_aligned() {
+    let mut gc = GCAlloc::new(65536);
+    for id in 0..4 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        gc.allocate_typed(&ALIGNED_RESOURCE_VTABLE, AlignedResource { id })
+            .expect("Malloc failed");
+    }
+    gc.collect();
+    assert_eq!(ALIGNED_FINALIZED.load(Ordering::SeqCst), 4);
+}
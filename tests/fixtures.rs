@@ -0,0 +1,84 @@
+use std::rc::Rc;
+
+use ike_gc::{
+    fixtures::{Cons, GcBox, GcTree},
+    gc_ptr::Gc,
+    GCAlloc,
+};
+
+/// Check that `tree` is a complete tree of the given depth numbered in pre-order from `first`.
+fn check_tree(gc: &GCAlloc, tree: &Option<Gc<GcTree>>, depth: usize, first: usize) {
+    if depth == 0 {
+        assert!(tree.is_none());
+        return;
+    }
+    let node = tree.as_ref().expect("Missing node");
+    assert!(gc.in_young_gen(node.clone()));
+    let node = unsafe { &*node.get() };
+    assert_eq!(node.value, first);
+    check_tree(gc, &node.left, depth - 1, first + 1);
+    check_tree(gc, &node.right, depth - 1, first + (1 << (depth - 1)));
+}
+
+#[test]
+fn tree_survives_collection() {
+    let mut gc = GCAlloc::new(65536);
+
+    // Interleave garbage so the tree has to move.
+    GcTree::alloc_complete(&mut gc, 4, 0);
+    let tree = GcTree::alloc_complete(&mut gc, 6, 0);
+    GcTree::alloc_complete(&mut gc, 4, 0);
+    let handle = gc.acquire_handle(tree.unwrap());
+
+    gc.collect();
+    check_tree(&gc, &Some(gc.get_handle(&handle)), 6, 0);
+    gc.collect();
+    check_tree(&gc, &Some(gc.get_handle(&handle)), 6, 0);
+
+    gc.release_handle(handle);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+fn cons_list_survives_collection() {
+    let mut gc = GCAlloc::new(65536);
+
+    let mut list = None;
+    for _ in 0..10 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        list = Some(Cons::alloc(&mut gc, None, list).expect("Malloc failed"));
+    }
+    let handle = gc.acquire_handle(list.unwrap());
+    gc.collect();
+
+    let mut len = 0;
+    let mut cur = Some(gc.get_handle(&handle));
+    while let Some(cell) = cur {
+        assert!(gc.in_young_gen(cell.clone()));
+        let cell = unsafe { &*cell.get() };
+        assert!(cell.car.is_none());
+        cur = cell.cdr.clone();
+        len += 1;
+    }
+    assert_eq!(len, 10);
+    gc.release_handle(handle);
+}
+
+#[test]
+fn box_is_dropped_when_collected() {
+    let mut gc = GCAlloc::new(4096);
+    let counter = Rc::new(());
+
+    let kept = GcBox::alloc(&mut gc, counter.clone()).expect("Malloc failed");
+    GcBox::alloc(&mut gc, counter.clone()).expect("Malloc failed");
+    assert_eq!(Rc::strong_count(&counter), 3);
+
+    let handle = gc.acquire_handle(kept);
+    gc.collect();
+    assert_eq!(Rc::strong_count(&counter), 2);
+
+    gc.release_handle(handle);
+    gc.collect();
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
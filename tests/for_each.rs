@@ -0,0 +1,49 @@
+use ike_gc::{
+    fixtures::{Cons, GcTree, CONS_VTABLE, GC_TREE_VTABLE},
+    GCAlloc,
+};
+
+/// Number of cons cells and tree nodes in the heap.
+fn count(gc: &GCAlloc) -> (usize, usize) {
+    let (mut cons, mut trees) = (0, 0);
+    gc.for_each_object(|hdr, vt| {
+        assert!(!vt.is_marked());
+        assert!(unsafe { (*hdr).size() } >= 16);
+        if std::ptr::eq(vt.ptr(), &CONS_VTABLE) {
+            cons += 1;
+        } else if std::ptr::eq(vt.ptr(), &GC_TREE_VTABLE) {
+            trees += 1;
+        } else {
+            panic!("Unexpected vtable {:p}", vt.ptr());
+        }
+    });
+    (cons, trees)
+}
+
+#[test]
+fn counts_live_objects() {
+    let mut gc = GCAlloc::new(4096);
+    assert_eq!(count(&gc), (0, 0));
+
+    let mut kept = vec![];
+    for i in 0..6 {
+        let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        if i % 2 == 0 {
+            kept.push(gc.acquire_handle(cons));
+        }
+    }
+    let tree = GcTree::alloc_complete(&mut gc, 3, 0).unwrap();
+    let tree = gc.acquire_handle(tree);
+    assert_eq!(count(&gc), (6, 7));
+
+    // Dead objects are gone after a collection.
+    gc.collect();
+    assert_eq!(count(&gc), (3, 7));
+
+    gc.release_handle(tree);
+    for handle in kept {
+        gc.release_handle(handle);
+    }
+    gc.collect();
+    assert_eq!(count(&gc), (0, 0));
+}
@@ -0,0 +1,32 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn exactly_full_heap_collects() {
+    let mut probe = GCAlloc::new(1024);
+    Cons::alloc(&mut probe, None, None).unwrap();
+    let chunk = 20 * probe.metadata().currently_allocated;
+
+    let mut gc = GCAlloc::new(chunk);
+    let mut handles = vec![];
+    while gc.metadata().currently_allocated < chunk {
+        let cell = Cons::alloc(&mut gc, None, None).unwrap();
+        handles.push(gc.acquire_handle(cell));
+    }
+    assert_eq!(gc.metadata().currently_allocated, chunk);
+    gc.verify();
+
+    // Everything survives, so the to-space fills up exactly too.
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, chunk);
+    gc.verify();
+    gc.collect();
+    gc.verify();
+
+    for handle in handles {
+        gc.release_handle(handle);
+    }
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+    Cons::alloc(&mut gc, None, None).unwrap();
+    gc.verify();
+}
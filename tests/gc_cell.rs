@@ -0,0 +1,71 @@
+use ike_gc::{
+    fixtures::Cons,
+    gc_ptr::{Gc, GcCell},
+    GCAlloc, GCConfig, Trace,
+};
+
+#[derive(Trace)]
+struct Holder {
+    #[trace]
+    slot: GcCell<Option<Gc<Cons>>>,
+}
+
+#[test]
+fn gc_cell_set_survives_collection() {
+    let mut gc = GCAlloc::new(4096);
+    let holder = gc
+        .alloc(Holder {
+            slot: GcCell::new(None),
+        })
+        .expect("Malloc failed");
+    let holder = gc.acquire_handle(holder);
+
+    // Garbage in front of the cons, so it has to move.
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let cons = Cons::alloc(&mut gc, Some(leaf), None).expect("Malloc failed");
+    let container = gc.get_handle(&holder);
+    gc.set_cell(container, |holder| &holder.slot, Some(cons.clone()));
+    gc.collect();
+
+    let stored = gc.read(&holder).slot.get().expect("Slot was cleared");
+    assert!(!stored.ptr_eq(&cons));
+    let car = gc.get(stored).car.clone().expect("Missing car");
+    assert!(gc.get(car).car.is_none());
+
+    let container = gc.get_handle(&holder);
+    gc.set_cell(container, |holder| &holder.slot, None);
+    gc.collect();
+    assert!(gc.read(&holder).slot.get().is_none());
+    let holder_size = gc.metadata().currently_allocated;
+    assert_eq!(
+        holder_size,
+        16 + std::mem::size_of::<Holder>().next_multiple_of(16)
+    );
+}
+
+#[test]
+fn gc_cell_set_runs_write_barrier() {
+    let config = GCConfig {
+        nursery_size: Some(4096),
+        ..Default::default()
+    };
+    let mut gc = GCAlloc::with_config(1 << 16, config);
+    let holder = gc
+        .alloc(Holder {
+            slot: GcCell::new(None),
+        })
+        .expect("Malloc failed");
+    let holder = gc.acquire_handle(holder);
+    gc.minor_collect();
+
+    // The holder is old now, and is the only thing pointing to the young cons.
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let container = gc.get_handle(&holder);
+    gc.set_cell(container, |holder| &holder.slot, Some(cons));
+    gc.minor_collect();
+
+    let stored = gc.read(&holder).slot.get().expect("Slot was cleared");
+    assert!(gc.get(stored).cdr.is_none());
+    gc.verify();
+}
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use ike_gc::{fixtures::GcTree, GCAlloc, GCConfig};
+
+#[test]
+fn collections_are_timed() {
+    let mut gc = GCAlloc::new(1 << 20);
+    assert_eq!(gc.metadata().total_gc_time, Duration::ZERO);
+
+    GcTree::alloc_complete(&mut gc, 8, 0);
+    let tree = GcTree::alloc_complete(&mut gc, 10, 0).unwrap();
+    let tree = gc.acquire_handle(tree);
+    gc.collect();
+    let first = gc.metadata().last_gc_time;
+    assert!(first > Duration::ZERO);
+    assert_eq!(gc.metadata().total_gc_time, first);
+
+    gc.release_handle(tree);
+    gc.collect();
+    let meta = gc.metadata();
+    assert_eq!(meta.total_gc_time, first + meta.last_gc_time);
+}
+
+#[test]
+fn phases_are_timed() {
+    // A Cheney scan would count marking and rewriting as copying.
+    let config = GCConfig {
+        separate_passes: true,
+        ..Default::default()
+    };
+    let mut gc = GCAlloc::with_config(1 << 20, config);
+    GcTree::alloc_complete(&mut gc, 8, 0);
+    let tree = GcTree::alloc_complete(&mut gc, 10, 0).unwrap();
+    let tree = gc.acquire_handle(tree);
+    gc.collect();
+
+    let meta = gc.metadata();
+    let phases = meta.last_phase_timings;
+    assert!(phases.mark_roots > Duration::ZERO);
+    assert!(phases.mark > Duration::ZERO);
+    assert!(phases.copy > Duration::ZERO);
+    assert!(phases.rewrite > Duration::ZERO);
+    // Only setting up and wrapping up the collection is left out.
+    assert!(phases.total() <= meta.last_gc_time);
+
+    gc.release_handle(tree);
+    gc.collect();
+    let phases = gc.metadata().last_phase_timings;
+    assert!(phases.total() <= gc.metadata().last_gc_time);
+}
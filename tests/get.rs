@@ -0,0 +1,66 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc};
+
+#[test]
+fn get_and_get_mut() {
+    let mut gc = GCAlloc::new(4096);
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let handle = gc.acquire_handle(cons.clone());
+
+    gc.get_mut(cons).car = Some(leaf);
+    gc.collect();
+
+    let cons = gc.get(gc.get_handle(&handle));
+    let leaf = gc.get(cons.car.clone().unwrap());
+    assert!(leaf.car.is_none() && leaf.cdr.is_none());
+}
+
+#[test]
+fn read_and_read_mut() {
+    let mut gc = GCAlloc::new(4096);
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let handle = gc.acquire_handle(cons);
+
+    gc.read_mut(&handle).cdr = Some(leaf);
+    gc.collect();
+
+    let leaf = gc.get(gc.read(&handle).cdr.clone().unwrap());
+    assert!(leaf.car.is_none() && leaf.cdr.is_none());
+    assert!(gc.read(&handle).car.is_none());
+}
+
+#[test]
+fn fields_are_read_inside_with() {
+    let mut gc = GCAlloc::new(4096);
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let cons = Cons::alloc(&mut gc, Some(leaf), None).expect("Malloc failed");
+    let handle = gc.acquire_handle(cons);
+    gc.collect();
+
+    let leaves = gc.with(|heap| {
+        let cons = heap.read(&handle);
+        let leaf = heap.get(cons.car.clone().unwrap());
+        assert!(cons.cdr.is_none());
+        [leaf.car.is_none(), leaf.cdr.is_none()]
+    });
+    assert_eq!(leaves, [true, true]);
+}
+
+#[test]
+#[should_panic(expected = "Misaligned pointer")]
+fn get_rejects_dangling_pointer() {
+    let gc = GCAlloc::new(4096);
+    gc.get(Gc::<Cons>::dangling());
+}
+
+#[test]
+#[should_panic(expected = "is outside of the heap")]
+fn get_rejects_stale_pointer() {
+    let mut gc = GCAlloc::new(4096);
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let handle = gc.acquire_handle(cons.clone());
+    gc.collect();
+    assert!(!gc.get_handle(&handle).ptr_eq(&cons));
+    gc.get(cons);
+}
@@ -0,0 +1,61 @@
+use ike_gc::{fixtures::Cons, GCAlloc, Handle};
+
+const LENGTH: usize = 1000;
+
+/// Build a rooted list of up to `LENGTH` cells with garbage in between, stopping at the first
+/// failed allocation.
+fn build_list(gc: &mut GCAlloc) -> Handle<Cons> {
+    let cell = Cons::alloc(gc, None, None).expect("Malloc failed");
+    let mut head = gc.acquire_handle(cell);
+    for _ in 1..LENGTH {
+        if Cons::alloc(gc, None, None).is_err() {
+            break;
+        }
+        let cdr = gc.get_handle(&head);
+        let Ok(cell) = Cons::alloc(gc, None, Some(cdr)) else {
+            break;
+        };
+        gc.release_handle(head);
+        head = gc.acquire_handle(cell);
+    }
+    head
+}
+
+fn list_length(gc: &GCAlloc, head: &Handle<Cons>) -> usize {
+    let mut cell = Some(gc.get_handle(head));
+    let mut len = 0;
+    while let Some(c) = cell {
+        assert!(gc.in_young_gen(c.clone()));
+        len += 1;
+        cell = unsafe { (*c.get()).cdr.clone() };
+    }
+    len
+}
+
+#[test]
+fn fixed_heap_runs_out() {
+    let mut gc = GCAlloc::new(4096);
+    let head = build_list(&mut gc);
+    assert!(list_length(&gc, &head) < LENGTH);
+}
+
+#[test]
+fn growable_heap_grows_past_initial_size() {
+    let mut gc = GCAlloc::with_growable(4096, 1 << 20);
+    let head = build_list(&mut gc);
+    assert_eq!(list_length(&gc, &head), LENGTH);
+    assert!(gc.metadata().currently_allocated > 4096);
+
+    gc.collect();
+    assert_eq!(list_length(&gc, &head), LENGTH);
+    gc.release_handle(head);
+}
+
+#[test]
+fn growable_heap_stops_at_max() {
+    let mut gc = GCAlloc::with_growable(4096, 8192);
+    let head = build_list(&mut gc);
+    let len = list_length(&gc, &head);
+    assert!(len < LENGTH);
+    assert!(len * 32 > 4096);
+}
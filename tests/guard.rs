@@ -0,0 +1,39 @@
+#![cfg(all(target_os = "linux", feature = "debug-guard"))]
+
+use std::os::unix::process::ExitStatusExt;
+use std::process::Command;
+
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+const CHILD_ENV: &str = "IKE_GC_GUARD_CHILD";
+
+const SIGSEGV: i32 = 11;
+
+#[test]
+fn overrunning_the_from_space_faults() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        let mut gc = GCAlloc::new(65536);
+        // The first object's header starts the from-space.
+        let cell = Cons::alloc(&mut gc, None, None).unwrap();
+        let end = cell.get().cast::<u8>().wrapping_sub(16).wrapping_add(65536);
+        unsafe { core::ptr::write_volatile(end as *mut u8, 0xff) };
+        unreachable!("Overrun into the to-space was not caught");
+    }
+
+    // The fault kills the process, so observe it from a child process.
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "overrunning_the_from_space_faults",
+            "--nocapture",
+        ])
+        .env(CHILD_ENV, "1")
+        .output()
+        .expect("Failed to spawn child test");
+    assert_eq!(
+        output.status.signal(),
+        Some(SIGSEGV),
+        "unexpected child output: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
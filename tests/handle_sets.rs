@@ -0,0 +1,43 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn release_handle_set_keeps_other_sets() {
+    let mut gc = GCAlloc::new(4096);
+    let first = gc.new_handle_set();
+    let second = gc.new_handle_set();
+
+    let mut kept = vec![];
+    for _ in 0..3 {
+        let dropped = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        gc.acquire_handle_in(first, dropped);
+        let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        kept.push(gc.acquire_handle_in(second, cons));
+    }
+    // Releasing a handle on its own leaves the rest of its set alone.
+    let released = kept.pop().unwrap();
+    gc.release_handle(released);
+
+    gc.release_handle_set(first);
+    gc.collect();
+
+    let cons_size = 16 + std::mem::size_of::<Cons>().next_multiple_of(16);
+    assert_eq!(gc.metadata().currently_allocated, kept.len() * cons_size);
+    for handle in &kept {
+        let cons = gc.read(handle);
+        assert!(cons.car.is_none() && cons.cdr.is_none());
+    }
+
+    gc.release_handle_set(second);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+#[should_panic(expected = "Handle set is released")]
+fn acquire_in_released_set_panics() {
+    let mut gc = GCAlloc::new(4096);
+    let set = gc.new_handle_set();
+    gc.release_handle_set(set);
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    gc.acquire_handle_in(set, cons);
+}
@@ -0,0 +1,151 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc, Handle};
+use slotmap::Key;
+
+#[test]
+fn handles_are_enumerated_and_compacted() {
+    let mut gc = GCAlloc::new(65536);
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+
+    let handles: Vec<_> = (0..10_000)
+        .map(|_| gc.acquire_handle(cell.clone()))
+        .collect();
+    assert_eq!(gc.handle_count(), 10_000);
+    let kept = gc.acquire_handle(cell.clone());
+
+    let mut seen = 0;
+    let mut found_kept = false;
+    gc.for_each_handle(|key, ptr| {
+        seen += 1;
+        found_kept |= key == kept.key();
+        assert_eq!(ptr, cell.get() as *const u8);
+    });
+    assert_eq!(seen, 10_001);
+    assert!(found_kept);
+
+    for handle in handles {
+        gc.release_handle(handle);
+    }
+    // A live handle keeps the table from being compacted.
+    gc.compact_handles();
+    assert!(gc.handle_capacity() >= 10_000);
+
+    gc.release_handle(kept);
+    assert_eq!(gc.handle_count(), 0);
+    gc.compact_handles();
+    assert!(gc.handle_capacity() < 10_000);
+
+    // The compacted table works as before.
+    let handle = gc.acquire_handle(cell);
+    gc.collect();
+    assert!(gc.get(gc.get_handle(&handle)).car.is_none());
+}
+
+#[test]
+#[should_panic(expected = "not an object of this heap")]
+fn handle_to_another_heap_panics() {
+    let mut gc = GCAlloc::new(4096);
+    let mut other = GCAlloc::new(4096);
+    let cell = Cons::alloc(&mut other, None, None).unwrap();
+    gc.acquire_handle(cell);
+}
+
+#[test]
+#[should_panic(expected = "not an object of this heap")]
+fn handle_past_the_from_space_panics() {
+    let mut gc = GCAlloc::new(4096);
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+    // The second half of the mapping is the to-space.
+    gc.acquire_handle(Gc::new(cell.get().wrapping_byte_add(4096)));
+}
+
+#[test]
+fn stale_handle_does_not_alias_recycled_slot() {
+    let mut gc = GCAlloc::new(4096);
+    let set = gc.new_handle_set();
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let stale = gc.acquire_handle_in(set, cons);
+    gc.release_handle_set(set);
+
+    // The new handles take the slot of the released one.
+    let mut handles = vec![];
+    for _ in 0..4 {
+        let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        handles.push(gc.acquire_handle(cons));
+    }
+    assert!(handles
+        .iter()
+        .any(|handle| handle.key().data().as_ffi() as u32 == stale.key().data().as_ffi() as u32));
+
+    assert!(gc.try_get_handle(&stale).is_none());
+    for handle in &handles {
+        assert!(gc.try_get_handle(handle).is_some());
+    }
+    gc.release_handle(stale);
+    assert_eq!(gc.handle_count(), handles.len());
+}
+
+#[test]
+#[should_panic(expected = "Handle is released")]
+fn get_released_handle_panics() {
+    let mut gc = GCAlloc::new(4096);
+    let set = gc.new_handle_set();
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let stale = gc.acquire_handle_in(set, cons);
+    gc.release_handle_set(set);
+    gc.get_handle(&stale);
+}
+
+#[test]
+fn presized_handle_table_does_not_grow() {
+    let mut gc = GCAlloc::with_handle_capacity(4096, 1000);
+    let capacity = gc.handle_capacity();
+    assert!(capacity >= 1000);
+
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+    let handles: Vec<_> = (0..1000).map(|_| gc.acquire_handle(cell.clone())).collect();
+    assert_eq!(gc.handle_capacity(), capacity);
+    for handle in handles {
+        gc.release_handle(handle);
+    }
+}
+
+#[test]
+fn handles_are_acquired_and_released_in_bulk() {
+    let mut gc = GCAlloc::new(65536);
+    let ptrs: Vec<_> = (0..100)
+        .map(|_| Cons::alloc(&mut gc, None, None).expect("Malloc failed"))
+        .collect();
+    let handles = gc.acquire_handles(&ptrs);
+    assert_eq!(gc.handle_count(), 100);
+    assert!(gc.handle_capacity() >= 100);
+
+    gc.collect();
+    let cons_size = 16 + std::mem::size_of::<Cons>().next_multiple_of(16);
+    assert_eq!(gc.metadata().currently_allocated, 100 * cons_size);
+    for (handle, ptr) in handles.iter().zip(&ptrs) {
+        assert!(!gc.get_handle(handle).ptr_eq(ptr));
+    }
+
+    gc.release_handles(handles);
+    assert_eq!(gc.handle_count(), 0);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+fn raw_handle_round_trips() {
+    let mut gc = GCAlloc::new(4096);
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let handle = gc.acquire_handle(cons);
+    let raw = handle.as_raw();
+    assert_eq!(raw, handle.key().data().as_ffi());
+
+    gc.collect();
+    let restored = unsafe { Handle::<Cons>::from_raw(raw) };
+    assert_eq!(restored.key(), handle.key());
+    assert!(gc.get_handle(&restored).ptr_eq(&gc.get_handle(&handle)));
+
+    gc.release_handle(restored);
+    let stale = unsafe { Handle::<Cons>::from_raw(raw) };
+    assert!(gc.try_get_handle(&stale).is_none());
+}
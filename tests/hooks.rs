@@ -0,0 +1,49 @@
+use std::sync::{Arc, Mutex};
+
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn hooks_fire_once_per_collect() {
+    let mut gc = GCAlloc::new(4096);
+    // (before, after) calls, and what each saw.
+    let before = Arc::new(Mutex::new(vec![]));
+    let after = Arc::new(Mutex::new(vec![]));
+    {
+        let before = before.clone();
+        gc.on_before_collect(Box::new(move |meta| {
+            before
+                .lock()
+                .unwrap()
+                .push((meta.gc_count, meta.currently_allocated))
+        }));
+        let after = after.clone();
+        gc.on_after_collect(Box::new(move |meta| {
+            after.lock().unwrap().push((
+                meta.gc_count,
+                meta.currently_allocated,
+                meta.last_reclaimed,
+            ))
+        }));
+    }
+
+    let live = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let live = gc.acquire_handle(live);
+    for _ in 0..10 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    let allocated = gc.metadata().currently_allocated;
+    let cell = allocated / 11;
+
+    gc.collect();
+    assert_eq!(*before.lock().unwrap(), [(0, allocated)]);
+    assert_eq!(*after.lock().unwrap(), [(1, cell, allocated - cell)]);
+
+    gc.collect();
+    assert_eq!(before.lock().unwrap().len(), 2);
+    assert_eq!(after.lock().unwrap()[1], (2, cell, 0));
+
+    gc.release_handle(live);
+    gc.collect();
+    assert_eq!(after.lock().unwrap()[2], (3, 0, cell));
+    assert_eq!(gc.metadata().last_reclaimed, cell);
+}
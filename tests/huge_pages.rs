@@ -0,0 +1,29 @@
+#![cfg(target_os = "linux")]
+
+use ike_gc::{fixtures::Cons, GCAlloc, GCInitError};
+
+#[test]
+fn huge_page_heap_works_with_or_without_huge_pages() {
+    // Rounded up to a 2 MiB half, which holds far more than the requested size.
+    let mut gc = GCAlloc::with_huge_pages(4096);
+    let mut list = None;
+    for _ in 0..10_000 {
+        list = Some(Cons::alloc(&mut gc, None, list).unwrap());
+    }
+    assert_eq!(gc.metadata().gc_count, 0);
+
+    let list = gc.acquire_handle(list.unwrap());
+    gc.collect();
+    gc.verify();
+    assert!(gc.metadata().currently_allocated >= 10_000 * 32);
+    gc.release_handle(list);
+}
+
+#[test]
+fn oversized_huge_page_heap_is_an_error() {
+    let result = GCAlloc::builder()
+        .size(usize::MAX - 4096)
+        .huge_pages(true)
+        .build();
+    assert!(matches!(result, Err(GCInitError::SizeOverflow)));
+}
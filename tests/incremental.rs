@@ -0,0 +1,75 @@
+use ike_gc::{
+    fixtures::{Cons, GcTree},
+    GCAlloc,
+};
+
+/// A heap with a tree of 2^10 - 1 nodes, with garbage trees allocated around it.
+fn heap_with_tree() -> GCAlloc {
+    let mut gc = GCAlloc::new(1 << 20);
+    for i in 0..8 {
+        GcTree::alloc_complete(&mut gc, 6, 0);
+        if i == 4 {
+            let tree = GcTree::alloc_complete(&mut gc, 10, 0).unwrap();
+            gc.acquire_handle(tree);
+        }
+    }
+    gc
+}
+
+fn live_objects(gc: &GCAlloc) -> usize {
+    let mut objects = 0;
+    gc.for_each_object(|_, _| objects += 1);
+    objects
+}
+
+#[test]
+fn stepped_marking_keeps_the_same_objects() {
+    let mut single = heap_with_tree();
+    single.collect();
+
+    let mut stepped = heap_with_tree();
+    let mut steps = 1;
+    while !stepped.mark_step(16) {
+        steps += 1;
+    }
+    assert!(steps > 50, "Marking finished in {} steps", steps);
+    assert!(stepped.mark_step(16));
+    stepped.collect();
+
+    assert_eq!(live_objects(&stepped), (1 << 10) - 1);
+    assert_eq!(live_objects(&stepped), live_objects(&single));
+    assert_eq!(
+        stepped.metadata().last_survivors,
+        single.metadata().last_survivors
+    );
+    stepped.verify();
+}
+
+#[test]
+fn barrier_keeps_objects_stored_into_marked_ones() {
+    let mut gc = GCAlloc::new(65536);
+    let root = Cons::alloc(&mut gc, None, None).unwrap();
+    let root = gc.acquire_handle(root);
+    // Not rooted, but a mark step doesn't move it.
+    let stored = Cons::alloc(&mut gc, None, None).unwrap();
+    let held = Cons::alloc(&mut gc, None, None).unwrap();
+    Cons::alloc(&mut gc, None, None).unwrap();
+
+    while !gc.mark_step(1) {}
+    // The root is black, and `stored` white until the barrier sees it.
+    let container = gc.get_handle(&root);
+    gc.get_mut(container.clone()).car = Some(stored.clone());
+    gc.write_barrier(container, stored);
+    // Objects allocated while marking start out gray, so what they point to is marked.
+    let fresh = Cons::alloc(&mut gc, Some(held), None).unwrap();
+    let container = gc.get_handle(&root);
+    gc.get_mut(container.clone()).cdr = Some(fresh.clone());
+    gc.write_barrier(container, fresh);
+    gc.verify();
+
+    gc.collect();
+    assert_eq!(live_objects(&gc), 4);
+    let root = gc.get(gc.get_handle(&root));
+    let fresh = gc.get(root.cdr.clone().unwrap());
+    assert!(root.car.is_some() && fresh.car.is_some());
+}
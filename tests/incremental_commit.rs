@@ -0,0 +1,65 @@
+#![cfg(target_os = "linux")]
+
+use ike_gc::{GCAlloc, GCConfig, SizeKind, VTable};
+
+static SMALL_VTABLE: VTable = VTable {
+    size: SizeKind::fixed(64),
+    align: 16,
+    ..VTable::DEFAULT
+};
+
+static PAGE_VTABLE: VTable = VTable {
+    size: SizeKind::fixed(4096 - 16),
+    align: 16,
+    ..VTable::DEFAULT
+};
+
+/// Resident set size of this process, in bytes.
+fn rss() -> usize {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    let line = status
+        .lines()
+        .find(|l| l.starts_with("VmRSS:"))
+        .expect("VmRSS not found");
+    let kb: usize = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .expect("Malformed VmRSS");
+    kb * 1024
+}
+
+const MB: usize = 1024 * 1024;
+
+#[test]
+fn rss_follows_usage() {
+    let baseline = rss();
+    let mut gc = GCAlloc::with_config(
+        256 * MB,
+        GCConfig {
+            incremental_commit: true,
+            ..Default::default()
+        },
+    );
+
+    let root = gc.allocate(&SMALL_VTABLE, 64).expect("Malloc failed");
+    let handle = gc.acquire_handle(root);
+    assert!(rss() < baseline + 16 * MB);
+    assert!(gc.metadata().committed_pages * 4096 <= MB);
+
+    // Fill 64 MiB with garbage.
+    for _ in 0..(64 * MB / 4096) {
+        gc.allocate(&PAGE_VTABLE, 4096 - 16).expect("Malloc failed");
+    }
+    assert_eq!(gc.metadata().gc_count, 0);
+    assert!(rss() >= baseline + 48 * MB);
+
+    // The first collection leaves the garbage in the to-space; the second one copies back into
+    // it and releases everything past the live data.
+    gc.collect();
+    gc.collect();
+    assert!(rss() < baseline + 16 * MB);
+    assert!(gc.metadata().committed_pages * 4096 <= 2 * MB);
+
+    gc.release_handle(handle);
+}
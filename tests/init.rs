@@ -0,0 +1,19 @@
+use ike_gc::{GCAlloc, GCInitError};
+
+#[test]
+fn try_new_reports_mapping_failure() {
+    let result = GCAlloc::try_new(1 << 60);
+    assert!(matches!(result, Err(GCInitError::Map(_))));
+}
+
+#[test]
+fn try_new_reports_size_overflow() {
+    let result = GCAlloc::try_new(usize::MAX / 2 + 1);
+    assert!(matches!(result, Err(GCInitError::SizeOverflow)));
+}
+
+#[test]
+fn try_new_succeeds() {
+    let gc = GCAlloc::try_new(4096).expect("Mapping failed");
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
@@ -0,0 +1,79 @@
+use ike_gc::{gc_ptr::Gc, GCAlloc, Trace};
+
+#[derive(Trace)]
+struct Leaf {
+    value: usize,
+}
+
+/// Bigger than a whole semispace of the heaps below.
+#[derive(Trace)]
+struct Big {
+    leaf: Gc<Leaf>,
+    data: [u8; 8192],
+}
+
+#[test]
+fn object_larger_than_semispace() {
+    let mut gc = GCAlloc::new(4096);
+
+    gc.alloc(Leaf { value: 0 }).expect("Malloc failed");
+    let leaf = gc.alloc(Leaf { value: 42 }).expect("Malloc failed");
+    let leaf_addr = leaf.get();
+    let leaf_handle = gc.acquire_handle(leaf);
+    let mut data = [0; 8192];
+    data[8191] = 7;
+    let leaf = gc.get_handle(&leaf_handle);
+    let big = gc.alloc(Big { leaf, data }).expect("Malloc failed");
+    gc.release_handle(leaf_handle);
+    let big_addr = big.get();
+    let handle = gc.acquire_handle(big);
+    assert!(gc.metadata().large_allocated > 8192);
+    gc.verify();
+
+    gc.collect();
+    gc.verify();
+    let big = gc.get_handle(&handle);
+    // Large objects don't move, but what they point to does.
+    assert_eq!(big.get(), big_addr);
+    let big = unsafe { &*big.get() };
+    assert_ne!(big.leaf.get(), leaf_addr);
+    assert_eq!(unsafe { (*big.leaf.get()).value }, 42);
+    assert_eq!(big.data[8191], 7);
+
+    gc.release_handle(handle);
+    gc.collect();
+    assert_eq!(gc.metadata().large_allocated, 0);
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+fn large_garbage_is_collected() {
+    let mut gc = GCAlloc::new(4096);
+    let leaf = gc.alloc(Leaf { value: 1 }).expect("Malloc failed");
+    let leaf = gc.acquire_handle(leaf);
+    for _ in 0..100 {
+        let leaf = gc.get_handle(&leaf);
+        gc.alloc(Big {
+            leaf,
+            data: [0; 8192],
+        })
+        .expect("Malloc failed");
+    }
+    // The large objects trigger collections on their own.
+    assert!(gc.metadata().gc_count > 0);
+    assert!(gc.metadata().large_allocated <= 2 * (8192 + 64));
+    let weak = {
+        let leaf = gc.get_handle(&leaf);
+        let big = gc
+            .alloc(Big {
+                leaf,
+                data: [0; 8192],
+            })
+            .expect("Malloc failed");
+        gc.acquire_weak(big)
+    };
+    assert!(gc.upgrade(&weak).is_some());
+    gc.collect();
+    assert!(gc.upgrade(&weak).is_none());
+    assert_eq!(gc.metadata().large_allocated, 0);
+}
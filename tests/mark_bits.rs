@@ -0,0 +1,88 @@
+use ike_gc::{
+    fixtures::{Cons, GcTree},
+    gc_ptr::Gc,
+    GCAlloc, GCConfig, Trace,
+};
+
+#[derive(Trace)]
+struct Large {
+    child: Gc<Cons>,
+    _data: [u8; 512],
+}
+
+/// Check that `tree` is a complete tree of the given depth numbered in pre-order from `first`.
+fn check_tree(gc: &GCAlloc, tree: Option<Gc<GcTree>>, depth: usize, first: usize) {
+    if depth == 0 {
+        assert!(tree.is_none());
+        return;
+    }
+    let node = gc.get(tree.expect("Missing node"));
+    assert_eq!(node.value, first);
+    check_tree(gc, node.left.clone(), depth - 1, first + 1);
+    check_tree(
+        gc,
+        node.right.clone(),
+        depth - 1,
+        first + (1 << (depth - 1)),
+    );
+}
+
+fn side_mark_bits() -> GCConfig {
+    GCConfig {
+        side_mark_bits: true,
+        large_object_threshold: Some(256),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn collections_with_side_mark_bits() {
+    let mut gc = GCAlloc::with_config(65536, side_mark_bits());
+
+    GcTree::alloc_complete(&mut gc, 4, 0);
+    let tree = GcTree::alloc_complete(&mut gc, 6, 0).unwrap();
+    GcTree::alloc_complete(&mut gc, 4, 0);
+    let tree = gc.acquire_handle(tree);
+    let pinned = Cons::alloc(&mut gc, None, None).unwrap();
+    let pinned = gc.pin(pinned);
+    let child = Cons::alloc(&mut gc, None, None).unwrap();
+    let large = gc
+        .alloc(Large {
+            child,
+            _data: [0; 512],
+        })
+        .unwrap();
+    let large = gc.acquire_handle(large);
+
+    for _ in 0..3 {
+        gc.collect();
+        check_tree(&gc, Some(gc.get_handle(&tree)), 6, 0);
+        gc.verify();
+    }
+    gc.unpin(pinned);
+    gc.minor_collect();
+    check_tree(&gc, Some(gc.get_handle(&tree)), 6, 0);
+
+    let large_obj = gc.get_handle(&large);
+    assert!(gc.get(gc.get(large_obj).child.clone()).car.is_none());
+
+    gc.release_handle(tree);
+    gc.release_handle(large);
+    gc.collect();
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+    assert_eq!(gc.metadata().large_allocated, 0);
+}
+
+#[test]
+fn side_mark_bits_with_protected_headers() {
+    let config = GCConfig {
+        protect_during_gc: true,
+        ..side_mark_bits()
+    };
+    let mut gc = GCAlloc::with_config(65536, config);
+    let tree = GcTree::alloc_complete(&mut gc, 6, 0).unwrap();
+    let tree = gc.acquire_handle(tree);
+    gc.collect();
+    check_tree(&gc, Some(gc.get_handle(&tree)), 6, 0);
+}
@@ -0,0 +1,177 @@
+use ike_gc::{
+    fixtures::{Cons, GcTree},
+    gc_ptr::Gc,
+    GCAlloc, GCConfig, Trace,
+};
+
+/// Check that `tree` is a complete tree of the given depth numbered in pre-order from `first`.
+fn check_tree(gc: &GCAlloc, tree: Option<Gc<GcTree>>, depth: usize, first: usize) {
+    if depth == 0 {
+        assert!(tree.is_none());
+        return;
+    }
+    let node = gc.get(tree.expect("Missing node"));
+    assert_eq!(node.value, first);
+    check_tree(gc, node.left.clone(), depth - 1, first + 1);
+    check_tree(
+        gc,
+        node.right.clone(),
+        depth - 1,
+        first + (1 << (depth - 1)),
+    );
+}
+
+#[test]
+fn minor_collections_leave_old_objects_in_place() {
+    let config = GCConfig {
+        nursery_size: Some(4096),
+        ..Default::default()
+    };
+    let mut gc = GCAlloc::with_config(1 << 16, config);
+
+    // Interleave garbage so the tree has to move when it's promoted.
+    GcTree::alloc_complete(&mut gc, 3, 0);
+    let tree = GcTree::alloc_complete(&mut gc, 5, 0).unwrap();
+    let tree = gc.acquire_handle(tree);
+    gc.minor_collect();
+    let old = gc.get_handle(&tree);
+    check_tree(&gc, Some(old.clone()), 5, 0);
+
+    // Churn through many nurseries' worth of garbage, while a young list grows.
+    let head = Cons::alloc(&mut gc, None, None).unwrap();
+    let mut list = gc.acquire_handle(head);
+    for _ in 0..200 {
+        for _ in 0..10 {
+            Cons::alloc(&mut gc, None, None).unwrap();
+        }
+        let head = gc.get_handle(&list);
+        let cell = Cons::alloc(&mut gc, None, Some(head)).unwrap();
+        let next = gc.acquire_handle(cell);
+        gc.release_handle(std::mem::replace(&mut list, next));
+    }
+
+    let meta = gc.metadata();
+    assert!(meta.minor_gc_count > 1);
+    assert_eq!(meta.gc_count, meta.minor_gc_count);
+    assert!(gc.same_object(gc.get_handle(&tree), old));
+    check_tree(&gc, Some(gc.get_handle(&tree)), 5, 0);
+    let mut len = 0;
+    let mut cell = Some(gc.get_handle(&list));
+    while let Some(c) = cell {
+        len += 1;
+        cell = gc.get(c).cdr.clone();
+    }
+    assert_eq!(len, 201);
+    gc.verify();
+}
+
+#[derive(Trace)]
+struct Holder {
+    child: Gc<Leaf>,
+}
+
+#[derive(Trace)]
+struct Leaf {
+    value: usize,
+}
+
+#[test]
+fn old_objects_keep_young_children_alive() {
+    let mut gc = GCAlloc::new(1 << 16);
+    let leaf = gc.alloc(Leaf { value: 1 }).unwrap();
+    let holder = gc.alloc(Holder { child: leaf }).unwrap();
+    let holder = gc.acquire_handle(holder);
+    gc.minor_collect();
+
+    // The holder is old now, and its only reference to the new leaf goes through the barrier.
+    let leaf = gc.alloc(Leaf { value: 2 }).unwrap();
+    gc.alloc(Leaf { value: 3 }).unwrap();
+    let container = gc.get_handle(&holder);
+    let slot = unsafe { &(*container.get()).child };
+    slot.store(&mut gc, container.clone(), leaf.clone());
+    gc.minor_collect();
+
+    assert_eq!(gc.get_handle(&holder).get(), container.get());
+    assert_eq!(gc.get(gc.get(container).child.clone()).value, 2);
+    assert_eq!(gc.metadata().gc_count, 2);
+    gc.verify();
+}
+
+fn nursery_heap() -> GCAlloc {
+    let config = GCConfig {
+        nursery_size: Some(4096),
+        ..Default::default()
+    };
+    GCAlloc::with_config(1 << 16, config)
+}
+
+#[test]
+fn objects_are_tenured_after_surviving_threshold() {
+    let mut gc = nursery_heap();
+    gc.set_tenure_threshold(3);
+
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let cons = Cons::alloc(&mut gc, Some(leaf), None).expect("Malloc failed");
+    let cons = gc.acquire_handle(cons);
+
+    for _ in 0..2 {
+        gc.minor_collect();
+        assert!(!gc.in_old_gen(gc.get_handle(&cons)));
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    gc.minor_collect();
+    let old = gc.get_handle(&cons);
+    assert!(gc.in_old_gen(old.clone()));
+    assert!(gc.in_old_gen(gc.get(old.clone()).car.clone().unwrap()));
+
+    // Old objects stay where they are from now on.
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    gc.minor_collect();
+    assert!(gc.get_handle(&cons).ptr_eq(&old));
+    gc.release_handle(cons);
+}
+
+#[test]
+fn promoted_objects_keep_young_survivors_alive() {
+    let mut gc = nursery_heap();
+    gc.set_tenure_threshold(2);
+
+    let parent = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let parent = gc.acquire_handle(parent);
+    gc.minor_collect();
+
+    // A child younger than its parent, which the parent only points to from the nursery.
+    let child = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    gc.get_mut(child.clone()).car = Some(leaf);
+    let container = gc.get_handle(&parent);
+    gc.write_barrier(container.clone(), child.clone());
+    gc.get_mut(container).cdr = Some(child);
+
+    // The parent is promoted, and the child stays young.
+    gc.minor_collect();
+    let parent_ptr = gc.get_handle(&parent);
+    assert!(gc.in_old_gen(parent_ptr.clone()));
+    let child = gc.get(parent_ptr.clone()).cdr.clone().unwrap();
+    assert!(!gc.in_old_gen(child.clone()));
+
+    // The child is only reachable through the old parent now.
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    gc.minor_collect();
+    let child = gc.get(gc.get_handle(&parent)).cdr.clone().unwrap();
+    assert!(gc.in_old_gen(child.clone()));
+    let leaf = gc.get(child).car.clone().unwrap();
+    assert!(gc.get(leaf).car.is_none());
+    gc.verify();
+
+    gc.release_handle(parent);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+#[should_panic(expected = "Tenure threshold 4 is not in 1..=3")]
+fn tenure_threshold_is_bounded() {
+    nursery_heap().set_tenure_threshold(4);
+}
@@ -0,0 +1,37 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn move_observer_reports_forwarding() {
+    let mut gc = GCAlloc::new(4096);
+    let mut handles = vec![];
+    for _ in 0..4 {
+        // Garbage between the live objects, so they all move.
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        handles.push(gc.acquire_handle(cons));
+    }
+
+    let moves = Arc::new(Mutex::new(HashMap::new()));
+    let recorded = moves.clone();
+    gc.set_move_observer(move |from, to| {
+        recorded.lock().unwrap().insert(from as usize, to as usize);
+    });
+
+    let before: Vec<_> = handles
+        .iter()
+        .map(|handle| gc.get_handle(handle).get() as usize)
+        .collect();
+    gc.collect();
+
+    let moves = moves.lock().unwrap();
+    assert_eq!(moves.len(), handles.len());
+    for (handle, old) in handles.iter().zip(before) {
+        assert_ne!(moves[&old], old);
+        assert_eq!(moves[&old], gc.get_handle(handle).get() as usize);
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex};
+
+use ike_gc::{
+    fixtures::Cons, gc_ptr::Gc, AbandonReason, GCAlloc, GCConfig, GcEvent, GcObserver, SizeKind,
+    VTable,
+};
+
+/// Records the events it sees, apart from copies.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<GcEvent>>>);
+
+impl GcObserver for Recorder {
+    fn event(&mut self, event: GcEvent) {
+        if !matches!(event, GcEvent::ObjectCopied { .. }) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+}
+
+#[test]
+fn observer_sees_collections() {
+    let mut gc = GCAlloc::with_config(
+        4096,
+        GCConfig {
+            nursery_size: Some(1024),
+            ..Default::default()
+        },
+    );
+    let cell_size = 16 + core::mem::size_of::<Cons>().next_multiple_of(16);
+    let recorder = Recorder::default();
+    gc.set_observer(recorder.clone());
+
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+    let cell = gc.acquire_handle(cell);
+    Cons::alloc(&mut gc, None, None).unwrap();
+    gc.collect();
+    gc.minor_collect();
+    let mut events = recorder.0.lock().unwrap().split_off(0);
+    for event in &mut events {
+        if let GcEvent::CollectFinished { time, .. } = event {
+            *time = Default::default();
+        }
+    }
+    assert_eq!(
+        events,
+        [
+            GcEvent::CollectStarted { minor: false },
+            GcEvent::CollectFinished {
+                minor: false,
+                reclaimed: cell_size,
+                survivors: cell_size,
+                time: Default::default(),
+            },
+            GcEvent::CollectStarted { minor: true },
+            GcEvent::CollectFinished {
+                minor: true,
+                reclaimed: 0,
+                survivors: cell_size,
+                time: Default::default(),
+            },
+        ]
+    );
+
+    // Fill the heap with live cells until it runs out.
+    let mut last = gc.get_handle(&cell);
+    while let Ok(next) = Cons::alloc(&mut gc, Some(last), None) {
+        last = next;
+        gc.push_root(last.clone());
+    }
+    assert!(recorder
+        .0
+        .lock()
+        .unwrap()
+        .contains(&GcEvent::OutOfMemory { size: cell_size }));
+}
+
+/// An object whose mark callback always fails.
+struct Broken {
+    _payload: u64,
+}
+
+fn broken_mark(gc: &mut GCAlloc, ptr: *const u8) {
+    gc.report_trace_error(ptr, "broken");
+}
+
+static BROKEN_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Broken>(),
+    align: std::mem::align_of::<Broken>(),
+    mark_cb: broken_mark,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn observer_sees_abandoned_collection() {
+    let mut gc = GCAlloc::new(4096);
+    let recorder = Recorder::default();
+    gc.set_observer(recorder.clone());
+
+    let broken: Gc<Broken> = gc
+        .allocate_typed(&BROKEN_VTABLE, Broken { _payload: 0 })
+        .unwrap();
+    let broken = gc.acquire_handle(broken);
+    gc.try_collect().unwrap_err();
+    assert_eq!(
+        recorder.0.lock().unwrap().split_off(0),
+        [
+            GcEvent::CollectStarted { minor: false },
+            GcEvent::TraceFailed {
+                object: gc.get_handle(&broken).get() as *const u8,
+                reason: "broken",
+            },
+            GcEvent::CollectAbandoned {
+                reason: AbandonReason::TraceError,
+            },
+        ]
+    );
+    gc.release_handle(broken);
+}
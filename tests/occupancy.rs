@@ -0,0 +1,33 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn occupancy_matches_live_set() {
+    let mut gc = GCAlloc::new(64 * 1024);
+    let cons_size = 16 + std::mem::size_of::<Cons>().next_multiple_of(16);
+    let mut handles = vec![];
+    for i in 0..400 {
+        let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        // Keep one in four.
+        if i % 4 == 0 {
+            handles.push(gc.acquire_handle(cons));
+        }
+    }
+    gc.collect();
+
+    let meta = gc.metadata();
+    assert_eq!(meta.live_bytes, handles.len() * cons_size);
+    let expected = (handles.len() * cons_size) as f64 / meta.semispace_size as f64;
+    assert!(
+        (meta.occupancy - expected).abs() < 1e-9,
+        "occupancy {} instead of {}",
+        meta.occupancy,
+        expected
+    );
+
+    for handle in handles {
+        gc.release_handle(handle);
+    }
+    gc.collect();
+    assert_eq!(gc.metadata().live_bytes, 0);
+    assert_eq!(gc.metadata().occupancy, 0.0);
+}
@@ -0,0 +1,71 @@
+use std::sync::{Arc, Mutex};
+
+use ike_gc::{fixtures::GcTree, AllocError, GCAlloc, Handle};
+
+/// Fill the heap with leaves held by handles, returning the raw handles.
+fn fill(gc: &mut GCAlloc) -> Vec<u64> {
+    let mut handles = vec![];
+    while let Ok(leaf) = GcTree::alloc(gc, 0, None, None) {
+        handles.push(gc.acquire_handle(leaf).as_raw());
+    }
+    handles
+}
+
+#[test]
+fn handler_frees_memory_and_retries() {
+    let mut gc = GCAlloc::new(4096);
+    let leaf = GcTree::alloc(&mut gc, 7, None, None).unwrap();
+    let leaf = gc.acquire_handle(leaf);
+    let mut handles = fill(&mut gc);
+    handles.push(leaf.as_raw());
+    let handles = Arc::new(Mutex::new(handles));
+
+    let calls = Arc::new(Mutex::new(vec![]));
+    gc.set_oom_handler({
+        let handles = handles.clone();
+        let calls = calls.clone();
+        move |gc, size| {
+            calls.lock().unwrap().push(size);
+            for raw in handles.lock().unwrap().drain(..) {
+                gc.release_handle(unsafe { Handle::<GcTree>::from_raw(raw) });
+            }
+            gc.collect();
+            true
+        }
+    });
+
+    // Once the handler released its handle, the leaf is only held by the value being
+    // allocated.
+    let left = gc.get_handle(&leaf);
+    let node = GcTree::alloc(&mut gc, 1, Some(left), None).unwrap();
+    assert_eq!(calls.lock().unwrap().len(), 1);
+    assert!(calls.lock().unwrap()[0] >= std::mem::size_of::<GcTree>());
+
+    let node = gc.acquire_handle(node);
+    gc.collect();
+    gc.verify();
+    let node = gc.get(gc.get_handle(&node));
+    assert_eq!(node.value, 1);
+    assert_eq!(gc.get(node.left.clone().unwrap()).value, 7);
+}
+
+#[test]
+fn declined_retry_fails() {
+    let mut gc = GCAlloc::new(4096);
+    let handles = fill(&mut gc);
+    let calls = Arc::new(Mutex::new(0));
+    gc.set_oom_handler({
+        let calls = calls.clone();
+        move |_, _| {
+            *calls.lock().unwrap() += 1;
+            false
+        }
+    });
+
+    assert!(matches!(
+        GcTree::alloc(&mut gc, 0, None, None),
+        Err(AllocError::OutOfMemory)
+    ));
+    assert_eq!(*calls.lock().unwrap(), 1);
+    assert_eq!(handles.len(), gc.handle_count());
+}
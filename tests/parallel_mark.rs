@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc, Handle};
+
+const CELLS: usize = 50_000;
+
+/// A deterministic random graph of cons cells, each pointing to earlier ones, with a few of
+/// them rooted. Returns the roots and the number of cells reachable from them.
+fn build_graph(gc: &mut GCAlloc) -> (Vec<Handle<Cons>>, usize) {
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move |bound: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as usize % bound
+    };
+    let mut cells: Vec<Gc<Cons>> = Vec::with_capacity(CELLS);
+    let mut edges = Vec::with_capacity(CELLS);
+    for i in 0..CELLS {
+        let mut pick = || (i > 0 && next(4) != 0).then(|| next(i));
+        let (car, cdr) = (pick(), pick());
+        let cell = Cons::alloc(
+            gc,
+            car.map(|j| cells[j].clone()),
+            cdr.map(|j| cells[j].clone()),
+        )
+        .unwrap();
+        cells.push(cell);
+        edges.push((car, cdr));
+    }
+    let roots: Vec<usize> = (0..64).map(|_| CELLS / 2 + next(CELLS / 2)).collect();
+
+    let mut reachable = HashSet::new();
+    let mut stack = roots.clone();
+    while let Some(i) = stack.pop() {
+        if reachable.insert(i) {
+            let (car, cdr) = edges[i];
+            stack.extend(car.into_iter().chain(cdr));
+        }
+    }
+    let handles = roots
+        .into_iter()
+        .map(|i| gc.acquire_handle(cells[i].clone()))
+        .collect();
+    (handles, reachable.len())
+}
+
+/// The number of distinct cells reachable from `roots`.
+fn count_reachable(gc: &GCAlloc, roots: &[Handle<Cons>]) -> usize {
+    let mut seen = HashSet::new();
+    let mut stack: Vec<_> = roots.iter().map(|root| gc.get_handle(root)).collect();
+    while let Some(cell) = stack.pop() {
+        if seen.insert(cell.get()) {
+            let cons = gc.get(cell);
+            stack.extend(cons.car.clone().into_iter().chain(cons.cdr.clone()));
+        }
+    }
+    seen.len()
+}
+
+fn survivors_after_collect(threads: usize) -> (usize, usize) {
+    let mut gc = GCAlloc::new(4 << 20);
+    unsafe { gc.set_mark_threads(threads) };
+    let (roots, reachable) = build_graph(&mut gc);
+    gc.collect();
+    let mut objects = 0;
+    gc.for_each_object(|_, _| objects += 1);
+    assert_eq!(objects, reachable);
+    assert_eq!(count_reachable(&gc, &roots), reachable);
+    gc.verify();
+    (objects, gc.metadata().last_survivors)
+}
+
+#[test]
+fn parallel_marking_keeps_the_same_objects() {
+    let serial = survivors_after_collect(1);
+    for threads in [2, 4, 8] {
+        assert_eq!(survivors_after_collect(threads), serial);
+    }
+}
@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+
+use ike_gc::{GCAlloc, GCConfig, GCPhase, SizeKind, VTable};
+
+thread_local! {
+    /// The phases seen by the callbacks, tagged with the callback.
+    static SEEN: RefCell<Vec<(&'static str, GCPhase)>> = const { RefCell::new(vec![]) };
+}
+
+fn record(callback: &'static str, gc: &GCAlloc) {
+    SEEN.with(|seen| seen.borrow_mut().push((callback, gc.phase())));
+}
+
+fn take_seen() -> Vec<(&'static str, GCPhase)> {
+    SEEN.with(|seen| seen.take())
+}
+
+fn cell_mark(gc: &mut GCAlloc, _ptr: *const u8) {
+    record("mark", gc);
+}
+
+fn cell_rewrite(gc: &mut GCAlloc, _ptr: *const u8) {
+    record("rewrite", gc);
+}
+
+static CELL_VTABLE: VTable = VTable {
+    size: SizeKind::of::<u64>(),
+    align: std::mem::align_of::<u64>(),
+    mark_cb: cell_mark,
+    rewrite_cb: cell_rewrite,
+    ..VTable::DEFAULT
+};
+
+fn heap_with_cell() -> GCAlloc {
+    // A Cheney scan would run both callbacks while copying.
+    let config = GCConfig {
+        separate_passes: true,
+        ..Default::default()
+    };
+    let mut gc = GCAlloc::with_config(4096, config);
+    gc.set_root_enumerator(Box::new(|gc| record("roots", gc)));
+    gc.set_root_rewriter(Box::new(|gc| record("root rewriter", gc)));
+    let cell = gc.allocate_typed(&CELL_VTABLE, 0u64).unwrap();
+    // Handles aren't released, so the cell stays alive.
+    gc.acquire_handle(cell);
+    gc
+}
+
+#[test]
+fn callbacks_see_their_phase() {
+    let mut gc = heap_with_cell();
+    assert_eq!(gc.phase(), GCPhase::Idle);
+    take_seen();
+
+    gc.collect();
+    assert_eq!(gc.phase(), GCPhase::Idle);
+    assert_eq!(
+        take_seen(),
+        [
+            ("roots", GCPhase::MarkingRoots),
+            ("mark", GCPhase::Marking),
+            ("rewrite", GCPhase::Rewriting),
+            ("root rewriter", GCPhase::Rewriting),
+        ]
+    );
+
+    let cell = gc.allocate_typed(&CELL_VTABLE, 0u64).unwrap();
+    gc.acquire_handle(cell);
+    take_seen();
+    gc.minor_collect();
+    assert_eq!(gc.phase(), GCPhase::Idle);
+    assert_eq!(
+        take_seen(),
+        [
+            ("roots", GCPhase::MarkingRoots),
+            ("mark", GCPhase::Marking),
+            ("rewrite", GCPhase::Rewriting),
+            ("root rewriter", GCPhase::Rewriting),
+        ]
+    );
+}
+
+#[test]
+fn incremental_mark_is_idle_between_steps() {
+    let mut gc = heap_with_cell();
+    take_seen();
+
+    assert!(gc.mark_step(16));
+    assert_eq!(gc.phase(), GCPhase::Idle);
+    assert_eq!(
+        take_seen(),
+        [("roots", GCPhase::MarkingRoots), ("mark", GCPhase::Marking)]
+    );
+    gc.collect();
+    assert_eq!(gc.phase(), GCPhase::Idle);
+}
+
+#[test]
+fn cheney_scan_runs_callbacks_while_copying() {
+    let mut gc = GCAlloc::new(4096);
+    gc.set_root_rewriter(Box::new(|gc| record("root rewriter", gc)));
+    let cell = gc.allocate_typed(&CELL_VTABLE, 0u64).unwrap();
+    gc.acquire_handle(cell);
+    take_seen();
+
+    gc.collect();
+    assert_eq!(
+        take_seen(),
+        [
+            ("mark", GCPhase::Copying),
+            ("rewrite", GCPhase::Copying),
+            ("root rewriter", GCPhase::Rewriting),
+        ]
+    );
+}
@@ -0,0 +1,81 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc};
+
+fn cons_at(ptr: &Gc<Cons>) -> &Cons {
+    unsafe { &*ptr.get() }
+}
+
+#[test]
+fn pinned_object_never_moves() {
+    let mut gc = GCAlloc::new(4096);
+
+    let child = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let pinned = Cons::alloc(&mut gc, Some(child), None).expect("Malloc failed");
+    let address = pinned.get();
+    let pin = gc.pin(pinned);
+
+    // Something else pointing to the pinned object, allocated in every round.
+    let mut referrer = None;
+    for _ in 0..5 {
+        // Garbage, so the other objects move.
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        let pinned = gc.get_pinned(&pin);
+        let cell = Cons::alloc(&mut gc, Some(pinned), None).expect("Malloc failed");
+        if let Some(old) = referrer.take() {
+            gc.release_handle(old);
+        }
+        referrer = Some(gc.acquire_handle(cell));
+
+        gc.collect();
+
+        let pinned = gc.get_pinned(&pin);
+        assert_eq!(pinned.get(), address);
+        // The child moved, and the pinned object's pointer to it was rewritten.
+        let child = cons_at(&pinned).car.clone().unwrap();
+        assert!(gc.in_young_gen(child.clone()));
+        assert!(cons_at(&child).car.is_none());
+        // Pointers to the pinned object are left as they are.
+        let cell = gc.get_handle(referrer.as_ref().unwrap());
+        assert_eq!(cons_at(&cell).car.as_ref().unwrap().get(), address);
+    }
+
+    // Once unpinned, the object moves with everything else again, and dies when unreachable.
+    gc.release_handle(referrer.unwrap());
+    let pinned = gc.get_pinned(&pin);
+    let handle = gc.acquire_handle(pinned);
+    gc.unpin(pin);
+    gc.collect();
+    gc.collect();
+    let moved = gc.get_handle(&handle);
+    assert!(gc.in_young_gen(moved.clone()));
+    assert!(cons_at(&moved).car.is_some());
+    gc.release_handle(handle);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+fn allocation_fills_around_pinned_objects() {
+    let mut gc = GCAlloc::new(4096);
+    let pinned = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let address = pinned.get();
+    let pin = gc.pin(pinned);
+
+    // Churn through many collections with live data around the pinned object.
+    let mut live = vec![];
+    for i in 0..500 {
+        let cell = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        if i % 10 == 0 {
+            live.push(gc.acquire_handle(cell));
+        }
+        if live.len() > 8 {
+            gc.release_handle(live.remove(0));
+        }
+    }
+    assert!(gc.metadata().gc_count > 2);
+    assert_eq!(gc.get_pinned(&pin).get(), address);
+    for handle in &live {
+        assert!(cons_at(&gc.get_handle(handle)).car.is_none());
+    }
+    gc.unpin(pin);
+}
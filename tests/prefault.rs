@@ -0,0 +1,22 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn prefaulted_heap_allocates_and_collects() {
+    let mut gc = GCAlloc::with_prefault(65536);
+    let lazy = GCAlloc::new(65536).metadata().committed_pages;
+    assert!(gc.metadata().committed_pages > lazy);
+
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+    let cell = gc.acquire_handle(cell);
+    for _ in 0..10_000 {
+        let car = gc.get_handle(&cell);
+        Cons::alloc(&mut gc, Some(car), None).unwrap();
+    }
+    gc.collect();
+    gc.verify();
+    assert!(gc.get(gc.get_handle(&cell)).car.is_none());
+    assert_eq!(
+        gc.metadata().currently_allocated,
+        gc.metadata().last_survivors
+    );
+}
@@ -0,0 +1,114 @@
+#![cfg(target_os = "linux")]
+
+use std::process::Command;
+
+use ike_gc::{gc_ptr::Gc, GCAlloc, GCConfig, SizeKind, VTable};
+
+struct Node {
+    value: usize,
+    next: Option<Gc<Node>>,
+}
+
+fn node_mark(gc: &mut GCAlloc, ptr: *const u8) {
+    let node = unsafe { &*(ptr as *const Node) };
+    if let Some(next) = &node.next {
+        gc.mark_accessible(next.clone());
+    }
+}
+
+fn node_mark_mutating(gc: &mut GCAlloc, ptr: *const u8) {
+    // A buggy callback that writes to the object while the collector is running.
+    unsafe { (*(ptr as *mut Node)).value += 1 };
+    node_mark(gc, ptr);
+}
+
+fn node_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let node = unsafe { &*(ptr as *const Node) };
+    if let Some(next) = &node.next {
+        gc.rewrite_ptr(next);
+    }
+}
+
+static NODE_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Node>(),
+    align: std::mem::align_of::<Node>(),
+    mark_cb: node_mark,
+    rewrite_cb: node_rewrite,
+    ..VTable::DEFAULT
+};
+
+static MUTATING_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Node>(),
+    align: std::mem::align_of::<Node>(),
+    mark_cb: node_mark_mutating,
+    rewrite_cb: node_rewrite,
+    ..VTable::DEFAULT
+};
+
+fn protected_heap() -> GCAlloc {
+    GCAlloc::with_config(
+        65536,
+        GCConfig {
+            protect_during_gc: true,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn well_behaved_collection_succeeds() {
+    let mut gc = protected_heap();
+    let node = gc
+        .allocate_typed(
+            &NODE_VTABLE,
+            Node {
+                value: 7,
+                next: None,
+            },
+        )
+        .expect("Malloc failed");
+    let handle = gc.acquire_handle(node);
+    gc.collect();
+    gc.collect();
+
+    let node = gc.get_handle(&handle);
+    assert_eq!(unsafe { (*node.get()).value }, 7);
+    // The heap is writable again after the collection.
+    unsafe { (*(node.get() as *mut Node)).value = 8 };
+    gc.release_handle(handle);
+}
+
+const CHILD_ENV: &str = "IKE_GC_PROTECT_CHILD";
+
+#[test]
+fn mutation_during_gc_faults() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        let mut gc = protected_heap();
+        let node = gc
+            .allocate_typed(
+                &MUTATING_VTABLE,
+                Node {
+                    value: 0,
+                    next: None,
+                },
+            )
+            .expect("Malloc failed");
+        let _handle = gc.acquire_handle(node);
+        gc.collect();
+        unreachable!("Mutation during GC was not caught");
+    }
+
+    // The fault aborts the process, so observe it from a child process.
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "mutation_during_gc_faults", "--nocapture"])
+        .env(CHILD_ENV, "1")
+        .output()
+        .expect("Failed to spawn child test");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("mutated during garbage collection"),
+        "unexpected child output: {}",
+        stderr
+    );
+}
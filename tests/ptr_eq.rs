@@ -0,0 +1,36 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc};
+
+#[test]
+fn ptr_eq_compares_identity() {
+    let mut gc = GCAlloc::new(65536);
+    let a = Cons::alloc(&mut gc, None, None).unwrap();
+    let b = Cons::alloc(&mut gc, None, None).unwrap();
+    assert!(a.ptr_eq(&a.clone()));
+    assert!(!a.ptr_eq(&b));
+
+    // Pointers rewritten by the same collection still agree.
+    let pair = Cons::alloc(&mut gc, Some(a.clone()), Some(a)).unwrap();
+    let pair = gc.acquire_handle(pair);
+    gc.collect();
+    let pair = gc.get(gc.get_handle(&pair));
+    let (car, cdr) = (pair.car.clone().unwrap(), pair.cdr.clone().unwrap());
+    assert!(car.ptr_eq(&cdr));
+}
+
+#[test]
+fn dangling_pointer_is_a_sentinel() {
+    let mut gc = GCAlloc::new(65536);
+    let dangling = Gc::<Cons>::dangling();
+    assert_eq!(
+        dangling.get(),
+        std::ptr::NonNull::<Cons>::dangling().as_ptr()
+    );
+    assert!(dangling.is_dangling());
+    assert!(dangling.ptr_eq(&Gc::dangling()));
+
+    let cons = Cons::alloc(&mut gc, None, None).unwrap();
+    assert!(!cons.is_dangling());
+    dangling.set(cons.get());
+    assert!(!dangling.is_dangling());
+    assert!(dangling.ptr_eq(&cons));
+}
@@ -0,0 +1,72 @@
+use ike_gc::{GCAlloc, SizeKind, VTable};
+
+const ENTRIES: usize = 4;
+
+/// A pointer table that stores bare pointers into the heap.
+struct Table {
+    entries: [*const u8; ENTRIES],
+}
+
+fn table_mark(gc: &mut GCAlloc, ptr: *const u8) {
+    let table = unsafe { &*(ptr as *const Table) };
+    for &entry in &table.entries {
+        gc.mark_raw(entry);
+    }
+}
+
+fn table_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let table = unsafe { &mut *(ptr as *mut Table) };
+    for entry in &mut table.entries {
+        gc.rewrite_raw(entry);
+    }
+}
+
+static TABLE_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Table>(),
+    align: std::mem::align_of::<Table>(),
+    mark_cb: table_mark,
+    rewrite_cb: table_rewrite,
+    ..VTable::DEFAULT
+};
+
+static LEAF_VTABLE: VTable = VTable {
+    size: SizeKind::fixed(8),
+    align: 16,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn raw_pointer_table_is_rewritten() {
+    let mut gc = GCAlloc::new(4096);
+
+    let table = gc
+        .allocate_typed(
+            &TABLE_VTABLE,
+            Table {
+                entries: [std::ptr::null(); ENTRIES],
+            },
+        )
+        .expect("Malloc failed");
+    let mut original = [std::ptr::null(); ENTRIES];
+    for slot in &mut original {
+        // Garbage between the leaves, so the survivors have to move.
+        gc.allocate(&LEAF_VTABLE, 8).expect("Malloc failed");
+        *slot = gc.allocate(&LEAF_VTABLE, 8).expect("Malloc failed").get();
+    }
+    unsafe { (*(table.get() as *mut Table)).entries = original };
+    let handle = gc.acquire_handle(table);
+
+    gc.collect();
+
+    let table = gc.get_handle(&handle);
+    let entries = unsafe { (*table.get()).entries };
+    for (i, &entry) in entries.iter().enumerate() {
+        assert_ne!(entry, original[i]);
+        assert!(gc.in_young_gen(ike_gc::gc_ptr::Gc::new(entry)));
+        if i > 0 {
+            // Survivors are compacted in allocation order.
+            assert_eq!(entry as usize - entries[i - 1] as usize, 32);
+        }
+    }
+    gc.release_handle(handle);
+}
@@ -0,0 +1,25 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn reclaimed_and_surviving_bytes() {
+    let mut gc = GCAlloc::new(65536);
+    let cells: Vec<_> = (0..100)
+        .map(|_| Cons::alloc(&mut gc, None, None).unwrap())
+        .collect();
+    let before = gc.metadata().currently_allocated;
+    let cell_size = before / cells.len();
+    assert_eq!(cell_size % 16, 0);
+
+    let kept = gc.acquire_handle(cells[42].clone());
+    gc.collect();
+    let meta = gc.metadata();
+    assert_eq!(meta.last_survivors, cell_size);
+    assert_eq!(meta.last_reclaimed, before - cell_size);
+    assert_eq!(meta.last_survivors, meta.currently_allocated);
+
+    gc.release_handle(kept);
+    gc.collect();
+    let meta = gc.metadata();
+    assert_eq!(meta.last_survivors, 0);
+    assert_eq!(meta.last_reclaimed, cell_size);
+}
@@ -0,0 +1,44 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc, Trace};
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Trace)]
+struct Node {
+    next: Option<Gc<Node>>,
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn reset_empties_the_heap() {
+    let mut gc = GCAlloc::new(65536);
+    let mut list = None;
+    for _ in 0..10 {
+        list = Some(gc.alloc(Node { next: list }).unwrap());
+    }
+    let handle = gc.acquire_handle(list.unwrap());
+    let weak = gc.acquire_weak(gc.get_handle(&handle));
+    gc.collect();
+
+    gc.reset();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 10);
+    let meta = gc.metadata();
+    assert_eq!(meta.currently_allocated, 0);
+    assert_eq!(meta.gc_count, 0);
+    assert_eq!(gc.handle_count(), 0);
+    assert!(gc.upgrade(&weak).is_none());
+    gc.verify();
+
+    // The heap is immediately reusable.
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+    let cell = gc.acquire_handle(cell);
+    gc.collect();
+    assert!(gc.get(gc.get_handle(&cell)).car.is_none());
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 10);
+}
@@ -0,0 +1,57 @@
+#![cfg(target_os = "linux")]
+
+use ike_gc::{GCAlloc, SizeKind, VTable};
+
+static PAGE_VTABLE: VTable = VTable {
+    size: SizeKind::fixed(4096 - 16),
+    align: 16,
+    ..VTable::DEFAULT
+};
+
+/// Resident set size of this process, in bytes.
+fn rss() -> usize {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    let line = status
+        .lines()
+        .find(|l| l.starts_with("VmRSS:"))
+        .expect("VmRSS not found");
+    let kb: usize = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .expect("Malformed VmRSS");
+    kb * 1024
+}
+
+const MB: usize = 1024 * 1024;
+
+#[test]
+fn collect_returns_dead_space() {
+    let baseline = rss();
+    let mut gc = GCAlloc::new(128 * MB);
+    gc.set_return_memory(true);
+
+    let root = gc.allocate(&PAGE_VTABLE, 4096 - 16).expect("Malloc failed");
+    let handle = gc.acquire_handle(root);
+
+    // Fill 64 MiB with garbage.
+    for _ in 0..(64 * MB / 4096) {
+        gc.allocate(&PAGE_VTABLE, 4096 - 16).expect("Malloc failed");
+    }
+    assert_eq!(gc.metadata().gc_count, 0);
+    let before = rss();
+    assert!(before >= baseline + 48 * MB);
+
+    gc.collect();
+    assert!(rss() + 48 * MB <= before);
+
+    // The released space is used again by the next collection.
+    for _ in 0..(64 * MB / 4096) {
+        gc.allocate(&PAGE_VTABLE, 4096 - 16).expect("Malloc failed");
+    }
+    gc.collect();
+    assert!(rss() < baseline + 16 * MB);
+    gc.verify();
+
+    gc.release_handle(handle);
+}
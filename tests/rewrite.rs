@@ -0,0 +1,66 @@
+use ike_gc::{fixtures::GcTree, gc_ptr::Gc, GCAlloc};
+
+/// Allocate a chain of `len` nodes linked through `left`, valued `len - 1` down to 0, with a
+/// garbage node before each one.
+fn alloc_chain(gc: &mut GCAlloc, len: usize) -> Gc<GcTree> {
+    let mut chain = None;
+    for value in 0..len {
+        GcTree::alloc(gc, usize::MAX, None, None).expect("Malloc failed");
+        chain = Some(GcTree::alloc(gc, value, chain, None).expect("Malloc failed"));
+    }
+    chain.unwrap()
+}
+
+#[test]
+fn linked_objects_are_rewritten() {
+    let mut gc = GCAlloc::new(65536);
+    let handle = {
+        let chain = alloc_chain(&mut gc, 5);
+        gc.acquire_handle(chain)
+    };
+
+    for _ in 0..3 {
+        gc.collect();
+
+        let mut expected = 5;
+        let mut cur = Some(gc.get_handle(&handle));
+        while let Some(node) = cur {
+            let node = unsafe { &*node.get() };
+            expected -= 1;
+            assert_eq!(node.value, expected);
+            assert!(node.right.is_none());
+            cur = node.left.clone();
+        }
+        assert_eq!(expected, 0);
+    }
+    gc.release_handle(handle);
+}
+
+#[test]
+fn child_pointers_land_in_new_from_space() {
+    let mut gc = GCAlloc::new(65536);
+    let chain = alloc_chain(&mut gc, 8);
+    let old_children: Vec<_> = {
+        let mut children = Vec::new();
+        let mut cur = unsafe { (*chain.get()).left.clone() };
+        while let Some(node) = cur {
+            children.push(node.get());
+            cur = unsafe { (*node.get()).left.clone() };
+        }
+        children
+    };
+    let handle = gc.acquire_handle(chain);
+
+    gc.collect();
+
+    let mut cur = unsafe { (*gc.get_handle(&handle).get()).left.clone() };
+    let mut count = 0;
+    while let Some(node) = cur {
+        assert!(gc.in_young_gen(node.clone()));
+        assert!(!old_children.contains(&node.get()));
+        cur = unsafe { (*node.get()).left.clone() };
+        count += 1;
+    }
+    assert_eq!(count, old_children.len());
+    gc.release_handle(handle);
+}
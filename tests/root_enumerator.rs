@@ -0,0 +1,44 @@
+use std::sync::{Arc, Mutex};
+
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc};
+
+#[test]
+fn enumerated_root_survives() {
+    let mut gc = GCAlloc::new(4096);
+    // Garbage in front of the objects, so they have to move.
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let cons = Cons::alloc(&mut gc, Some(leaf), None).expect("Malloc failed");
+
+    // A root table the heap knows nothing about.
+    let table = Arc::new(Mutex::new(vec![cons.get() as *const u8 as usize]));
+    let roots = table.clone();
+    gc.set_root_enumerator(Box::new(move |gc| {
+        for &root in roots.lock().unwrap().iter() {
+            gc.mark_raw(root as *const u8);
+        }
+    }));
+    let roots = table.clone();
+    gc.set_root_rewriter(Box::new(move |gc| {
+        for root in roots.lock().unwrap().iter_mut() {
+            let mut slot = *root as *const u8;
+            gc.rewrite_raw(&mut slot);
+            *root = slot as usize;
+        }
+    }));
+
+    let before = table.lock().unwrap()[0];
+    gc.collect();
+    let after = table.lock().unwrap()[0];
+    assert_ne!(before, after);
+
+    let cons = gc.get(Gc::new(after as *const Cons));
+    let leaf = gc.get(cons.car.clone().unwrap());
+    assert!(leaf.car.is_none() && leaf.cdr.is_none());
+    let cons_size = 16 + std::mem::size_of::<Cons>().next_multiple_of(16);
+    assert_eq!(gc.metadata().currently_allocated, 2 * cons_size);
+
+    table.lock().unwrap().clear();
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
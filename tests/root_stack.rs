@@ -0,0 +1,34 @@
+use ike_gc::{fixtures::GcTree, GCAlloc};
+
+#[test]
+fn shadow_stack_roots_are_rewritten() {
+    let mut gc = GCAlloc::new(65536);
+    let mut roots = vec![];
+    let mut addrs = vec![];
+    for value in 0..5 {
+        // Garbage in between, so the roots have to move.
+        GcTree::alloc(&mut gc, 100, None, None).expect("Malloc failed");
+        let node = GcTree::alloc(&mut gc, value, None, None).expect("Malloc failed");
+        addrs.push(node.get());
+        roots.push(gc.push_root(node));
+    }
+    assert_eq!(gc.root_stack_len(), 5);
+
+    gc.collect();
+    for (value, (&root, &addr)) in roots.iter().zip(&addrs).enumerate() {
+        let node = gc.get_root(root);
+        assert_ne!(node.get(), addr);
+        assert!(gc.in_young_gen(node.clone()));
+        assert_eq!(gc.get(node).value, value);
+    }
+
+    // Popping unroots the top of the stack only.
+    gc.pop_root(3);
+    gc.collect();
+    assert_eq!(gc.root_stack_len(), 2);
+    assert_eq!(gc.get(gc.get_root(roots[1])).value, 1);
+
+    gc.pop_root(2);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use ike_gc::{gc_ptr::Gc, GCAlloc, SizeKind, VTable};
+
+struct Leaf {
+    _value: usize,
+}
+
+/// Address of the leaf before the collection moved it.
+static ORIGINAL: AtomicUsize = AtomicUsize::new(0);
+static SAME_AS_ORIGINAL: AtomicBool = AtomicBool::new(false);
+static SAME_AS_NEIGHBOR: AtomicBool = AtomicBool::new(true);
+
+fn leaf_mark(_gc: &mut GCAlloc, _ptr: *const u8) {}
+
+fn leaf_free(_gc: &mut GCAlloc, _ptr: *const u8) {}
+
+fn leaf_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    // Called after the copy phase, so `ptr` is the forwarded copy of the leaf.
+    let original = Gc::new(ORIGINAL.load(Ordering::SeqCst) as *const u8);
+    let moved = Gc::new(ptr);
+    SAME_AS_ORIGINAL.store(gc.same_object(original, moved.clone()), Ordering::SeqCst);
+    let neighbor = Gc::new(unsafe { ptr.add(16) });
+    SAME_AS_NEIGHBOR.store(gc.same_object(moved, neighbor), Ordering::SeqCst);
+}
+
+static LEAF_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Leaf>(),
+    align: std::mem::align_of::<Leaf>(),
+    mark_cb: leaf_mark,
+    rewrite_cb: leaf_rewrite,
+    free_cb: leaf_free,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn original_and_forwarded_are_same_object() {
+    let mut gc = GCAlloc::new(4096);
+
+    let leaf = gc
+        .allocate_typed(&LEAF_VTABLE, Leaf { _value: 42 })
+        .expect("Malloc failed");
+    let other = gc
+        .allocate_typed(&LEAF_VTABLE, Leaf { _value: 43 })
+        .expect("Malloc failed");
+    assert!(gc.same_object(leaf.clone(), leaf.clone()));
+    assert!(!gc.same_object(leaf.clone(), other));
+
+    ORIGINAL.store(leaf.get() as usize, Ordering::SeqCst);
+    let handle = gc.acquire_handle(leaf);
+    gc.collect();
+
+    assert!(SAME_AS_ORIGINAL.load(Ordering::SeqCst));
+    assert!(!SAME_AS_NEIGHBOR.load(Ordering::SeqCst));
+
+    // Outside of a collection, the stale address is no longer the same object.
+    let moved = gc.get_handle(&handle);
+    let stale = Gc::new(ORIGINAL.load(Ordering::SeqCst) as *const Leaf);
+    assert!(!gc.same_object(moved, stale));
+    gc.release_handle(handle);
+}
@@ -0,0 +1,80 @@
+use std::{
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ike_gc::{GCAlloc, SizeKind, VTable};
+
+static MARKED: AtomicUsize = AtomicUsize::new(0);
+static REWRITTEN: AtomicUsize = AtomicUsize::new(0);
+static FREED: AtomicUsize = AtomicUsize::new(0);
+
+fn count_mark(_gc: &mut GCAlloc, _ptr: *const u8) {
+    MARKED.fetch_add(1, Ordering::SeqCst);
+}
+
+fn count_rewrite(_gc: &mut GCAlloc, _ptr: *const u8) {
+    REWRITTEN.fetch_add(1, Ordering::SeqCst);
+}
+
+fn count_free(_gc: &mut GCAlloc, _ptr: *const u8) {
+    FREED.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Objects of this type store their own size in their first word.
+unsafe fn stored_size(ptr: *const u8) -> NonZeroUsize {
+    unsafe { NonZeroUsize::new(*(ptr as *const usize)).unwrap() }
+}
+
+static COUNTING_VTABLE: VTable = VTable {
+    size: SizeKind::callback(stored_size),
+    align: 16,
+    mark_cb: count_mark,
+    rewrite_cb: count_rewrite,
+    free_cb: count_free,
+    ..VTable::DEFAULT
+};
+
+fn reset() -> (usize, usize, usize) {
+    (
+        MARKED.swap(0, Ordering::SeqCst),
+        REWRITTEN.swap(0, Ordering::SeqCst),
+        FREED.swap(0, Ordering::SeqCst),
+    )
+}
+
+#[test]
+fn scans_visit_every_block_once() {
+    let mut gc = GCAlloc::new(65536);
+    let sizes = [8, 16, 40, 100, 1000, 24, 8, 300, 56];
+
+    let mut handles = Vec::new();
+    for (i, &sz) in sizes.iter().cycle().take(60).enumerate() {
+        let ptr = gc.allocate(&COUNTING_VTABLE, sz).expect("Malloc failed");
+        unsafe { *(ptr.get() as *mut usize) = sz };
+        if i % 3 == 0 {
+            handles.push(gc.acquire_handle(ptr));
+        }
+    }
+
+    gc.collect();
+    assert_eq!(reset(), (20, 20, 40));
+
+    // Release every other survivor, so live and dead blocks interleave again.
+    for (i, handle) in std::mem::take(&mut handles).into_iter().enumerate() {
+        if i % 2 == 0 {
+            gc.release_handle(handle);
+        } else {
+            handles.push(handle);
+        }
+    }
+    gc.collect();
+    assert_eq!(reset(), (10, 10, 10));
+
+    for handle in handles {
+        gc.release_handle(handle);
+    }
+    gc.collect();
+    assert_eq!(reset(), (0, 0, 10));
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
@@ -0,0 +1,45 @@
+use ike_gc::{fixtures::Cons, AllocError, GCAlloc};
+
+#[test]
+fn scope_releases_handles_on_drop() {
+    let mut gc = GCAlloc::new(4096);
+
+    {
+        let mut scope = gc.scope();
+        let a = Cons::alloc(&mut scope, None, None).expect("Malloc failed");
+        let a = scope.root(a);
+        {
+            let mut inner = scope.scope();
+            let b = Cons::alloc(&mut inner, None, None).expect("Malloc failed");
+            inner.root(b);
+            inner.collect();
+            assert_eq!(inner.handle_count(), 2);
+        }
+        assert_eq!(scope.handle_count(), 1);
+
+        // The outer root is still usable, and only it survives.
+        scope.collect();
+        let a = scope.get_handle(&a);
+        assert!(scope.in_young_gen(a));
+        assert!(scope.metadata().currently_allocated > 0);
+    }
+
+    assert_eq!(gc.handle_count(), 0);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+fn early_return_releases_handles() {
+    fn root_and_bail(gc: &mut GCAlloc) -> Result<(), AllocError> {
+        let mut scope = gc.scope();
+        let cons = Cons::alloc(&mut scope, None, None)?;
+        scope.root(cons);
+        Err(AllocError::OutOfMemory)?;
+        unreachable!()
+    }
+
+    let mut gc = GCAlloc::new(4096);
+    assert!(root_and_bail(&mut gc).is_err());
+    assert_eq!(gc.handle_count(), 0);
+}
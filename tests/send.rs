@@ -0,0 +1,20 @@
+use ike_gc::{fixtures::GcTree, GCAlloc};
+
+#[test]
+fn heap_moves_to_another_thread() {
+    let mut gc = GCAlloc::new(65536);
+    GcTree::alloc_complete(&mut gc, 4, 0);
+    let tree = GcTree::alloc_complete(&mut gc, 5, 0).unwrap();
+    let handle = gc.acquire_handle(tree);
+
+    let (gc, handle) = std::thread::spawn(move || {
+        gc.collect();
+        (gc, handle)
+    })
+    .join()
+    .unwrap();
+
+    let tree = gc.get(gc.get_handle(&handle));
+    assert_eq!(tree.value, 0);
+    assert_eq!(gc.metadata().gc_count, 1);
+}
@@ -0,0 +1,55 @@
+use ike_gc::{fixtures::Cons, GCAlloc, ShrinkPolicy};
+
+const SIZE: usize = 1 << 20;
+
+#[test]
+fn heap_shrinks_after_a_spike() {
+    let mut gc = GCAlloc::new(SIZE);
+    gc.set_shrink_policy(Some(ShrinkPolicy {
+        occupancy: 0.25,
+        collections: 3,
+        min_size: 16 * 1024,
+    }));
+    let kept = Cons::alloc(&mut gc, None, None).unwrap();
+    let kept = gc.acquire_handle(kept);
+
+    // A spike of live data, most of the heap.
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+    let mut spike = gc.acquire_handle(cell);
+    let cell_size = 16 + core::mem::size_of::<Cons>().next_multiple_of(16);
+    for _ in 0..SIZE / 2 / cell_size {
+        let cdr = gc.get_handle(&spike);
+        let cell = Cons::alloc(&mut gc, None, Some(cdr)).unwrap();
+        gc.release_handle(spike);
+        spike = gc.acquire_handle(cell);
+    }
+    gc.collect();
+    assert_eq!(gc.metadata().semispace_size, SIZE);
+
+    // Then it settles at a small live set.
+    gc.release_handle(spike);
+    for _ in 0..3 {
+        gc.collect();
+        assert_eq!(gc.metadata().semispace_size, SIZE);
+    }
+    gc.collect();
+    let shrunk = gc.metadata().semispace_size;
+    assert!(shrunk < SIZE, "Heap stayed at {} bytes", shrunk);
+    assert!(shrunk >= 16 * 1024);
+
+    // The live object moved along, and the heap still works.
+    let cell = Cons::alloc(&mut gc, None, None).unwrap();
+    gc.get_mut(gc.get_handle(&kept)).car = Some(cell);
+    gc.collect();
+    gc.verify();
+    assert!(gc.get(gc.get_handle(&kept)).car.is_some());
+}
+
+#[test]
+fn heap_keeps_its_size_without_a_policy() {
+    let mut gc = GCAlloc::new(SIZE);
+    for _ in 0..8 {
+        gc.collect();
+    }
+    assert_eq!(gc.metadata().semispace_size, SIZE);
+}
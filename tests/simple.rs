@@ -1,4 +1,4 @@
-use ike_gc::{gc_ptr::Gc, GCAlloc, SizeKind, VTable};
+use ike_gc::{gc_ptr::Gc, GCAlloc, Trace, Visitor};
 use log::info;
 
 struct Cons {
@@ -12,37 +12,17 @@ impl Cons {
     }
 }
 
-fn cons_mark(gc: &mut GCAlloc, ptr: *const u8) {
-    let cons = unsafe { &*(ptr as *const Cons) };
-    if let Some(car) = &cons.car {
-        gc.mark_accessible(car.clone());
+impl Trace for Cons {
+    fn visit_fields(&self, visitor: &mut dyn Visitor) {
+        if let Some(car) = &self.car {
+            visitor.visit(car.erase());
+        }
+        if let Some(cdr) = &self.cdr {
+            visitor.visit(cdr.erase());
+        }
     }
-    if let Some(cdr) = &cons.cdr {
-        gc.mark_accessible(cdr.clone());
-    }
-}
-
-fn cons_free(_gc: &mut GCAlloc, _ptr: *const u8) {
-    // noop
 }
 
-fn cons_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
-    let cons = unsafe { &*(ptr as *const Cons) };
-    if let Some(car) = &cons.car {
-        gc.rewrite_ptr(car);
-    }
-    if let Some(cdr) = &cons.cdr {
-        gc.rewrite_ptr(cdr);
-    }
-}
-
-static CONS_VTABLE: VTable = VTable {
-    // size: SizeKind::of::<Cons>(),
-    mark_cb: cons_mark,
-    rewrite_cb: cons_rewrite,
-    free_cb: cons_free,
-};
-
 #[test]
 fn test_main() {
     env_logger::init_from_env(env_logger::Env::default().default_filter_or("trace"));
@@ -50,18 +30,16 @@ fn test_main() {
     let mut gc = GCAlloc::new(65536);
 
     info!("Before allocation; {:?}", gc.metadata());
-    let alloc1 = gc
-        .allocate_typed::<Cons>(&CONS_VTABLE, Cons::new(None, None))
-        .expect("Malloc failed");
+    let alloc1 = gc.alloc(Cons::new(None, None)).expect("Malloc failed");
     let alloc2 = gc
-        .allocate_typed::<Cons>(&CONS_VTABLE, Cons::new(Some(alloc1.clone()), None))
+        .alloc(Cons::new(Some(alloc1.clone()), None))
         .expect("Malloc failed");
     let alloc3 = gc
-        .allocate_typed::<Cons>(&CONS_VTABLE, Cons::new(Some(alloc2.clone()), None))
+        .alloc(Cons::new(Some(alloc2.clone()), None))
         .expect("Malloc failed");
 
     let _alloc4 = gc
-        .allocate_typed::<Cons>(&CONS_VTABLE, Cons::new(Some(alloc3.clone()), None))
+        .alloc(Cons::new(Some(alloc3.clone()), None))
         .expect("Malloc failed");
     let handle3 = gc.acquire_handle(alloc3);
 
@@ -73,14 +51,13 @@ fn test_main() {
     info!("After collection; {:?}", gc.metadata());
 
     // match structure
-    let cons3 = gc.get_handle(&handle3);
-    let cons3 = unsafe { &*cons3.get() };
+    let cons3 = gc.read(&handle3);
     assert!(cons3.car.is_some());
     assert!(cons3.cdr.is_none());
-    let cons2 = unsafe { &*cons3.car.as_ref().unwrap().get() };
+    let cons2 = gc.get(cons3.car.clone().unwrap());
     assert!(cons2.car.is_some());
     assert!(cons2.cdr.is_none());
-    let cons1 = unsafe { &*cons2.car.as_ref().unwrap().get() };
+    let cons1 = gc.get(cons2.car.clone().unwrap());
     assert!(cons1.car.is_none());
     assert!(cons1.cdr.is_none());
 
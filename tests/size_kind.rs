@@ -0,0 +1,17 @@
+use ike_gc::{fixtures::CONS_VTABLE, GCAlloc, VTable};
+
+#[test]
+#[should_panic(expected = "does not match the fixed size")]
+fn fixed_size_mismatch_panics() {
+    let mut gc = GCAlloc::new(4096);
+    let _ = gc.allocate(&CONS_VTABLE, 8);
+}
+
+#[test]
+#[should_panic(expected = "is not aligned to 2 bytes")]
+fn underaligned_vtable_panics() {
+    let mut gc = GCAlloc::new(4096);
+    // Never dereferenced: the allocation checks the pointer first.
+    let vt = (&CONS_VTABLE as *const VTable as *const u8).wrapping_add(1) as *const VTable;
+    let _ = gc.allocate(vt, 32);
+}
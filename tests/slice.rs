@@ -0,0 +1,64 @@
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, GCAlloc, GcArray, VTable};
+
+fn array_mark(gc: &mut GCAlloc, ptr: *const u8) {
+    let array = unsafe { &*(ptr as *const GcArray<Gc<Cons>>) };
+    for elem in unsafe { array.as_slice() } {
+        gc.mark_accessible(elem.clone());
+    }
+}
+
+fn array_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let array = unsafe { &*(ptr as *const GcArray<Gc<Cons>>) };
+    for elem in unsafe { array.as_slice() } {
+        gc.rewrite_ptr(elem);
+    }
+}
+
+static CONS_ARRAY_VTABLE: VTable = VTable {
+    size: GcArray::<Gc<Cons>>::SIZE,
+    align: std::mem::align_of::<GcArray<Gc<Cons>>>(),
+    mark_cb: array_mark,
+    rewrite_cb: array_rewrite,
+    ..VTable::DEFAULT
+};
+
+/// The number of cells in the list starting at `cell`, following `cdr`.
+fn list_len(gc: &GCAlloc, mut cell: Option<Gc<Cons>>) -> usize {
+    let mut len = 0;
+    while let Some(c) = cell {
+        len += 1;
+        cell = gc.get(c).cdr.clone();
+    }
+    len
+}
+
+#[test]
+fn slice_of_pointers_survives_collection() {
+    let mut gc = GCAlloc::new(65536);
+
+    // Element i heads a list of i + 1 cells.
+    let mut elems = vec![];
+    let mut list = None;
+    for _ in 0..5 {
+        Cons::alloc(&mut gc, None, None).unwrap();
+        let cell = Cons::alloc(&mut gc, None, list).unwrap();
+        elems.push(cell.clone());
+        list = Some(cell);
+    }
+    let slice = gc
+        .allocate_slice(&CONS_ARRAY_VTABLE, elems.into_iter())
+        .unwrap();
+    assert_eq!(slice.len(), 5);
+    let handle = gc.acquire_handle(slice.array().clone());
+
+    gc.collect();
+    gc.collect();
+
+    let array = gc.get_handle(&handle);
+    let len = gc.get(array.clone()).len();
+    let slice = ike_gc::GcSlice::new(array, len);
+    for (i, elem) in slice.get(&gc).iter().enumerate() {
+        assert_eq!(list_len(&gc, Some(elem.clone())), i + 1);
+    }
+    gc.verify();
+}
@@ -0,0 +1,47 @@
+use ike_gc::{
+    fixtures::{GcTree, GC_TREE_VTABLE},
+    snapshot::{SnapshotError, SnapshotReader},
+    GCAlloc,
+};
+
+#[test]
+fn snapshot_round_trip() {
+    let mut gc = GCAlloc::new(65536);
+    let tree = GcTree::alloc_complete(&mut gc, 4, 0).unwrap();
+    let handle = gc.acquire_handle(tree);
+    gc.collect();
+
+    let data = gc.snapshot(|vt| {
+        if std::ptr::eq(vt, &GC_TREE_VTABLE) {
+            1
+        } else {
+            0
+        }
+    });
+    let reader = SnapshotReader::new(&data).unwrap();
+    assert_eq!(reader.objects().len(), 15);
+    assert!(reader.objects().iter().all(|object| object.type_id == 1));
+
+    // Walk the tree from the root offline.
+    assert_eq!(reader.roots().len(), 1);
+    let root = gc.get_handle(&handle).get() as u64;
+    assert_eq!(reader.base() + reader.roots()[0] + 16, root);
+    let mut stack = vec![reader.roots()[0]];
+    let mut seen = 0;
+    while let Some(offset) = stack.pop() {
+        let object = reader.object(offset).unwrap();
+        assert_eq!(reader.bytes(object).len() as u64, object.size);
+        stack.extend(&object.pointers);
+        seen += 1;
+    }
+    assert_eq!(seen, 15);
+
+    assert_eq!(
+        SnapshotReader::new(&data[..data.len() - 1]).err(),
+        Some(SnapshotError::Truncated)
+    );
+    assert_eq!(
+        SnapshotReader::new(b"not a snapshot").err(),
+        Some(SnapshotError::BadMagic)
+    );
+}
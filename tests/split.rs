@@ -0,0 +1,63 @@
+use ike_gc::{fixtures::Cons, AllocError, GCAlloc};
+
+fn cons_size() -> usize {
+    let mut probe = GCAlloc::new(1024);
+    Cons::alloc(&mut probe, None, None).unwrap();
+    probe.metadata().currently_allocated
+}
+
+#[test]
+fn survivors_fit_small_to_space() {
+    let cons = cons_size();
+    let mut gc = GCAlloc::with_split(100 * cons, 10 * cons);
+
+    // A short list survives every collection, while the garbage around it is dropped.
+    let mut list = None;
+    for _ in 0..5 {
+        list = Some(Cons::alloc(&mut gc, None, list).unwrap());
+    }
+    let list = gc.acquire_handle(list.unwrap());
+    for _ in 0..1000 {
+        Cons::alloc(&mut gc, None, None).unwrap();
+    }
+    assert!(gc.metadata().gc_count > 1);
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 5 * cons);
+    gc.verify();
+
+    let mut len = 0;
+    let mut cell = Some(gc.get_handle(&list));
+    while let Some(c) = cell {
+        len += 1;
+        cell = gc.get(c).cdr.clone();
+    }
+    assert_eq!(len, 5);
+}
+
+#[test]
+fn survivors_overflowing_to_space_run_out_of_memory() {
+    let cons = cons_size();
+    let mut gc = GCAlloc::with_split(20 * cons, 10 * cons);
+
+    let mut handles = vec![];
+    let err = loop {
+        match Cons::alloc(&mut gc, None, None) {
+            Ok(cell) => handles.push(gc.acquire_handle(cell)),
+            Err(err) => break err,
+        }
+    };
+    assert_eq!(err, AllocError::OutOfMemory);
+    assert_eq!(handles.len(), 20);
+    // The abandoned collections left the heap untouched.
+    assert_eq!(gc.metadata().gc_count, 0);
+    assert_eq!(gc.metadata().currently_allocated, 20 * cons);
+    gc.verify();
+
+    // Once enough is released, the survivors fit again.
+    for handle in handles.drain(..15) {
+        gc.release_handle(handle);
+    }
+    Cons::alloc(&mut gc, None, None).unwrap();
+    assert_eq!(gc.metadata().gc_count, 1);
+    gc.verify();
+}
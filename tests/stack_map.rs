@@ -0,0 +1,49 @@
+use std::sync::{Arc, Mutex};
+
+use ike_gc::{fixtures::Cons, gc_ptr::Gc, Frame, GCAlloc, StackMap};
+
+const RETURN_ADDRESS: usize = 0x1234;
+
+#[test]
+fn stack_map_slots_are_roots_and_rewritten() {
+    let mut gc = GCAlloc::new(4096);
+    // Garbage in front of the objects, so they have to move.
+    Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let leaf = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let cons = Cons::alloc(&mut gc, Some(leaf), None).expect("Malloc failed");
+
+    // A synthetic frame: a slot holding the object below the frame pointer, a null slot, and
+    // a slot the stack map doesn't cover. The frame pointer points at the end.
+    let stack = Arc::new(Mutex::new([cons.get() as usize, 0, 0xdead_0000, 0]));
+    gc.register_stack_map(RETURN_ADDRESS, StackMap::new([-32, -24]));
+    let frames = stack.clone();
+    gc.set_frame_walker(Box::new(move |visit| {
+        let mut frame = frames.lock().unwrap();
+        let frame_pointer = unsafe { frame.as_mut_ptr().add(4) } as *mut u8;
+        // A native frame, which has no stack map.
+        visit(Frame {
+            return_address: 0x9999,
+            frame_pointer: std::ptr::null_mut(),
+        });
+        visit(Frame {
+            return_address: RETURN_ADDRESS,
+            frame_pointer,
+        });
+    }));
+
+    let before = stack.lock().unwrap()[0];
+    gc.collect();
+    let after = stack.lock().unwrap()[0];
+    assert_ne!(before, after);
+    assert_eq!(stack.lock().unwrap()[1..], [0, 0xdead_0000, 0]);
+
+    let cons = gc.get(Gc::new(after as *const Cons));
+    let leaf = gc.get(cons.car.clone().unwrap());
+    assert!(leaf.car.is_none() && leaf.cdr.is_none());
+    let cons_size = 16 + std::mem::size_of::<Cons>().next_multiple_of(16);
+    assert_eq!(gc.metadata().currently_allocated, 2 * cons_size);
+
+    stack.lock().unwrap()[0] = 0;
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
@@ -0,0 +1,36 @@
+use ike_gc::{
+    fixtures::{Cons, GcTree, CONS_VTABLE, GC_TREE_VTABLE},
+    GCAlloc, VTable,
+};
+
+fn stats_of(gc: &GCAlloc, vt: &VTable) -> (usize, usize) {
+    gc.type_stats()
+        .into_iter()
+        .find(|&(stat_vt, _, _)| core::ptr::eq(stat_vt, vt))
+        .map_or((0, 0), |(_, count, bytes)| (count, bytes))
+}
+
+#[test]
+fn allocations_are_counted_per_type() {
+    let mut gc = GCAlloc::new(65536);
+    for _ in 0..10 {
+        Cons::alloc(&mut gc, None, None).unwrap();
+    }
+    // A complete tree of depth 3 has 7 nodes.
+    GcTree::alloc_complete(&mut gc, 3, 0);
+    gc.collect();
+    Cons::alloc(&mut gc, None, None).unwrap();
+
+    let cons_size = 16 + core::mem::size_of::<Cons>().next_multiple_of(16);
+    let tree_size = 16 + core::mem::size_of::<GcTree>().next_multiple_of(16);
+    assert_eq!(stats_of(&gc, &CONS_VTABLE), (11, 11 * cons_size));
+    assert_eq!(stats_of(&gc, &GC_TREE_VTABLE), (7, 7 * tree_size));
+    assert_eq!(gc.type_stats().len(), 2);
+    assert_eq!(
+        gc.metadata().total_allocated,
+        11 * cons_size + 7 * tree_size
+    );
+
+    gc.reset();
+    assert!(gc.type_stats().is_empty());
+}
@@ -0,0 +1,29 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn strings_survive_collection() {
+    let mut gc = GCAlloc::new(65536);
+    let words = [
+        "",
+        "a",
+        "hello, world",
+        "a somewhat longer string crossing a few words",
+        "ü✓",
+    ];
+
+    let mut handles = vec![];
+    for word in words {
+        Cons::alloc(&mut gc, None, None).unwrap();
+        let s = gc.alloc_str(word).unwrap();
+        handles.push(gc.acquire_handle(s));
+    }
+    gc.collect();
+    gc.collect();
+
+    for (word, handle) in words.iter().zip(&handles) {
+        let s = gc.get(gc.get_handle(handle));
+        assert_eq!(s.as_str(), *word);
+        assert_eq!(s.len(), word.len());
+    }
+    gc.verify();
+}
@@ -0,0 +1,55 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+/// Number of garbage cons cells allocated before the first collection.
+fn allocations_until_gc(threshold: Option<f64>) -> usize {
+    let mut gc = GCAlloc::new(4096);
+    if let Some(threshold) = threshold {
+        gc.set_gc_threshold(threshold);
+    }
+    let mut count = 0;
+    while gc.metadata().gc_count == 0 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        count += 1;
+    }
+    count
+}
+
+#[test]
+fn lower_threshold_collects_earlier() {
+    let full = allocations_until_gc(None);
+    let half = allocations_until_gc(Some(0.5));
+    assert!(half < full, "{} >= {}", half, full);
+    assert!(half <= full / 2 + 2);
+}
+
+#[test]
+fn collection_on_full_heap_keeps_allocating() {
+    // Allocating past a full heap collects and then carries on in the freed space.
+    let mut gc = GCAlloc::new(4096);
+    for _ in 0..1000 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    assert!(gc.metadata().gc_count > 0);
+}
+
+#[test]
+fn collect_if_needed_respects_threshold() {
+    let mut gc = GCAlloc::new(4096);
+    gc.set_gc_threshold(0.5);
+    let half = gc.metadata().semispace_size / 2;
+
+    while gc.used_bytes() <= half / 2 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    assert!(!gc.collect_if_needed());
+    assert_eq!(gc.metadata().gc_count, 0);
+
+    while gc.used_bytes() <= half {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    }
+    assert_eq!(gc.metadata().gc_count, 0);
+    assert!(gc.collect_if_needed());
+    assert_eq!(gc.metadata().gc_count, 1);
+    assert_eq!(gc.used_bytes(), 0);
+    assert!(!gc.collect_if_needed());
+}
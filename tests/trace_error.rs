@@ -0,0 +1,123 @@
+use ike_gc::{gc_ptr::Gc, CollectError, GCAlloc, SizeKind, TraceError, VTable};
+
+const CORRUPT: u64 = 0xdead;
+
+/// A list node whose mark callback refuses to trace it when its tag is [`CORRUPT`].
+struct Node {
+    next: Option<Gc<Node>>,
+    tag: u64,
+}
+
+fn node_mark(gc: &mut GCAlloc, ptr: *const u8) {
+    let node = unsafe { &*(ptr as *const Node) };
+    if node.tag == CORRUPT {
+        gc.report_trace_error(ptr, "corrupt tag");
+        return;
+    }
+    if let Some(next) = &node.next {
+        gc.mark_accessible(next.clone());
+    }
+}
+
+fn node_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let node = unsafe { &*(ptr as *const Node) };
+    if let Some(next) = &node.next {
+        gc.rewrite_ptr(next);
+    }
+}
+
+static NODE_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Node>(),
+    align: std::mem::align_of::<Node>(),
+    mark_cb: node_mark,
+    rewrite_cb: node_rewrite,
+    ..VTable::DEFAULT
+};
+
+/// A list of `len` nodes, with garbage between them, whose last node is corrupt.
+fn corrupt_list(gc: &mut GCAlloc, len: usize) -> (Gc<Node>, Gc<Node>) {
+    let bad = Node {
+        next: None,
+        tag: CORRUPT,
+    };
+    let bad = gc.allocate_typed(&NODE_VTABLE, bad).unwrap();
+    let mut list = bad.clone();
+    for tag in 0..len as u64 {
+        gc.allocate_typed(&NODE_VTABLE, Node { next: None, tag })
+            .unwrap();
+        let node = Node {
+            next: Some(list),
+            tag,
+        };
+        list = gc.allocate_typed(&NODE_VTABLE, node).unwrap();
+    }
+    (list, bad)
+}
+
+#[test]
+fn failed_trace_leaves_heap_usable() {
+    let mut gc = GCAlloc::new(4096);
+    let (list, bad) = corrupt_list(&mut gc, 4);
+    let address = list.get();
+    let list = gc.acquire_handle(list);
+    let allocated = gc.metadata().currently_allocated;
+
+    let err = gc.try_collect().unwrap_err();
+    assert_eq!(
+        err,
+        CollectError::Trace(TraceError {
+            object: bad.get() as *const u8,
+            reason: "corrupt tag",
+        })
+    );
+    // Nothing moved or was freed, and nothing is left marked.
+    assert_eq!(gc.get_handle(&list).get(), address);
+    assert_eq!(gc.metadata().currently_allocated, allocated);
+    assert_eq!(gc.metadata().gc_count, 0);
+
+    // Once the object is repaired, the heap collects as usual.
+    gc.get_mut(bad).tag = 0;
+    gc.verify();
+    gc.try_collect().unwrap();
+    gc.verify();
+    assert_eq!(gc.metadata().gc_count, 1);
+    let mut node = Some(gc.get_handle(&list));
+    let mut len = 0;
+    while let Some(ptr) = node {
+        node = gc.get(ptr).next.clone();
+        len += 1;
+    }
+    assert_eq!(len, 5);
+    assert_eq!(gc.metadata().currently_allocated, allocated / 9 * 5);
+
+    let node = Node { next: None, tag: 1 };
+    gc.allocate_typed(&NODE_VTABLE, node).unwrap();
+    gc.release_handle(list);
+}
+
+#[test]
+#[should_panic(expected = "Tracing failed: can't trace object")]
+fn trace_error_panics_in_collect() {
+    let mut gc = GCAlloc::new(4096);
+    let (list, _) = corrupt_list(&mut gc, 2);
+    gc.acquire_handle(list);
+    gc.collect();
+}
+
+#[cfg(feature = "parallel-mark")]
+#[test]
+fn failed_parallel_trace_leaves_heap_usable() {
+    let mut gc = GCAlloc::new(65536);
+    unsafe { gc.set_mark_threads(4) };
+    let (list, bad) = corrupt_list(&mut gc, 200);
+    let address = list.get();
+    let list = gc.acquire_handle(list);
+
+    assert!(matches!(gc.try_collect(), Err(CollectError::Trace(_))));
+    assert_eq!(gc.get_handle(&list).get(), address);
+    gc.get_mut(bad).tag = 0;
+    gc.verify();
+    gc.try_collect().unwrap();
+    gc.verify();
+    gc.release_handle(list);
+}
@@ -0,0 +1,49 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn unrooted_pointer_is_valid_until_collection() {
+    let mut gc = GCAlloc::new(4096);
+    let cells: Vec<_> = (0..4)
+        .map(|_| {
+            let cell = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+            gc.unrooted(cell)
+        })
+        .collect();
+    // Allocating without collecting leaves them valid.
+    let first = gc.get_unrooted(&cells[0]);
+    let head = Cons::alloc(&mut gc, Some(first), None).unwrap();
+    for cell in &cells {
+        assert!(gc.get(gc.get_unrooted(cell)).car.is_none());
+    }
+    assert!(gc
+        .get(head)
+        .car
+        .as_ref()
+        .unwrap()
+        .ptr_eq(&gc.get_unrooted(&cells[0])));
+}
+
+#[test]
+#[should_panic(expected = "used after a collection")]
+fn unrooted_pointer_is_caught_after_collection() {
+    let mut gc = GCAlloc::new(4096);
+    let cell = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let handle = gc.acquire_handle(cell.clone());
+    // A pointer kept outside of the roots isn't rewritten when the cell moves.
+    let stashed = gc.unrooted(cell.clone());
+    gc.collect();
+    assert!(!gc.get_handle(&handle).ptr_eq(&cell));
+    gc.get_unrooted(&stashed);
+}
+
+#[test]
+#[should_panic(expected = "used after a collection")]
+fn unrooted_pointer_is_caught_after_collect_into_new() {
+    let mut gc = GCAlloc::new(4096);
+    let cell = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let handle = gc.acquire_handle(cell.clone());
+    let stashed = gc.unrooted(cell);
+    let new = gc.collect_into_new(0);
+    assert!(new.in_young_gen(new.get_handle(&handle)));
+    new.get_unrooted(&stashed);
+}
@@ -0,0 +1,23 @@
+use ike_gc::{fixtures::Cons, GCAlloc};
+
+#[test]
+fn used_and_free_bytes_add_up() {
+    let mut gc = GCAlloc::new(4096);
+    let size = gc.metadata().semispace_size;
+    assert_eq!(gc.used_bytes(), 0);
+    assert_eq!(gc.free_bytes(), size);
+
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let handle = gc.acquire_handle(cons);
+    for _ in 0..10 {
+        Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+        assert_eq!(gc.used_bytes() + gc.free_bytes(), size);
+        assert_eq!(gc.used_bytes(), gc.metadata().currently_allocated);
+    }
+
+    gc.collect();
+    let cons_size = 16 + std::mem::size_of::<Cons>().next_multiple_of(16);
+    assert_eq!(gc.used_bytes(), cons_size);
+    assert_eq!(gc.free_bytes(), size - cons_size);
+    gc.release_handle(handle);
+}
@@ -0,0 +1,87 @@
+use ike_gc::{
+    fixtures::{Cons, GcTree},
+    gc_ptr::Gc,
+    CollectorMode, GCAlloc, GCConfig,
+};
+
+#[test]
+fn healthy_heap_verifies() {
+    let mut gc = GCAlloc::new(65536);
+    gc.verify();
+
+    GcTree::alloc_complete(&mut gc, 4, 0);
+    let tree = GcTree::alloc_complete(&mut gc, 5, 0).unwrap();
+    let tree = gc.acquire_handle(tree);
+    let mut list = None;
+    for _ in 0..10 {
+        list = Some(Cons::alloc(&mut gc, None, list).expect("Malloc failed"));
+    }
+    let list = gc.acquire_handle(list.unwrap());
+    gc.verify();
+
+    gc.collect();
+    gc.verify();
+
+    gc.release_handle(tree);
+    gc.release_handle(list);
+    gc.collect();
+    gc.verify();
+}
+
+#[test]
+#[should_panic(expected = "outside of the heap")]
+fn pointer_out_of_heap_is_caught() {
+    let mut gc = GCAlloc::new(4096);
+    let outside = Box::new([0u128; 4]);
+    let bogus = Gc::new(&outside[1] as *const u128 as *const Cons);
+    Cons::alloc(&mut gc, Some(bogus), None).expect("Malloc failed");
+    gc.verify();
+}
+
+/// A pointer to the garbage cell slid over by a compacting collection, which leaves a free
+/// block before the pinned cell after it.
+fn pointer_into_free_block(gc: &mut GCAlloc) -> Gc<Cons> {
+    let garbage = Cons::alloc(gc, None, None).expect("Malloc failed");
+    let pinned = Cons::alloc(gc, None, None).expect("Malloc failed");
+    gc.pin(pinned);
+    gc.collect();
+    garbage
+}
+
+fn compacting() -> GCAlloc {
+    GCAlloc::with_config(
+        4096,
+        GCConfig {
+            mode: CollectorMode::Compacting,
+            ..GCConfig::default()
+        },
+    )
+}
+
+#[test]
+fn live_pointer_passes() {
+    let mut gc = compacting();
+    let cell = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    gc.assert_live(cell.clone());
+    let cell = gc.acquire_handle(cell);
+    gc.collect();
+    gc.assert_live(gc.get_handle(&cell));
+}
+
+#[test]
+#[should_panic(expected = "points into a free block")]
+fn pointer_into_free_block_is_caught() {
+    let mut gc = compacting();
+    let dangling = pointer_into_free_block(&mut gc);
+    gc.assert_live(dangling);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "points into a free block")]
+fn marking_pointer_into_free_block_panics() {
+    let mut gc = compacting();
+    let dangling = pointer_into_free_block(&mut gc);
+    gc.acquire_handle(dangling);
+    gc.collect();
+}
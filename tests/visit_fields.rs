@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+
+use ike_gc::{gc_ptr::Gc, GCAlloc, Trace, VTable, Visitor};
+
+#[derive(Trace)]
+struct Leaf {
+    value: usize,
+}
+
+/// Traced through a hand-written field traversal.
+struct Node {
+    left: Gc<Leaf>,
+    right: Option<Gc<Leaf>>,
+}
+
+impl Trace for Node {
+    fn visit_fields(&self, visitor: &mut dyn Visitor) {
+        visitor.visit(self.left.erase());
+        if let Some(right) = &self.right {
+            visitor.visit(right.erase());
+        }
+    }
+}
+
+thread_local! {
+    /// The fields seen by each traversal the collector ran, in order.
+    static TRAVERSALS: RefCell<Vec<Vec<*const ()>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Passes the fields on to the collector's visitor, recording them as they were before it
+/// visited them.
+struct Recording<'a>(&'a mut dyn Visitor, Vec<*const ()>);
+
+impl Visitor for Recording<'_> {
+    fn visit(&mut self, field: &Gc<()>) {
+        self.1.push(field.get());
+        self.0.visit(field);
+    }
+}
+
+fn recording_visit(ptr: *const u8, visitor: &mut dyn Visitor) {
+    let node = unsafe { &*(ptr as *const Node) };
+    let mut recording = Recording(visitor, vec![]);
+    node.visit_fields(&mut recording);
+    TRAVERSALS.with(|t| t.borrow_mut().push(recording.1));
+}
+
+/// The vtable of `Node`, recording the traversals of the collector.
+static RECORDING_VTABLE: VTable = VTable {
+    visit_cb: Some(recording_visit),
+    ..Node::VTABLE
+};
+
+fn traversals() -> Vec<Vec<*const ()>> {
+    TRAVERSALS.with(|t| t.take())
+}
+
+/// Records the fields it visits, and checks that they point into the from-space.
+struct VerifyVisitor<'a> {
+    gc: &'a GCAlloc,
+    visited: Vec<*const ()>,
+}
+
+impl Visitor for VerifyVisitor<'_> {
+    fn visit(&mut self, field: &Gc<()>) {
+        assert!(self.gc.in_young_gen(field.clone()));
+        self.visited.push(field.get());
+    }
+}
+
+fn verify(gc: &GCAlloc, node: Gc<Node>) -> Vec<*const ()> {
+    let mut visitor = VerifyVisitor {
+        gc,
+        visited: vec![],
+    };
+    gc.get(node).visit_fields(&mut visitor);
+    visitor.visited
+}
+
+#[test]
+fn mark_and_verify_visit_the_same_fields() {
+    let mut gc = GCAlloc::new(4096);
+    // Garbage in front of the objects, so they have to move.
+    gc.alloc(Leaf { value: 0 }).expect("Malloc failed");
+    let left = gc.alloc(Leaf { value: 1 }).expect("Malloc failed");
+    let right = gc.alloc(Leaf { value: 2 }).expect("Malloc failed");
+    let node = Node {
+        left: left.clone(),
+        right: Some(right.clone()),
+    };
+    let node = gc
+        .allocate_typed(&RECORDING_VTABLE, node)
+        .expect("Malloc failed");
+    let expected = vec![left.get() as *const (), right.get() as *const ()];
+    assert_eq!(verify(&gc, node.clone()), expected);
+
+    let node = gc.acquire_handle(node);
+    gc.collect();
+
+    // The collector marked, then rewrote, the same fields the verify visitor saw.
+    assert_eq!(traversals(), [expected.clone(), expected.clone()]);
+    let leaf_size = 16 + std::mem::size_of::<Leaf>().next_multiple_of(16);
+    let node_size = 16 + std::mem::size_of::<Node>().next_multiple_of(16);
+    assert_eq!(gc.metadata().currently_allocated, 2 * leaf_size + node_size);
+
+    // The heap verification visits the rewritten fields.
+    gc.verify();
+    let node = gc.get_handle(&node);
+    let visited = verify(&gc, node.clone());
+    assert_eq!(visited.len(), 2);
+    assert_ne!(visited, expected);
+    assert_eq!(traversals(), [visited]);
+    let node = gc.get(node);
+    assert_eq!(gc.get(node.left.clone()).value, 1);
+    assert_eq!(gc.get(node.right.clone().unwrap()).value, 2);
+}
@@ -0,0 +1,139 @@
+use ike_gc::{
+    fixtures::Cons,
+    gc_ptr::{Gc, Weak},
+    GCAlloc, SizeKind, VTable,
+};
+
+#[test]
+fn weak_handle_does_not_keep_object_alive() {
+    let mut gc = GCAlloc::new(4096);
+
+    let weak_only = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let strong = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let weak_only = gc.acquire_weak(weak_only);
+    let weak_strong = gc.acquire_weak(strong.clone());
+    let strong = gc.acquire_handle(strong);
+
+    gc.collect();
+
+    assert!(gc.upgrade(&weak_only).is_none());
+    let upgraded = gc.upgrade(&weak_strong).expect("Object should be alive");
+    assert_eq!(upgraded.get(), gc.get_handle(&strong).get());
+
+    gc.release_handle(strong);
+    gc.collect();
+    assert!(gc.upgrade(&weak_strong).is_none());
+    assert_eq!(gc.metadata().currently_allocated, 0);
+
+    gc.release_weak(weak_only);
+    gc.release_weak(weak_strong);
+}
+
+#[test]
+fn weak_root_handle_clears_after_strong_handle_is_released() {
+    let mut gc = GCAlloc::new(4096);
+
+    let cell = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let weak = gc.acquire_weak_handle(cell.clone());
+    let strong = gc.acquire_handle(cell);
+
+    gc.collect();
+    let upgraded = gc.upgrade(&weak).expect("Object should be alive");
+    assert!(upgraded.ptr_eq(&gc.get_handle(&strong)));
+
+    gc.release_handle(strong);
+    gc.collect();
+    assert!(gc.upgrade(&weak).is_none());
+
+    // A handle that isn't registered in a heap resolves to nothing rather than panicking.
+    let other = GCAlloc::new(4096);
+    assert!(other.upgrade(&weak).is_none());
+    gc.release_weak(weak);
+}
+
+/// An object with a strong and a weak pointer.
+struct Pair {
+    strong: Option<Gc<Cons>>,
+    weak: Weak<Cons>,
+}
+
+fn pair_mark(gc: &mut GCAlloc, ptr: *const u8) {
+    let pair = unsafe { &*(ptr as *const Pair) };
+    if let Some(strong) = &pair.strong {
+        gc.mark_accessible(strong.clone());
+    }
+}
+
+fn pair_rewrite(gc: &mut GCAlloc, ptr: *const u8) {
+    let pair = unsafe { &*(ptr as *const Pair) };
+    if let Some(strong) = &pair.strong {
+        gc.rewrite_ptr(strong);
+    }
+    gc.rewrite_weak(&pair.weak);
+}
+
+static PAIR_VTABLE: VTable = VTable {
+    size: SizeKind::of::<Pair>(),
+    align: std::mem::align_of::<Pair>(),
+    mark_cb: pair_mark,
+    rewrite_cb: pair_rewrite,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn weak_field_is_cleared_or_rewritten() {
+    let mut gc = GCAlloc::new(4096);
+
+    let target = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let dead = Pair {
+        strong: None,
+        weak: Weak::new(target.clone()),
+    };
+    let alive = Pair {
+        strong: Some(target.clone()),
+        weak: Weak::new(target),
+    };
+    let dead = gc
+        .allocate_typed(&PAIR_VTABLE, dead)
+        .expect("Malloc failed");
+    let alive = gc
+        .allocate_typed(&PAIR_VTABLE, alive)
+        .expect("Malloc failed");
+    let dead = gc.acquire_handle(dead);
+    let alive = gc.acquire_handle(alive);
+
+    gc.collect();
+
+    // Strongly referenced through `alive`, so both weak pointers follow the move.
+    let alive_pair = unsafe { &*gc.get_handle(&alive).get() };
+    let target = alive_pair.strong.clone().unwrap();
+    assert_eq!(alive_pair.weak.get().unwrap().get(), target.get());
+    let dead_pair = unsafe { &*gc.get_handle(&dead).get() };
+    assert_eq!(dead_pair.weak.get().unwrap().get(), target.get());
+
+    // Only weakly referenced from now on.
+    gc.release_handle(alive);
+    gc.collect();
+    let dead_pair = unsafe { &*gc.get_handle(&dead).get() };
+    assert!(dead_pair.weak.get().is_none());
+    gc.release_handle(dead);
+}
+
+#[test]
+fn weak_handle_to_pinned_object_survives() {
+    let mut gc = GCAlloc::new(4096);
+
+    let cons = Cons::alloc(&mut gc, None, None).expect("Malloc failed");
+    let weak = gc.acquire_weak(cons.clone());
+    let pin = gc.pin(cons.clone());
+
+    gc.collect();
+    let upgraded = gc.upgrade(&weak).expect("Pinned object should be alive");
+    assert!(upgraded.ptr_eq(&cons));
+
+    gc.unpin(pin);
+    gc.collect();
+    gc.collect();
+    assert!(gc.upgrade(&weak).is_none());
+    gc.release_weak(weak);
+}
@@ -0,0 +1,61 @@
+use ike_gc::{fixtures::GcTree, gc_ptr::Gc, GCAlloc, GCConfig};
+
+fn count(gc: &GCAlloc, tree: &Option<Gc<GcTree>>) -> usize {
+    match tree {
+        None => 0,
+        Some(node) => {
+            let node = gc.get(node.clone());
+            1 + count(gc, &node.left) + count(gc, &node.right)
+        }
+    }
+}
+
+fn collect_broad_tree(limit: Option<usize>) -> usize {
+    let mut gc = GCAlloc::with_config(
+        1 << 20,
+        GCConfig {
+            work_list_limit: limit,
+            // A Cheney scan doesn't use the work list.
+            separate_passes: true,
+            ..Default::default()
+        },
+    );
+    GcTree::alloc_complete(&mut gc, 8, 0);
+    let tree = GcTree::alloc_complete(&mut gc, 12, 0).unwrap();
+    let handle = gc.acquire_handle(tree);
+    gc.collect();
+    gc.verify();
+
+    let tree = gc.get_handle(&handle);
+    assert_eq!(count(&gc, &Some(tree)), (1 << 12) - 1);
+    gc.metadata().peak_work_list
+}
+
+#[test]
+fn bounded_work_list_marks_everything() {
+    let unbounded = collect_broad_tree(None);
+    let bounded = collect_broad_tree(Some(16));
+    assert!(unbounded > 1000, "{}", unbounded);
+    assert!(bounded <= 16, "{}", bounded);
+}
+
+#[test]
+fn work_list_is_reused_across_collections() {
+    let config = GCConfig {
+        separate_passes: true,
+        ..Default::default()
+    };
+    let mut gc = GCAlloc::with_config(1 << 20, config);
+    let tree = GcTree::alloc_complete(&mut gc, 12, 0).unwrap();
+    let handle = gc.acquire_handle(tree);
+
+    gc.collect();
+    let first = gc.metadata();
+    assert!(first.work_list_capacity >= first.peak_work_list);
+    gc.collect();
+    let second = gc.metadata();
+    assert_eq!(second.peak_work_list, first.peak_work_list);
+    assert_eq!(second.work_list_capacity, first.work_list_capacity);
+
+    gc.release_handle(handle);
+}
@@ -0,0 +1,51 @@
+use ike_gc::{GCAlloc, SizeKind, Trace, VTable};
+
+static MARKER_VTABLE: VTable = VTable {
+    size: SizeKind::fixed(0),
+    align: 1,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn zero_size_sentinels_are_distinct_and_survive() {
+    let mut gc = GCAlloc::new(4096);
+    let mut handles = vec![];
+    for i in 0..4 {
+        // Garbage between the sentinels, so they have to move.
+        gc.alloc(()).expect("Malloc failed");
+        let sentinel = if i % 2 == 0 {
+            gc.alloc(()).expect("Malloc failed")
+        } else {
+            gc.allocate_typed(&MARKER_VTABLE, ())
+                .expect("Malloc failed")
+        };
+        handles.push(gc.acquire_handle(sentinel));
+    }
+    let cell = gc.metadata().currently_allocated / 8;
+
+    let distinct = |gc: &GCAlloc| {
+        let mut addrs: Vec<_> = handles.iter().map(|h| gc.get_handle(h).get()).collect();
+        addrs.sort();
+        addrs.dedup();
+        addrs.len()
+    };
+    assert_eq!(distinct(&gc), handles.len());
+
+    let before = gc.get_handle(&handles[0]).get();
+    gc.collect();
+    assert_ne!(gc.get_handle(&handles[0]).get(), before);
+    assert_eq!(distinct(&gc), handles.len());
+    assert_eq!(gc.metadata().currently_allocated, handles.len() * cell);
+    gc.verify();
+
+    for handle in handles {
+        gc.release_handle(handle);
+    }
+    gc.collect();
+    assert_eq!(gc.metadata().currently_allocated, 0);
+}
+
+#[test]
+fn zero_size_vtable_from_trace() {
+    assert!(matches!(<() as Trace>::VTABLE.size, SizeKind::Fixed(0)));
+}
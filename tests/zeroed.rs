@@ -0,0 +1,24 @@
+use ike_gc::{GCAlloc, SizeKind, VTable};
+
+const SIZE: usize = 64;
+
+static RAW_VTABLE: VTable = VTable {
+    size: SizeKind::fixed(SIZE),
+    align: 16,
+    ..VTable::DEFAULT
+};
+
+#[test]
+fn reused_space_is_zeroed() {
+    let mut gc = GCAlloc::new(4096);
+    let first = gc.allocate(&RAW_VTABLE, SIZE).expect("Malloc failed");
+    unsafe { std::ptr::write_bytes(first.get() as *mut u8, 0xab, SIZE) };
+
+    // Two collections bring the allocation back to the half the dead object is in.
+    gc.collect();
+    gc.collect();
+    let second = gc.allocate(&RAW_VTABLE, SIZE).expect("Malloc failed");
+    assert_eq!(second.get(), first.get());
+    let payload = unsafe { std::slice::from_raw_parts(second.get(), SIZE) };
+    assert!(payload.iter().all(|&b| b == 0));
+}